@@ -1,4 +1,8 @@
 //! How to append a postscript to the help message generated.
+//!
+//! `before_long_help` and `after_long_help` only show up in `--help`,
+//! `before_help` and `after_help` in `-h` (and in `--help` when there is
+//! no long variant).
 
 use clap::Clap;
 
@@ -6,7 +10,13 @@ use clap::Clap;
 ///
 /// Sometimes they even work.
 #[derive(Clap, Debug)]
-#[clap(after_help = "Beware `-d`, dragons be here")]
+#[clap(
+    after_help = "Beware `-d`, dragons be here",
+    before_long_help = "This program is best run on a sunny day.",
+    after_long_help = "Beware `-d`, dragons be here.\n\n\
+                       Dragons are known to set fire to anything nearby, \
+                       the operator included."
+)]
 struct Opt {
     /// Release the dragon.
     #[clap(short)]
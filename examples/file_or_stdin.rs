@@ -0,0 +1,55 @@
+//! A field type reading from a file, or from stdin when given `-`.
+//!
+//! `clap_derive` is a procedural macro crate and can only export derive
+//! macros, so it cannot ship this type itself. Copy it into your project
+//! (or into a shared crate) and use it as a field type.
+
+use clap::Clap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+enum FileOrStdin {
+    Stdin,
+    File(PathBuf),
+}
+
+impl FileOrStdin {
+    /// `-` is stdin, anything else a path.
+    fn from_os_str(s: &OsStr) -> Self {
+        if s == "-" {
+            FileOrStdin::Stdin
+        } else {
+            FileOrStdin::File(PathBuf::from(s))
+        }
+    }
+
+    fn reader(&self) -> io::Result<Box<dyn BufRead>> {
+        Ok(match self {
+            FileOrStdin::Stdin => Box::new(BufReader::new(io::stdin())),
+            FileOrStdin::File(path) => Box::new(BufReader::new(File::open(path)?)),
+        })
+    }
+
+    fn read_to_string(&self) -> io::Result<String> {
+        let mut content = String::new();
+        self.reader()?.read_to_string(&mut content)?;
+        Ok(content)
+    }
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    /// File to count the lines of, `-` for stdin
+    #[clap(parse(from_os_str = FileOrStdin::from_os_str), default_value = "-")]
+    input: FileOrStdin,
+}
+
+fn main() -> io::Result<()> {
+    let opt = Opt::parse();
+    let content = opt.input.read_to_string()?;
+    println!("{}", content.lines().count());
+    Ok(())
+}
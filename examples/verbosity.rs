@@ -0,0 +1,56 @@
+//! A reusable `-v`/`-q` verbosity helper meant to be flattened.
+//!
+//! `clap_derive` is a procedural macro crate and can only export derive
+//! macros, so it cannot ship this struct itself. Copy it into your project
+//! (or into a shared crate) and `#[clap(flatten)]` it wherever needed.
+
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Verbosity {
+    /// More output per occurrence
+    #[clap(short, long, parse(from_occurrences), conflicts_with = "quiet")]
+    verbose: i8,
+
+    /// Less output per occurrence
+    #[clap(short, long, parse(from_occurrences))]
+    quiet: i8,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Verbosity {
+    /// The log level selected on the command line, `None` meaning that
+    /// logging is disabled. Defaults to `Level::Warn`.
+    fn log_level(&self) -> Option<Level> {
+        match 1 + self.verbose - self.quiet {
+            level if level < 0 => None,
+            0 => Some(Level::Error),
+            1 => Some(Level::Warn),
+            2 => Some(Level::Info),
+            3 => Some(Level::Debug),
+            _ => Some(Level::Trace),
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(flatten)]
+    verbosity: Verbosity,
+
+    /// File to process
+    file: String,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}: {:?}", opt.file, opt.verbosity.log_level());
+}
@@ -8,87 +8,128 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 use proc_macro2;
-// use quote;
-use syn;
-// use syn::punctuated;
-// use syn::token;
+use proc_macro_error::{abort, abort_call_site};
+use syn::{self, punctuated, spanned::Spanned, token};
 
-pub fn derive_arg_enum(_ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    unimplemented!()
+use super::{spanned::Sp, Attrs, CasingStyle, Name, DEFAULT_CASING, DEFAULT_ENV_CASING};
 
-    // let from_str_block = impl_from_str(ast)?;
-    // let variants_block = impl_variants(ast)?;
-
-    // quote! {
-    //     #from_str_block
-    //     #variants_block
-    // }
-}
+pub fn derive_arg_enum(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let variants = match ast.data {
+        syn::Data::Enum(ref data) => &data.variants,
+        _ => abort_call_site!("`ArgEnum` only supports enums"),
+    };
+    if variants.is_empty() {
+        abort_call_site!("`ArgEnum` needs at least one variant");
+    }
 
-/*
-fn impl_from_str(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    let ident = &ast.ident;
-    let is_case_sensitive = ast.attrs.iter().any(|v| v.name() == "case_sensitive");
-    let variants = variants(ast)?;
+    let attrs_with_casing = |casing| {
+        Attrs::from_struct(
+            proc_macro2::Span::call_site(),
+            &ast.attrs,
+            Name::Derived(name.clone()),
+            Sp::call_site(casing),
+            Sp::call_site(DEFAULT_ENV_CASING),
+        )
+    };
+    // the values of a case-sensitive enum are its variants as written,
+    // unless it has a `rename_all`
+    let attrs = attrs_with_casing(DEFAULT_CASING);
+    let attrs = if attrs.case_sensitive().is_some() {
+        attrs_with_casing(CasingStyle::Verbatim)
+    } else {
+        attrs
+    };
+    let values = variant_values(variants, &attrs);
+    let variant_names: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
+    let count = values.len();
+    let case_sensitive = attrs.case_sensitive().is_some();
+    let matches = if case_sensitive {
+        quote!( #( #values => Ok(#name::#variant_names), )* )
+    } else {
+        quote!( #( _ if value.eq_ignore_ascii_case(#values) => Ok(#name::#variant_names), )* )
+    };
 
-    let strings = variants
-        .iter()
-        .map(|ref variant| String::from(variant.ident.as_ref()))
-        .collect::<Vec<_>>();
+    let from_str = attrs.impl_from_str().map(|_| {
+        quote! {
+            impl ::std::str::FromStr for #name {
+                type Err = String;
 
-    // All of these need to be iterators.
-    let ident_slice = [ident.clone()];
-    let idents = ident_slice.iter().cycle();
+                fn from_str(value: &str) -> ::std::result::Result<Self, Self::Err> {
+                    Self::from_value(value)
+                }
+            }
+        }
+    });
+    let display = attrs.impl_display().map(|_| {
+        quote! {
+            impl ::std::fmt::Display for #name {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    f.write_str(self.value())
+                }
+            }
+        }
+    });
 
-    let for_error_message = strings.clone();
+    quote! {
+        #[allow(dead_code)]
+        impl #name {
+            /// The values standing for the variants on the command line.
+            pub const VARIANTS: &'static [&'static str] = &[ #( #values ),* ];
 
-    let condition_function_slice = [match is_case_sensitive {
-        true => quote! { str::eq },
-        false => quote! { ::std::ascii::AsciiExt::eq_ignore_ascii_case },
-    }];
-    let condition_function = condition_function_slice.iter().cycle();
+            /// Whether the values are matched in a case-sensitive way, as
+            /// asked with `case_sensitive`.
+            pub const CASE_SENSITIVE: bool = #case_sensitive;
 
-    Ok(quote! {
-        impl ::std::str::FromStr for #ident {
-            type Err = String;
+            pub fn variants() -> [&'static str; #count] {
+                [ #( #values ),* ]
+            }
 
-            fn from_str(input: &str) -> ::std::result::Result<Self, Self::Err> {
-                match input {
-                    #(val if #condition_function(val, #strings) => Ok(#idents::#variants),)*
-                    _ => Err({
-                        let v = #for_error_message;
-                        format!("valid values: {}",
-                            v.join(" ,"))
-                    }),
+            /// The variant `value` stands for, whatever its case unless the
+            /// enum is `case_sensitive`.
+            pub fn from_value(value: &str) -> ::std::result::Result<Self, String> {
+                match value {
+                    #matches
+                    _ => Err(format!("valid values: {}", Self::VARIANTS.join(", "))),
                 }
             }
-        }
-    })
-}
 
-fn impl_variants(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    let ident = &ast.ident;
-    let variants = variants(ast)?
-        .iter()
-        .map(|ref variant| String::from(variant.ident.as_ref()))
-        .collect::<Vec<_>>();
-    let length = variants.len();
-
-    Ok(quote! {
-        impl #ident {
-            fn variants() -> [&'static str; #length] {
-                #variants
+            /// The value standing for this variant.
+            pub fn value(&self) -> &'static str {
+                match self {
+                    #( #name::#variant_names => #values, )*
+                }
             }
         }
-    })
-}
 
-fn variants(ast: &syn::DeriveInput) -> &punctuated::Punctuated<syn::Variant, token::Comma> {
-    use syn::Data::*;
+        #from_str
 
-    match ast.data {
-        Enum(ref data) => data.variants,
-        _ => panic!("Only enums are supported for deriving the ArgEnum trait"),
+        #display
     }
 }
-*/
+
+/// The value of each variant: its name, cased with the `rename_all` of the
+/// enum, or left as is when the enum is `case_sensitive` without one, unless
+/// it is given one with `name`.
+fn variant_values(
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> Vec<syn::LitStr> {
+    variants
+        .iter()
+        .map(|variant| {
+            match variant.fields {
+                syn::Fields::Unit => (),
+                _ => abort!(variant.span(), "`ArgEnum` only supports unit variants"),
+            }
+            Attrs::from_struct(
+                variant.span(),
+                &variant.attrs,
+                Name::Derived(variant.ident.clone()),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+            )
+            .cased_name()
+        })
+        .collect()
+}
@@ -0,0 +1,137 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fields holding the command line itself rather than an argument:
+//! `bin_name`, the name of the binary as invoked, and `raw_argv`, the whole
+//! command line before argument files are expanded.
+//!
+//! The matches don't keep the command line, so these fields are left empty
+//! by `from_argmatches` and filled by the parse functions afterwards.
+
+use proc_macro2;
+use proc_macro_error::abort;
+use syn::{self, spanned::Spanned};
+
+use super::{plugin, sub_type, Attrs, Kind, Ty, VariantAttrs};
+
+/// Generate the `__clap_fill_argv()` function of a struct, filling its
+/// fields, flattened ones and subcommands included.
+pub fn gen_fill_argv_fn_for_struct(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let fills = gen_fills(fields, |name| quote!(self.#name));
+
+    quote! {
+        /// Fill the fields holding the command line `args`.
+        pub fn __clap_fill_argv(&mut self, args: &[::std::ffi::OsString]) {
+            #( #fills )*
+        }
+    }
+}
+
+/// Generate the `__clap_fill_argv()` function of an enum, filling the
+/// fields of the variant it holds.
+pub fn gen_fill_argv_fn_for_enum(
+    name: &syn::Ident,
+    variants: &[VariantAttrs],
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    let arms = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .filter_map(|(variant, _, fields)| {
+            let variant_name = &variant.ident;
+            match (&variant.fields, fields) {
+                (Named(_), Some(fields)) => {
+                    let fills = gen_fills(fields, |name| quote!((*#name)));
+                    if fills.is_empty() {
+                        return None;
+                    }
+                    let names = fields.iter().map(|(field, _)| &field.ident);
+                    Some(quote! {
+                        #name::#variant_name { #( #names ),* } => {
+                            #( #fills )*
+                        }
+                    })
+                }
+                (Unnamed(fields), _) if fields.unnamed.len() == 1 => Some(quote! {
+                    #name::#variant_name(inner) => inner.__clap_fill_argv(args),
+                }),
+                _ => None,
+            }
+        });
+
+    quote! {
+        /// Fill the fields of the variant holding the command line `args`.
+        pub fn __clap_fill_argv(&mut self, args: &[::std::ffi::OsString]) {
+            match self {
+                #( #arms )*
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Generate the code filling `fields`, found at the places given by
+/// `place` from their names.
+fn gen_fills(
+    fields: &[(&syn::Field, Attrs)],
+    place: impl Fn(&syn::Ident) -> proc_macro2::TokenStream,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            let place = place(field.ident.as_ref().unwrap());
+            match &*attrs.kind() {
+                Kind::Subcommand(ty) => match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(_)) => Some(quote! {
+                        if let Some(subcommand) = &mut #place {
+                            subcommand.__clap_fill_argv(args);
+                        }
+                    }),
+                    _ => Some(quote!( #place.__clap_fill_argv(args); )),
+                },
+                Kind::FlattenStruct => Some(quote!( #place.__clap_fill_argv(args); )),
+                Kind::Skip(None) if attrs.raw_argv().is_some() => {
+                    let raw_argv = attrs.raw_argv().unwrap();
+                    Some(quote_spanned!(raw_argv.span()=> #place = args.to_vec().into();))
+                }
+                Kind::Skip(None) => {
+                    let bin_name = attrs.bin_name()?;
+                    let value = match type_name(&field.ty) {
+                        Some(name) if name == "String" => quote! {
+                            args.first()
+                                .map(|arg| arg.to_string_lossy().into_owned())
+                                .unwrap_or_default()
+                        },
+                        Some(name) if name == "OsString" => {
+                            quote!( args.first().cloned().unwrap_or_default() )
+                        }
+                        _ => abort!(
+                            field.ty.span(),
+                            "`{}` needs a `String` or `OsString` field", bin_name
+                        ),
+                    };
+                    Some(quote_spanned!(bin_name.span()=> #place = #value;))
+                }
+                Kind::Skip(Some(_)) | Kind::Arg(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// The name of the type `ty`, without its path.
+fn type_name(ty: &syn::Type) -> Option<&syn::Ident> {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => {
+            path.path.segments.last().map(|segment| &segment.ident)
+        }
+        _ => None,
+    }
+}
@@ -12,7 +12,11 @@
 // commit#ea76fa1b1b273e65e3b0b1046643715b49bec51f which is licensed under the
 // MIT/Apache 2.0 license.
 
-use super::{parse::*, spanned::Sp, ty::Ty};
+use super::{
+    parse::*,
+    spanned::Sp,
+    ty::{fixed_values, is_integer_ty, is_log_filter, log_levels, sub_type, Ty},
+};
 
 use std::env;
 
@@ -20,11 +24,25 @@ use heck::{CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::{self, Span, TokenStream};
 use proc_macro_error::abort;
 use quote::{quote, quote_spanned, ToTokens};
-use syn::{self, ext::IdentExt, spanned::Spanned, Attribute, Expr, Ident, LitStr, MetaNameValue};
+use syn::{
+    self, ext::IdentExt, punctuated, spanned::Spanned, token, Attribute, Expr, Ident, LitStr,
+    MetaNameValue,
+};
 
 /// Default casing style for generated arguments.
 pub const DEFAULT_CASING: CasingStyle = CasingStyle::Kebab;
 
+/// Default casing style for environment variables
+pub const DEFAULT_ENV_CASING: CasingStyle = CasingStyle::ScreamingSnake;
+
+/// A variant of an enum with its `Attrs` and, when it has named fields,
+/// the ones of its fields, see `Attrs::from_variants()`.
+pub type VariantAttrs<'a> = (
+    &'a syn::Variant,
+    Attrs,
+    Option<Vec<(&'a syn::Field, Attrs)>>,
+);
+
 #[derive(Clone)]
 pub enum Kind {
     Arg(Sp<Ty>),
@@ -39,6 +57,15 @@ pub struct Method {
     args: proc_macro2::TokenStream,
 }
 
+/// The help or about message given by doc attributes some of which are
+/// expressions, like `#[doc = include_str!("help.md")]`, joined at runtime.
+#[derive(Clone)]
+pub struct DocExprs {
+    pub parts: Vec<proc_macro2::TokenStream>,
+    /// The `[e.g. ...]` suffix of the `example` values, if any.
+    pub suffix: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct Parser {
     pub kind: Sp<ParserKind>,
@@ -70,6 +97,10 @@ pub enum CasingStyle {
     Snake,
     /// Use the original attribute name defined in the code.
     Verbatim,
+    /// Keep all letters lowercase and remove word boundaries.
+    Lower,
+    /// Keep all letters uppercase and remove word boundaries.
+    Upper,
 }
 
 #[derive(Clone)]
@@ -82,13 +113,100 @@ pub enum Name {
 pub struct Attrs {
     name: Name,
     casing: Sp<CasingStyle>,
+    env_casing: Sp<CasingStyle>,
+    value_casing: Option<Sp<CasingStyle>>,
+    legacy_casing: Option<Sp<CasingStyle>>,
+    /// Whether `casing` was given with `rename_all` rather than inherited.
+    explicit_casing: bool,
+    propagate_rename_all: Option<Ident>,
+    /// Whether the long flag is derived from `name` rather than given.
+    derived_long: bool,
+    /// `name` translated by `casing`, once they are both settled.
+    cased_name: Option<LitStr>,
+    /// The id of the argument, when it differs from `cased_name`.
+    id: Option<LitStr>,
     methods: Vec<Method>,
     parser: Sp<Parser>,
     author: Option<Method>,
     about: Option<Method>,
     version: Option<Method>,
     no_version: Option<syn::Ident>,
+    no_auto_version: Option<syn::Ident>,
+    config_file: Option<Sp<String>>,
+    partial: Option<syn::Ident>,
+    secret: Option<syn::Ident>,
+    warn_legacy_env: Option<syn::Ident>,
+    explicit_bool: Option<syn::Ident>,
+    canonicalize: Option<syn::Ident>,
+    expand_tilde: Option<syn::Ident>,
+    trim: Option<syn::Ident>,
+    lowercase: Option<syn::Ident>,
+    value_names: Option<(syn::Ident, Vec<LitStr>)>,
+    complete_with: Option<(syn::Ident, syn::Expr)>,
+    has_fixed_values: bool,
+    plugin: Option<syn::Ident>,
+    registered: Option<syn::Ident>,
+    dynamic_completion: Option<syn::Ident>,
+    docs: Option<syn::Ident>,
+    parse_from_str: Option<syn::Ident>,
+    argfile: Option<syn::Ident>,
+    arbitrary: Option<syn::Ident>,
+    prompt: Option<syn::Ident>,
+    zeroize: Option<syn::Ident>,
+    cached_app: Option<syn::Ident>,
+    case_insensitive_longs: Option<syn::Ident>,
+    show_defaults: Option<syn::Ident>,
+    show_env: Option<syn::Ident>,
+    source_order: Option<syn::Ident>,
+    arg_enum: Option<syn::Ident>,
+    verbosity: Option<syn::Ident>,
+    parse_output: Option<syn::Ident>,
+    untagged: Option<syn::Ident>,
+    hide_short_help: Option<syn::Ident>,
+    hide_long_help: Option<syn::Ident>,
+    impl_from_str: Option<syn::Ident>,
+    impl_display: Option<syn::Ident>,
+    case_sensitive: Option<syn::Ident>,
+    lazy: Option<syn::Ident>,
+    env_fallbacks: Vec<LitStr>,
+    color: Option<syn::Ident>,
+    color_arg: Option<syn::Ident>,
+    dotenv: Option<Sp<Option<String>>>,
+    arg_ids: Option<Sp<Option<String>>>,
+    env_prefix: Option<Sp<String>>,
+    long_prefix: Option<Sp<String>>,
+    prefix: Option<Sp<String>>,
+    help_key: Option<LitStr>,
+    help_template: Option<LitStr>,
+    help_message: Option<LitStr>,
+    version_message: Option<LitStr>,
+    localizer: Option<(syn::Ident, syn::Expr)>,
+    mut_arg: Option<(syn::Ident, syn::Expr)>,
+    mut_quiet: Option<(syn::Ident, syn::Expr)>,
+    doc_exprs: Option<DocExprs>,
+    possible_values_fn: Option<(syn::Ident, syn::Expr)>,
+    /// The function of the parent type giving the values of
+    /// `possible_values_fn`.
+    possible_values_fn_name: Option<Ident>,
+    action: Option<(syn::Ident, syn::Ident)>,
+    occurrences_of: Option<(syn::Ident, LitStr)>,
+    arg_matches: Option<Ident>,
+    bin_name: Option<Ident>,
+    raw_argv: Option<Ident>,
+    examples: Vec<(syn::Ident, LitStr)>,
+    flatten_relations: Vec<(syn::Ident, LitStr)>,
+    requires_all: Vec<(syn::Ident, Vec<LitStr>)>,
+    groups: Vec<(syn::Ident, GroupSpec)>,
+    required_unless: Vec<(syn::Ident, Vec<LitStr>)>,
+    default_value_ifs: Vec<(syn::Ident, Vec<(LitStr, Option<LitStr>, LitStr)>)>,
+    relations_if: Vec<(syn::Ident, LitStr, LitStr)>,
+    levels: Option<(syn::Ident, Vec<Expr>)>,
+    hide_possible_values: Option<syn::Ident>,
+    max_occurrences: Option<(syn::Ident, Expr)>,
+    error_exit_code: Option<(syn::Ident, Expr)>,
+    usage_exit_code: Option<(syn::Ident, Expr)>,
     has_custom_parser: bool,
+    default_occurrences: bool,
     kind: Sp<Kind>,
 }
 
@@ -137,7 +255,14 @@ impl ToTokens for Method {
         let Method { ref name, ref args } = self;
 
         let tokens = if name == "short" {
-            quote!( .#name(#args.chars().nth(0).unwrap()) )
+            match syn::parse2::<LitStr>(args.clone()) {
+                Ok(lit) => {
+                    let c = lit.value().chars().next().unwrap();
+                    let c = syn::LitChar::new(c, lit.span());
+                    quote!( .#name(#c) )
+                }
+                Err(_) => quote!( .#name(#args.chars().nth(0).unwrap()) ),
+            }
         } else {
             quote!( .#name(#args) )
         };
@@ -153,6 +278,25 @@ impl Parser {
         Sp::new(Parser { kind, func }, span)
     }
 
+    /// The `from_occurrences` parser picking the value of `levels` at the
+    /// number of occurrences, the last one being used past the end.
+    fn from_levels(levels_ident: syn::Ident, levels: Vec<Expr>) -> Sp<Self> {
+        let span = levels_ident.span();
+        let (last, levels) = levels.split_last().unwrap();
+        let counts = (0..levels.len() as u64).map(proc_macro2::Literal::u64_unsuffixed);
+        let func = quote_spanned! { span=>
+            {
+                |count: u64| match count {
+                    #( #counts => #levels, )*
+                    _ => #last,
+                }
+            }
+        };
+
+        let kind = Sp::new(ParserKind::FromOccurrences, span);
+        Sp::new(Parser { kind, func }, span)
+    }
+
     fn from_spec(parse_ident: syn::Ident, spec: ParserSpec) -> Sp<Self> {
         use self::ParserKind::*;
 
@@ -176,7 +320,7 @@ impl Parser {
                     spec.kind.span(),
                     "you must set parser for `try_from_os_str` explicitly"
                 ),
-                FromOccurrences => quote_spanned!(spec.kind.span()=> { |v| v as _ }),
+                FromOccurrences => counted_occurrences(spec.kind.span()),
                 FromFlag => quote_spanned!(spec.kind.span()=> ::std::convert::From::from),
             },
 
@@ -192,7 +336,124 @@ impl Parser {
     }
 }
 
+/// The parser of the `canonicalize` and `expand_tilde` arguments, building a
+/// `PathBuf` and converting it to the type of the field.
+fn path_parser(span: Span, expand_tilde: bool, canonicalize: bool) -> proc_macro2::TokenStream {
+    let expand_tilde = if expand_tilde {
+        quote_spanned! { span=>
+            let path = match path.strip_prefix("~") {
+                Ok(rest) => match ::std::env::var_os("HOME")
+                    .or_else(|| ::std::env::var_os("USERPROFILE"))
+                {
+                    Some(home) => ::std::path::Path::new(&home).join(rest),
+                    None => {
+                        return Err(::std::ffi::OsString::from(
+                            "can't expand `~`, the home directory is unknown",
+                        ))
+                    }
+                },
+                Err(_) => path,
+            };
+        }
+    } else {
+        quote!()
+    };
+    let canonicalize = if canonicalize {
+        quote_spanned! { span=>
+            let path = path.canonicalize().map_err(|e| {
+                ::std::ffi::OsString::from(format!("invalid path `{}`: {}", path.display(), e))
+            })?;
+        }
+    } else {
+        quote!()
+    };
+
+    quote_spanned! { span=>
+        {
+            |s: &::std::ffi::OsStr| -> ::std::result::Result<_, ::std::ffi::OsString> {
+                let path = ::std::path::PathBuf::from(s);
+                #expand_tilde
+                #canonicalize
+                Ok(::std::convert::From::from(path))
+            }
+        }
+    }
+}
+
+/// Wrap the `&str` parser `func`, trimming and lowercasing the values first.
+fn normalized(func: &TokenStream, trim: bool, lowercase: bool) -> TokenStream {
+    let trim = if trim {
+        quote!( let s = s.trim(); )
+    } else {
+        quote!()
+    };
+    let lowercase = if lowercase {
+        quote!( let s = s.to_lowercase(); )
+    } else {
+        quote!()
+    };
+
+    quote_spanned! { func.span()=>
+        {
+            |s: &str| {
+                #trim
+                #lowercase
+                (#func)(&*s)
+            }
+        }
+    }
+}
+
+/// The default `from_occurrences` parser, casting the count to the field
+/// type.
+fn counted_occurrences(span: Span) -> proc_macro2::TokenStream {
+    quote_spanned!(span=> { |count: u64| count as _ })
+}
+
+/// The default `from_occurrences` parser of an integer field, saturating at
+/// the largest value of the type instead of wrapping around.
+fn saturating_occurrences(span: Span, ty: &syn::Type) -> proc_macro2::TokenStream {
+    quote_spanned! { span=>
+        {
+            |count: u64| {
+                if count > <#ty>::max_value() as u64 {
+                    <#ty>::max_value()
+                } else {
+                    count as #ty
+                }
+            }
+        }
+    }
+}
+
 impl CasingStyle {
+    const ALL: [CasingStyle; 8] = [
+        CasingStyle::Camel,
+        CasingStyle::Kebab,
+        CasingStyle::Pascal,
+        CasingStyle::ScreamingSnake,
+        CasingStyle::Snake,
+        CasingStyle::Verbatim,
+        CasingStyle::Lower,
+        CasingStyle::Upper,
+    ];
+
+    /// The name standing for the casing in runtime prefixes.
+    fn key(self) -> &'static str {
+        use self::CasingStyle::*;
+
+        match self {
+            Camel => "camel",
+            Kebab => "kebab",
+            Pascal => "pascal",
+            ScreamingSnake => "screaming_snake",
+            Snake => "snake",
+            Verbatim => "verbatim",
+            Lower => "lower",
+            Upper => "upper",
+        }
+    }
+
     fn from_lit(name: syn::LitStr) -> Sp<Self> {
         use self::CasingStyle::*;
 
@@ -206,6 +467,8 @@ impl CasingStyle {
             "screamingsnake" | "screamingsnakecase" => cs(ScreamingSnake),
             "snake" | "snakecase" => cs(Snake),
             "verbatim" | "verbatimcase" => cs(Verbatim),
+            "lower" | "lowercase" => cs(Lower),
+            "upper" | "uppercase" => cs(Upper),
             s => abort!(name.span(), "unsupported casing: `{}`", s),
         }
     }
@@ -226,6 +489,8 @@ impl Name {
                     ScreamingSnake => s.to_shouty_snake_case(),
                     Snake => s.to_snake_case(),
                     Verbatim => s,
+                    Lower => s.to_snake_case().replace('_', ""),
+                    Upper => s.to_snake_case().replace('_', "").to_uppercase(),
                 };
                 syn::LitStr::new(&s, ident.span())
             }
@@ -234,18 +499,104 @@ impl Name {
 }
 
 impl Attrs {
-    fn new(default_span: Span, name: Name, casing: Sp<CasingStyle>) -> Self {
+    fn new(
+        default_span: Span,
+        name: Name,
+        casing: Sp<CasingStyle>,
+        env_casing: Sp<CasingStyle>,
+    ) -> Self {
         Self {
             name,
             casing,
+            env_casing,
+            value_casing: None,
+            legacy_casing: None,
+            explicit_casing: false,
+            propagate_rename_all: None,
+            derived_long: false,
+            cased_name: None,
+            id: None,
             methods: vec![],
             parser: Parser::default_spanned(default_span),
             about: None,
             author: None,
             version: None,
             no_version: None,
+            no_auto_version: None,
+            config_file: None,
+            partial: None,
+            secret: None,
+            warn_legacy_env: None,
+            explicit_bool: None,
+            canonicalize: None,
+            expand_tilde: None,
+            trim: None,
+            lowercase: None,
+            value_names: None,
+            complete_with: None,
+            has_fixed_values: false,
+            plugin: None,
+            registered: None,
+            dynamic_completion: None,
+            docs: None,
+            parse_from_str: None,
+            argfile: None,
+            arbitrary: None,
+            prompt: None,
+            zeroize: None,
+            cached_app: None,
+            case_insensitive_longs: None,
+            show_defaults: None,
+            show_env: None,
+            source_order: None,
+            arg_enum: None,
+            verbosity: None,
+            parse_output: None,
+            untagged: None,
+            hide_short_help: None,
+            hide_long_help: None,
+            impl_from_str: None,
+            impl_display: None,
+            case_sensitive: None,
+            lazy: None,
+            env_fallbacks: Vec::new(),
+            color: None,
+            color_arg: None,
+            dotenv: None,
+            arg_ids: None,
+            env_prefix: None,
+            long_prefix: None,
+            prefix: None,
+            help_key: None,
+            help_template: None,
+            help_message: None,
+            version_message: None,
+            localizer: None,
+            mut_arg: None,
+            mut_quiet: None,
+            doc_exprs: None,
+            possible_values_fn: None,
+            possible_values_fn_name: None,
+            action: None,
+            occurrences_of: None,
+            arg_matches: None,
+            bin_name: None,
+            raw_argv: None,
+            examples: Vec::new(),
+            flatten_relations: Vec::new(),
+            requires_all: Vec::new(),
+            groups: Vec::new(),
+            required_unless: Vec::new(),
+            default_value_ifs: Vec::new(),
+            relations_if: Vec::new(),
+            levels: None,
+            hide_possible_values: None,
+            max_occurrences: None,
+            error_exit_code: None,
+            usage_exit_code: None,
 
             has_custom_parser: false,
+            default_occurrences: false,
             kind: Sp::new(Kind::Arg(Sp::new(Ty::Other, default_span)), default_span),
         }
     }
@@ -256,6 +607,9 @@ impl Attrs {
             ("name", _) => {
                 self.name = Name::Assigned(arg.as_lit());
             }
+            ("id", _) => {
+                self.id = Some(arg.as_lit());
+            }
             _ => self
                 .methods
                 .push(Method::new(name.as_ident(), quote!(#arg))),
@@ -268,6 +622,9 @@ impl Attrs {
         for attr in parse_clap_attributes(attrs) {
             match attr {
                 Short(ident) | Long(ident) => {
+                    if ident == "long" {
+                        self.derived_long = true;
+                    }
                     self.push_str_method(
                         ident.into(),
                         self.name.clone().translate(*self.casing).into(),
@@ -292,6 +649,172 @@ impl Attrs {
 
                 NoVersion(ident) => self.no_version = Some(ident),
 
+                NoAutoVersion(ident) => self.no_auto_version = Some(ident),
+
+                Partial(ident) => {
+                    if !cfg!(feature = "serde") {
+                        abort!(ident.span(),
+                            "`partial` requires the `serde` feature";
+                            help = "enable the `serde` feature of `clap_derive`";
+                        );
+                    }
+                    self.partial = Some(ident);
+                }
+
+                Secret(ident) => self.secret = Some(ident),
+
+                WarnLegacyEnv(ident) => self.warn_legacy_env = Some(ident),
+
+                ExplicitBool(ident) => self.explicit_bool = Some(ident),
+
+                Canonicalize(ident) => self.canonicalize = Some(ident),
+
+                ExpandTilde(ident) => self.expand_tilde = Some(ident),
+
+                Trim(ident) => self.trim = Some(ident),
+
+                Lowercase(ident) => self.lowercase = Some(ident),
+
+                Plugin(ident) => self.plugin = Some(ident),
+
+                DynamicCompletion(ident) => self.dynamic_completion = Some(ident),
+
+                Docs(ident) => self.docs = Some(ident),
+
+                ParseFromStr(ident) => self.parse_from_str = Some(ident),
+
+                Argfile(ident) => self.argfile = Some(ident),
+
+                CaseInsensitiveLongs(ident) => self.case_insensitive_longs = Some(ident),
+
+                ShowDefaults(ident) => self.show_defaults = Some(ident),
+
+                ShowEnv(ident) => self.show_env = Some(ident),
+
+                ArgEnum(ident) => self.arg_enum = Some(ident),
+
+                Verbosity(ident) => self.verbosity = Some(ident),
+
+                ParseOutput(ident) => self.parse_output = Some(ident),
+
+                Untagged(ident) => self.untagged = Some(ident),
+
+                HideShortHelp(ident) => self.hide_short_help = Some(ident),
+
+                HideLongHelp(ident) => self.hide_long_help = Some(ident),
+
+                ImplFromStr(ident) => self.impl_from_str = Some(ident),
+
+                ImplDisplay(ident) => self.impl_display = Some(ident),
+
+                CaseSensitive(ident) => self.case_sensitive = Some(ident),
+
+                SourceOrder(ident) => {
+                    let method = syn::Ident::new("global_setting", ident.span());
+                    self.methods.push(Method::new(
+                        method,
+                        quote!(::clap::AppSettings::DeriveDisplayOrder),
+                    ));
+                    self.source_order = Some(ident);
+                }
+
+                Lazy(ident) => self.lazy = Some(ident),
+
+                HidePossibleValues(ident) => {
+                    self.methods.push(Method::new(ident.clone(), quote!(true)));
+                    self.hide_possible_values = Some(ident);
+                }
+
+                Color(ident, Some(choice)) => {
+                    let setting = match &*choice.value() {
+                        "auto" => quote!(::clap::AppSettings::ColorAuto),
+                        "always" => quote!(::clap::AppSettings::ColorAlways),
+                        "never" => quote!(::clap::AppSettings::ColorNever),
+                        _ => abort!(
+                            choice.span(),
+                            "`color` must be one of `auto`, `always` or `never`"
+                        ),
+                    };
+                    let method = syn::Ident::new("setting", ident.span());
+                    self.methods.push(Method::new(method, setting));
+                    self.color = Some(ident);
+                }
+
+                Color(ident, None) => {
+                    let method = syn::Ident::new("possible_values", ident.span());
+                    self.methods.push(Method::new(
+                        method,
+                        quote!(&["auto", "always", "never"]),
+                    ));
+                    self.color_arg = Some(ident);
+                }
+
+                Registered(ident) => {
+                    if !cfg!(feature = "inventory") {
+                        abort!(ident.span(),
+                            "`registered` requires the `inventory` feature";
+                            help = "enable the `inventory` feature of `clap_derive`";
+                        );
+                    }
+                    self.registered = Some(ident);
+                }
+
+                Arbitrary(ident) => {
+                    if !cfg!(feature = "arbitrary") {
+                        abort!(ident.span(),
+                            "`arbitrary` requires the `arbitrary` feature";
+                            help = "enable the `arbitrary` feature of `clap_derive`";
+                        );
+                    }
+                    self.arbitrary = Some(ident);
+                }
+
+                Prompt(ident) => {
+                    if !cfg!(feature = "prompt") {
+                        abort!(ident.span(),
+                            "`prompt` requires the `prompt` feature";
+                            help = "enable the `prompt` feature of `clap_derive`";
+                        );
+                    }
+                    self.prompt = Some(ident);
+                }
+
+                Zeroize(ident) => {
+                    if !cfg!(feature = "zeroize") {
+                        abort!(ident.span(),
+                            "`zeroize` requires the `zeroize` feature";
+                            help = "enable the `zeroize` feature of `clap_derive`";
+                        );
+                    }
+                    self.zeroize = Some(ident);
+                }
+
+                CachedApp(ident) => {
+                    if !cfg!(feature = "cached_app") {
+                        abort!(ident.span(),
+                            "`cached_app` requires the `cached_app` feature";
+                            help = "enable the `cached_app` feature of `clap_derive`";
+                        );
+                    }
+                    self.cached_app = Some(ident);
+                }
+
+                Dotenv(ident, path) => {
+                    if !cfg!(feature = "dotenv") {
+                        abort!(ident.span(),
+                            "`dotenv` requires the `dotenv` feature";
+                            help = "enable the `dotenv` feature of `clap_derive`";
+                        );
+                    }
+                    let path = path.map(|lit| lit.value());
+                    self.dotenv = Some(Sp::new(path, ident.span()));
+                }
+
+                ArgIds(ident, name) => {
+                    let name = name.map(|name| name.value());
+                    self.arg_ids = Some(Sp::new(name, ident.span()));
+                }
+
                 About(ident, about) => {
                     self.about = Method::from_lit_or_env(ident, about, "CARGO_PKG_DESCRIPTION");
                 }
@@ -301,23 +824,150 @@ impl Attrs {
                 }
 
                 Version(ident, version) => {
-                    self.version = Some(Method::new(ident, quote!(#version)))
+                    self.version = Method::from_lit_or_env(ident, version, "CARGO_PKG_VERSION");
                 }
 
                 NameLitStr(name, lit) => {
+                    if name == "short" && lit.value().chars().count() != 1 {
+                        abort!(
+                            lit.span(),
+                            "`short` must be a single character, got `{}`", lit.value();
+                            help = "use `long` for names longer than one character";
+                        )
+                    }
                     self.push_str_method(name.into(), lit.into());
                 }
 
                 NameExpr(name, expr) => self.methods.push(Method::new(name, quote!(#expr))),
 
+                Env(ident) => {
+                    self.push_str_method(
+                        ident.into(),
+                        self.name.clone().translate(*self.env_casing).into(),
+                    );
+                }
+
+                EnvList(name, mut names) => {
+                    let env = names.remove(0);
+                    self.methods.push(Method::new(name, quote!(#env)));
+                    self.env_fallbacks = names;
+                }
+
+                MethodCall(name, args) if is_literal_list(&name, &args) => {
+                    self.methods.push(Method::new(name, quote!(&[ #(#args),* ])))
+                }
+
                 MethodCall(name, args) => self.methods.push(Method::new(name, quote!(#(#args),*))),
 
                 RenameAll(_, casing_lit) => {
                     self.casing = CasingStyle::from_lit(casing_lit);
+                    self.explicit_casing = true;
+                }
+
+                PropagateRenameAll(ident) => self.propagate_rename_all = Some(ident),
+
+                RenameAllEnv(_, casing_lit) => {
+                    self.env_casing = CasingStyle::from_lit(casing_lit);
+                }
+
+                RenameAllValue(_, casing_lit) => {
+                    self.value_casing = Some(CasingStyle::from_lit(casing_lit));
+                }
+
+                LegacyRenameAll(_, casing_lit) => {
+                    self.legacy_casing = Some(CasingStyle::from_lit(casing_lit));
+                }
+
+                ConfigFile(ident, flag) => {
+                    if !cfg!(feature = "toml") {
+                        abort!(ident.span(),
+                            "`config_file` requires the `toml` feature";
+                            help = "enable the `toml` feature of `clap_derive`";
+                        );
+                    }
+
+                    let long = flag.value().trim_start_matches('-').to_string();
+                    if long.is_empty() {
+                        abort!(
+                            flag.span(),
+                            "`config_file` must name a long flag, like `--config`"
+                        );
+                    }
+                    self.config_file = Some(Sp::new(long, flag.span()));
+                }
+
+                EnvPrefix(_, prefix) => {
+                    self.env_prefix = Some(prefix.into());
+                }
+
+                LongPrefix(_, prefix) => {
+                    self.long_prefix = Some(prefix.into());
+                }
+
+                Prefix(_, prefix) => {
+                    self.prefix = Some(prefix.into());
+                }
+
+                HelpKey(_, key) => self.help_key = Some(key),
+
+                HelpTemplate(_, template) => self.help_template = Some(template),
+
+                HelpMessage(_, message) => self.help_message = Some(message),
+
+                VersionMessage(_, message) => self.version_message = Some(message),
+
+                Levels(ident, levels) => self.levels = Some((ident, levels)),
+
+                MaxOccurrences(ident, max) => self.max_occurrences = Some((ident, max)),
+
+                PossibleValuesFn(ident, func) => self.possible_values_fn = Some((ident, func)),
+
+                Action(ident, action) => self.action = Some((ident, action)),
+
+                ExitCode(ident, code) => {
+                    if ident == "error_exit_code" {
+                        self.error_exit_code = Some((ident, code));
+                    } else {
+                        self.usage_exit_code = Some((ident, code));
+                    }
+                }
+
+                RequiresAll(ident, names) => self.requires_all.push((ident, names)),
+
+                ValueNames(ident, names) => self.value_names = Some((ident, names)),
+
+                CompleteWith(ident, func) => self.complete_with = Some((ident, func)),
+
+                Group(ident, spec) => self.groups.push((ident, spec)),
+
+                RequiredUnless(ident, names) => self.required_unless.push((ident, names)),
+
+                DefaultValueIfs(ident, conditions) => {
+                    self.default_value_ifs.push((ident, conditions))
                 }
 
+                RelationIf(ident, first, second) => self.relations_if.push((ident, first, second)),
+
+                Localizer(ident, expr) => self.localizer = Some((ident, expr)),
+
+                MutArg(ident, expr) => self.mut_arg = Some((ident, expr)),
+
+                MutQuiet(ident, expr) => self.mut_quiet = Some((ident, expr)),
+
+                OccurrencesOf(ident, field) => self.occurrences_of = Some((ident, field)),
+
+                ArgMatches(ident) => self.arg_matches = Some(ident),
+
+                BinName(ident) => self.bin_name = Some(ident),
+
+                RawArgv(ident) => self.raw_argv = Some(ident),
+
+                Example(ident, example) => self.examples.push((ident, example)),
+
                 Parse(ident, spec) => {
                     self.has_custom_parser = true;
+                    self.default_occurrences =
+                        spec.kind == "from_occurrences" && spec.parse_func.is_none();
                     self.parser = Parser::from_spec(ident, spec);
                 }
             }
@@ -325,6 +975,11 @@ impl Attrs {
     }
 
     fn push_doc_comment(&mut self, attrs: &[syn::Attribute], name: &str) {
+        if attrs.iter().any(|attr| doc_expr(attr).is_some()) {
+            self.push_doc_exprs(attrs);
+            return;
+        }
+
         let doc_comments = attrs
             .iter()
             .filter_map(|attr| {
@@ -344,20 +999,7 @@ impl Attrs {
                     if !path.is_ident("doc") {
                         return None;
                     }
-                    let value = s.value();
-
-                    let text = value
-                        .trim_start_matches("//!")
-                        .trim_start_matches("///")
-                        .trim_start_matches("/*!")
-                        .trim_start_matches("/**")
-                        .trim_end_matches("*/")
-                        .trim();
-                    if text.is_empty() {
-                        Some("\n\n".to_string())
-                    } else {
-                        Some(text.to_string())
-                    }
+                    Some(doc_line(&s.value()))
                 } else {
                     None
                 }
@@ -406,22 +1048,199 @@ impl Attrs {
         }
     }
 
+    /// Same as `push_doc_comment`, when some of the doc attributes are
+    /// expressions, like `#[doc = include_str!("help.md")]`, rather than
+    /// literals: the lines are kept to be joined at runtime, see
+    /// `docs::gen_doc_exprs`.
+    fn push_doc_exprs(&mut self, attrs: &[syn::Attribute]) {
+        let parts = attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(MetaNameValue {
+                    lit: syn::Lit::Str(s),
+                    ..
+                })) => {
+                    let line = doc_line(&s.value());
+                    Some(quote!(#line))
+                }
+                Ok(_) => None,
+                Err(_) => doc_expr(attr).map(|expr| quote!(#expr)),
+            })
+            .collect();
+
+        self.doc_exprs = Some(DocExprs {
+            parts,
+            suffix: None,
+        });
+    }
+
+    /// Append the `example` values given on an argument to its help, like
+    /// clap does for defaults and possible values.
+    fn push_examples_to_help(&mut self) {
+        let examples = self
+            .examples
+            .iter()
+            .map(|(_, example)| example.value())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let suffix = format!("[e.g. {}]", examples);
+        let span = self.examples[0].0.span();
+
+        let explicit = self
+            .methods
+            .iter()
+            .any(|m| m.name == "help" || m.name == "long_help");
+        if let (Some(doc_exprs), false) = (&mut self.doc_exprs, explicit) {
+            doc_exprs.suffix = Some(suffix);
+            return;
+        }
+        if !self.has_doc_methods() {
+            self.methods
+                .push(Method::new(Ident::new("help", span), quote!(#suffix)));
+            return;
+        }
+        for method in &mut self.methods {
+            if method.name != "help" && method.name != "long_help" {
+                continue;
+            }
+            method.args = match syn::parse2::<LitStr>(method.args.clone()) {
+                Ok(lit) => {
+                    let help = format!("{} {}", lit.value().trim_end(), suffix);
+                    quote!(#help)
+                }
+                Err(_) => {
+                    let args = &method.args;
+                    quote!( Self::__clap_interned(format!("{} {}", #args, #suffix)) )
+                }
+            };
+        }
+    }
+
     pub fn from_struct(
         span: Span,
         attrs: &[syn::Attribute],
         name: Name,
         argument_casing: Sp<CasingStyle>,
+        env_casing: Sp<CasingStyle>,
     ) -> Self {
-        let mut res = Self::new(span, name, argument_casing);
+        let mut res = Self::new(span, name, argument_casing, env_casing);
         res.push_attrs(attrs);
         res.push_doc_comment(attrs, "about");
+        res.cased_name = Some(res.name.clone().translate(*res.casing));
 
+        if let Some(id) = &res.id {
+            abort!(id.span(), "`id` is only allowed on fields");
+        }
         if res.has_custom_parser {
             abort!(
                 res.parser.span(),
                 "`parse` attribute is only allowed on fields"
             );
         }
+        if let Some(env_prefix) = &res.env_prefix {
+            abort!(
+                env_prefix.span(),
+                "`env_prefix` is only allowed on flattened fields"
+            );
+        }
+        if let Some(prefix) = &res.prefix {
+            abort!(prefix.span(), "`prefix` is only allowed on flattened fields");
+        }
+        if let Some(secret) = &res.secret {
+            abort!(secret.span(), "`secret` is only allowed on fields");
+        }
+        if let Some(warn_legacy_env) = &res.warn_legacy_env {
+            abort!(warn_legacy_env.span(), "`warn_legacy_env` is only allowed on fields");
+        }
+        if let Some(explicit_bool) = &res.explicit_bool {
+            abort!(
+                explicit_bool.span(),
+                "`explicit_bool` is only allowed on fields"
+            );
+        }
+        if let Some(ident) = res.canonicalize.as_ref().or_else(|| res.expand_tilde.as_ref()) {
+            abort!(ident.span(), "`{}` is only allowed on fields", ident);
+        }
+        if let Some(ident) = res.trim.as_ref().or_else(|| res.lowercase.as_ref()) {
+            abort!(ident.span(), "`{}` is only allowed on fields", ident);
+        }
+        if let Some((value_names, _)) = &res.value_names {
+            abort!(value_names.span(), "`value_names` is only allowed on fields");
+        }
+        if let Some((complete_with, _)) = &res.complete_with {
+            abort!(complete_with.span(), "`complete_with` is only allowed on fields");
+        }
+        if let Some((possible_values_fn, _)) = &res.possible_values_fn {
+            abort!(
+                possible_values_fn.span(),
+                "`possible_values_fn` is only allowed on fields"
+            );
+        }
+        if let Some((action, _)) = &res.action {
+            abort!(action.span(), "`action` is only allowed on fields");
+        }
+        if let Some((mut_arg, _)) = &res.mut_arg {
+            abort!(mut_arg.span(), "`mut_arg` is only allowed on fields");
+        }
+        if let Some((mut_quiet, _)) = &res.mut_quiet {
+            abort!(mut_quiet.span(), "`mut_quiet` is only allowed on fields");
+        }
+        if let Some((occurrences_of, _)) = &res.occurrences_of {
+            abort!(
+                occurrences_of.span(),
+                "`occurrences_of` is only allowed on fields"
+            );
+        }
+        if let Some(ident) = res
+            .arg_matches
+            .as_ref()
+            .or_else(|| res.bin_name.as_ref())
+            .or_else(|| res.raw_argv.as_ref())
+        {
+            abort!(ident.span(), "`{}` is only allowed on fields", ident);
+        }
+        if let Some(arg_enum) = &res.arg_enum {
+            abort!(arg_enum.span(), "`arg_enum` is only allowed on fields");
+        }
+        if let Some(verbosity) = &res.verbosity {
+            abort!(verbosity.span(), "`verbosity` is only allowed on fields");
+        }
+        if let Some(hide) = &res.hide_possible_values {
+            abort!(hide.span(), "`hide_possible_values` is only allowed on fields");
+        }
+        if let Some((levels, _)) = &res.levels {
+            abort!(levels.span(), "`levels` is only allowed on fields");
+        }
+        if let Some((ident, _)) = res
+            .required_unless
+            .first()
+            .or_else(|| res.default_value_ifs.first().map(|(ident, _)| ident))
+            .or_else(|| res.relations_if.first().map(|(ident, _, _)| ident))
+        {
+            abort!(ident.span(), "`{}` is only allowed on fields", ident);
+        }
+        if let Some((max_occurrences, _)) = &res.max_occurrences {
+            abort!(
+                max_occurrences.span(),
+                "`max_occurrences` is only allowed on fields"
+            );
+        }
+        if let Some(propagate) = &res.propagate_rename_all {
+            if !res.explicit_casing {
+                abort!(
+                    propagate.span(),
+                    "`propagate_rename_all` needs a `rename_all` to propagate"
+                );
+            }
+        }
+        if let Some(color_arg) = &res.color_arg {
+            abort!(
+                color_arg.span(),
+                "`color` needs a value on top of structs and enums";
+                help = "use `color = \"auto\"`, `color = \"always\"` or `color = \"never\"`";
+            );
+        }
         match &*res.kind {
             Kind::Subcommand(_) => abort!(res.kind.span(), "subcommand is only allowed on fields"),
             Kind::FlattenStruct => abort!(res.kind.span(), "flatten is only allowed on fields"),
@@ -430,23 +1249,693 @@ impl Attrs {
         }
     }
 
-    pub fn from_field(field: &syn::Field, struct_casing: Sp<CasingStyle>) -> Self {
+    /// The `variants` of an enum along with their `Attrs` and, for those with
+    /// named fields, the ones of their fields, computed once and shared by
+    /// every generated function.
+    ///
+    /// The variants follow the `rename_all_value` of `parent` unless they have
+    /// their own, and count its `rename_all` as theirs.
+    pub fn from_variants<'a>(
+        variants: &'a punctuated::Punctuated<syn::Variant, token::Comma>,
+        parent: &Attrs,
+    ) -> Vec<VariantAttrs<'a>> {
+        variants
+            .iter()
+            .map(|variant| {
+                let attrs = Self::from_struct(
+                    variant.span(),
+                    &variant.attrs,
+                    Name::Derived(variant.ident.clone()),
+                    parent.casing(),
+                    parent.env_casing(),
+                )
+                .inherit_value_casing(parent)
+                .inherit_explicit_casing(parent);
+                let fields = match &variant.fields {
+                    syn::Fields::Named(fields) => Some(Self::from_fields(&fields.named, &attrs)),
+                    _ => None,
+                };
+                (variant, attrs, fields)
+            })
+            .collect()
+    }
+
+    /// The named `fields` of a struct or variant along with their `Attrs`,
+    /// computed once and shared by every generated function.
+    ///
+    /// The long flags carry the `long_prefix` of `parent`, and the
+    /// environment values are shown when `parent` has `show_env`.
+    pub fn from_fields<'a>(
+        fields: &'a punctuated::Punctuated<syn::Field, token::Comma>,
+        parent: &Attrs,
+    ) -> Vec<(&'a syn::Field, Self)> {
+        fields
+            .iter()
+            .map(|field| {
+                let attrs = Self::from_field(field, parent.casing(), parent.env_casing())
+                    .with_long_prefix(parent)
+                    .with_shown_env(parent)
+                    .with_possible_values_fn(parent);
+                (field, attrs)
+            })
+            .collect()
+    }
+
+    pub fn from_field(
+        field: &syn::Field,
+        struct_casing: Sp<CasingStyle>,
+        env_casing: Sp<CasingStyle>,
+    ) -> Self {
         let name = field.ident.clone().unwrap();
-        let mut res = Self::new(field.span(), Name::Derived(name.clone()), struct_casing);
+        let mut res = Self::new(
+            field.span(),
+            Name::Derived(name.clone()),
+            struct_casing,
+            env_casing,
+        );
         res.push_doc_comment(&field.attrs, "help");
         res.push_attrs(&field.attrs);
 
-        match &*res.kind {
-            Kind::FlattenStruct => {
-                if res.has_custom_parser {
-                    abort!(
-                        res.parser.span(),
-                        "parse attribute is not allowed for flattened entry"
-                    );
-                }
-                if res.has_explicit_methods() || res.has_doc_methods() {
-                    abort!(
-                        res.kind.span(),
+        // the field counts the occurrences of another one, or holds the
+        // matches or the command line, without an argument of its own
+        let filled: Vec<_> = res
+            .occurrences_of
+            .iter()
+            .map(|(ident, _)| ident)
+            .chain(res.arg_matches.iter())
+            .chain(res.bin_name.iter())
+            .chain(res.raw_argv.iter())
+            .collect();
+        if let Some(ident) = filled.first() {
+            if let Some(other) = filled.get(1) {
+                abort!(
+                    other.span(),
+                    "`{}` and `{}` can't be used together", ident, other
+                );
+            }
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(
+                    ident.span(),
+                    "`{}` can't be used with subcommand, flatten or skip", ident
+                ),
+            }
+            if res.has_explicit_methods() {
+                abort!(
+                    ident.span(),
+                    "methods are not allowed with `{}`", ident;
+                    help = "the field has no argument of its own"
+                );
+            }
+            res.kind = Sp::new(Kind::Skip(None), ident.span());
+        }
+
+        if let Some((ident, _)) = &res.possible_values_fn {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(
+                    ident.span(),
+                    "`possible_values_fn` is only allowed on arguments"
+                ),
+            }
+            if let Some(m) = res.find_method("possible_values") {
+                abort!(
+                    m.name.span(),
+                    "`possible_values` and `possible_values_fn` can't be used together"
+                );
+            }
+        }
+
+        if let Some(ident) = res
+            .impl_from_str
+            .as_ref()
+            .or_else(|| res.impl_display.as_ref())
+            .or_else(|| res.case_sensitive.as_ref())
+        {
+            abort!(
+                ident.span(),
+                "`{}` is only allowed on top of enums deriving `ArgEnum`", ident
+            );
+        }
+
+        if let Some(arg_enum) = &res.arg_enum {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(arg_enum.span(), "`arg_enum` is only allowed on arguments"),
+            }
+            if res.has_custom_parser {
+                abort!(
+                    res.parser.span(),
+                    "`arg_enum` parses the values already, it can't be used with `parse`"
+                );
+            }
+            let value_type = match *Ty::from_syn_ty(&field.ty) {
+                Ty::Option | Ty::Vec => sub_type(&field.ty),
+                Ty::OptionOption | Ty::OptionVec => sub_type(&field.ty).and_then(sub_type),
+                _ => None,
+            }
+            .unwrap_or(&field.ty);
+            let span = arg_enum.span();
+            res.parser = Sp::new(
+                Parser {
+                    kind: Sp::new(ParserKind::TryFromStr, span),
+                    func: quote_spanned!(span=> <#value_type>::from_value),
+                },
+                span,
+            );
+            res.has_custom_parser = true;
+            if !res.has_method("possible_values") {
+                res.methods.push(Method::new(
+                    Ident::new("possible_values", span),
+                    quote_spanned!(span=> <#value_type>::VARIANTS),
+                ));
+            }
+            if !res.has_method("case_insensitive") {
+                res.methods.push(Method::new(
+                    Ident::new("case_insensitive", span),
+                    quote_spanned!(span=> !<#value_type>::CASE_SENSITIVE),
+                ));
+            }
+        }
+
+        if let Some(hide_short_help) = &res.hide_short_help {
+            match *res.kind {
+                Kind::FlattenStruct => (),
+                Kind::Arg(_) => res.methods.push(Method::new(
+                    Ident::new("hidden_short_help", hide_short_help.span()),
+                    quote!(true),
+                )),
+                _ => abort!(
+                    hide_short_help.span(),
+                    "`hide_short_help` is only allowed on top of structs, on arguments \
+                     and on flattened fields"
+                ),
+            }
+        }
+
+        if let Some((mut_arg, _)) = &res.mut_arg {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(
+                    mut_arg.span(),
+                    "`mut_arg` is only allowed on arguments";
+                    help = "it is given the `clap::Arg` generated for the field"
+                ),
+            }
+        }
+
+        if let Some(hide_long_help) = &res.hide_long_help {
+            match *res.kind {
+                Kind::Arg(_) => res.methods.push(Method::new(
+                    Ident::new("hidden_long_help", hide_long_help.span()),
+                    quote!(true),
+                )),
+                _ => abort!(
+                    hide_long_help.span(),
+                    "`hide_long_help` is only allowed on arguments"
+                ),
+            }
+        }
+
+        if let Some(verbosity) = res.verbosity.clone() {
+            let plain = match *res.kind {
+                Kind::Arg(_) => *Ty::from_syn_ty(&field.ty) == Ty::Other,
+                _ => abort!(verbosity.span(), "`verbosity` is only allowed on arguments"),
+            };
+            if !plain || res.has_custom_parser {
+                abort!(
+                    verbosity.span(),
+                    "`verbosity` needs a plain integer field";
+                    help = "use a field like `level: i8`";
+                );
+            }
+            // the field is seen as the `-v` counted flag, `-q` is added along
+            // with it
+            let span = verbosity.span();
+            if !res.has_method("long") && !res.has_method("short") {
+                res.methods.push(Method::new(Ident::new("long", span), quote!("verbose")));
+                res.methods.push(Method::new(Ident::new("short", span), quote!("v")));
+            }
+            if !res.has_doc_methods() {
+                res.methods.push(Method::new(
+                    Ident::new("help", span),
+                    quote!("More output per occurrence"),
+                ));
+            }
+            res.parser = Sp::new(
+                Parser {
+                    kind: Sp::new(ParserKind::FromOccurrences, span),
+                    func: counted_occurrences(span),
+                },
+                span,
+            );
+            res.default_occurrences = true;
+        }
+
+        if let Some((mut_quiet, _)) = &res.mut_quiet {
+            if res.verbosity.is_none() {
+                abort!(
+                    mut_quiet.span(),
+                    "`mut_quiet` is only allowed along `verbosity`";
+                    help = "it is given the `clap::Arg` of the quiet flag"
+                );
+            }
+        }
+
+        if let Some((ident, action)) = res.action.clone() {
+            let ty = match *res.kind {
+                Kind::Arg(_) => Ty::from_syn_ty(&field.ty),
+                _ => abort!(ident.span(), "`action` is only allowed on arguments"),
+            };
+            if res.has_custom_parser {
+                abort!(
+                    res.parser.span(),
+                    "`action` tells how the values are parsed already, \
+                     it can't be used with `parse`"
+                );
+            }
+            // the actions are spelled out with the parsers doing the same,
+            // the type of the field picking the rest as usual
+            let parser = match (&*action.to_string(), *ty) {
+                ("Set", Ty::Bool) => Some("try_from_str"),
+                ("Set", Ty::Option) | ("Set", Ty::Other) => None,
+                ("SetTrue", Ty::Bool) => None,
+                ("SetTrue", Ty::Other) => Some("from_flag"),
+                ("Count", Ty::Other) => Some("from_occurrences"),
+                ("Append", Ty::Vec) | ("Append", Ty::OptionVec) => None,
+                ("Set", _) | ("SetTrue", _) | ("Count", _) | ("Append", _) => abort!(
+                    action.span(),
+                    "`action = {}` can't be used with this field type", action;
+                    help = "`Set` needs a single value field, `SetTrue` a `bool`, \
+                            `Count` an integer and `Append` a `Vec<T>`"
+                ),
+                _ => abort!(
+                    action.span(),
+                    "`action` expects one of `Set`, `SetTrue`, `Count` or `Append`"
+                ),
+            };
+            if let Some(parser) = parser {
+                let kind = Ident::new(parser, action.span());
+                let spec = ParserSpec {
+                    kind,
+                    eq_token: None,
+                    parse_func: None,
+                };
+                res.has_custom_parser = true;
+                res.default_occurrences = parser == "from_occurrences";
+                res.parser = Parser::from_spec(ident, spec);
+            }
+        }
+
+        if res.default_occurrences && is_integer_ty(&field.ty) {
+            res.parser.func = saturating_occurrences(res.parser.span(), &field.ty);
+        }
+
+        if let Some(ident) = res.canonicalize.clone().or_else(|| res.expand_tilde.clone()) {
+            match *res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(ident.span(), "`{}` is only allowed on arguments", ident),
+            }
+            if res.has_custom_parser {
+                abort!(
+                    res.parser.span(),
+                    "`{}` parses the values already, it can't be used with `parse`", ident
+                );
+            }
+            let span = ident.span();
+            res.parser = Sp::new(
+                Parser {
+                    kind: Sp::new(ParserKind::TryFromOsStr, span),
+                    func: path_parser(span, res.expand_tilde.is_some(), res.canonicalize.is_some()),
+                },
+                span,
+            );
+            res.has_custom_parser = true;
+        }
+
+        if let Some(ident) = res.trim.clone().or_else(|| res.lowercase.clone()) {
+            let flag = match *res.kind {
+                Kind::Arg(_) => {
+                    (*Ty::from_syn_ty(&field.ty) == Ty::Bool && !res.has_custom_parser)
+                        || *res.parser.kind == ParserKind::FromOccurrences
+                        || *res.parser.kind == ParserKind::FromFlag
+                }
+                _ => abort!(ident.span(), "`{}` is only allowed on arguments", ident),
+            };
+            if flag {
+                abort!(
+                    ident.span(),
+                    "`{}` is only allowed on arguments taking a value", ident
+                );
+            }
+            match *res.parser.kind {
+                ParserKind::FromStr | ParserKind::TryFromStr => (),
+                _ => abort!(
+                    ident.span(),
+                    "`{}` needs the values as `&str`", ident;
+                    help = "use `parse(from_str)` or `parse(try_from_str)`"
+                ),
+            }
+            // the possible values are checked by clap before the values
+            // reach the parser
+            if res.lowercase.is_some()
+                && res.has_method("possible_values")
+                && !res.has_method("case_insensitive")
+            {
+                res.methods.push(Method::new(
+                    Ident::new("case_insensitive", ident.span()),
+                    quote!(true),
+                ));
+            }
+            let span = res.parser.span();
+            let kind = res.parser.kind.clone();
+            let func = normalized(&res.parser.func, res.trim.is_some(), res.lowercase.is_some());
+            res.parser = Sp::new(Parser { kind, func }, span);
+        }
+
+        if let Some((ident, _)) = res.default_value_ifs.first() {
+            let takes_value = match &*res.kind {
+                Kind::Arg(ty) => match **ty {
+                    Ty::Bool => false,
+                    _ => match *res.parser.kind {
+                        ParserKind::FromOccurrences | ParserKind::FromFlag => false,
+                        _ => true,
+                    },
+                },
+                _ => false,
+            };
+            if !takes_value {
+                abort!(
+                    ident.span(),
+                    "`default_value_ifs` is only allowed on arguments taking a value"
+                );
+            }
+        }
+
+        if let Some((ident, _, _)) = res.relations_if.first() {
+            match &*res.kind {
+                Kind::Arg(_) => (),
+                _ => abort!(ident.span(), "`{}` is only allowed on arguments", ident),
+            }
+        }
+
+        let required_if = res
+            .relations_if
+            .iter()
+            .map(|(ident, _, _)| ident)
+            .filter(|ident| *ident == "required_if");
+        for ident in res.required_unless.iter().map(|(ident, _)| ident).chain(required_if) {
+            let optional = match &*res.kind {
+                Kind::Arg(ty) => match **ty {
+                    Ty::Bool => false,
+                    Ty::Other => {
+                        res.has_method("default_value") || res.is_explicitly_optional()
+                    }
+                    _ => true,
+                },
+                _ => abort!(ident.span(), "`{}` is only allowed on arguments", ident),
+            };
+            if !optional {
+                abort!(
+                    ident.span(),
+                    "`{}` needs a field which can be left out", ident;
+                    help = "use an `Option` field, or give the argument a `default_value`"
+                );
+            }
+        }
+
+        // on fields, this is the method of `Arg`
+        for (ident, names) in res.requires_all.drain(..) {
+            res.methods.push(Method::new(ident, quote!(&[#( #names ),*])));
+        }
+
+        if let Some((ident, levels)) = res.levels.take() {
+            match (&*res.kind, &*res.parser.kind) {
+                (Kind::Arg(_), ParserKind::FromOccurrences) => (),
+                (Kind::Arg(_), _) if !res.has_custom_parser => (),
+                (Kind::Arg(_), _) => abort!(
+                    res.parser.kind.span(),
+                    "`levels` counts occurrences, it can't be used with another parser"
+                ),
+                _ => abort!(ident.span(), "`levels` is only allowed on arguments"),
+            }
+            res.parser = Parser::from_levels(ident, levels);
+        }
+
+        if let Some(config_file) = &res.config_file {
+            abort!(
+                config_file.span(),
+                "`config_file` is only allowed on structs"
+            );
+        }
+
+        if let Some(partial) = &res.partial {
+            abort!(partial.span(), "`partial` is only allowed on structs");
+        }
+
+        if let Some(untagged) = &res.untagged {
+            abort!(untagged.span(), "`untagged` is only allowed on top of enums");
+        }
+
+        if let Some(parse_output) = &res.parse_output {
+            abort!(
+                parse_output.span(),
+                "`parse_output` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(propagate) = &res.propagate_rename_all {
+            abort!(
+                propagate.span(),
+                "`propagate_rename_all` is only allowed on top of structs, enums and variants"
+            );
+        }
+
+        if let Some((ident, _)) = res.groups.first() {
+            abort!(
+                ident.span(),
+                "`group(...)` is only allowed on top of structs and variants";
+                help = "use `group = \"...\"` to put an argument in a group"
+            );
+        }
+
+        if let Some(dotenv) = &res.dotenv {
+            abort!(
+                dotenv.span(),
+                "`dotenv` is only allowed on top of structs and enums"
+            );
+        }
+        if let Some(arg_ids) = &res.arg_ids {
+            abort!(
+                arg_ids.span(),
+                "`arg_ids` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(plugin) = &res.plugin {
+            abort!(plugin.span(), "`plugin` is only allowed on enum variants");
+        }
+
+        if let Some(registered) = &res.registered {
+            abort!(
+                registered.span(),
+                "`registered` is only allowed on enum variants"
+            );
+        }
+
+        if let Some(color) = &res.color {
+            abort!(
+                color.span(),
+                "`color = \"...\"` is only allowed on top of structs and enums";
+                help = "use a bare `color` to turn the field into a color choice";
+            );
+        }
+
+        if let Some(dynamic_completion) = &res.dynamic_completion {
+            abort!(
+                dynamic_completion.span(),
+                "`dynamic_completion` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(docs) = &res.docs {
+            abort!(docs.span(), "`docs` is only allowed on top of structs and enums");
+        }
+
+        if let Some(parse_from_str) = &res.parse_from_str {
+            abort!(
+                parse_from_str.span(),
+                "`parse_from_str` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(argfile) = &res.argfile {
+            abort!(
+                argfile.span(),
+                "`argfile` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(arbitrary) = &res.arbitrary {
+            abort!(
+                arbitrary.span(),
+                "`arbitrary` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(prompt) = &res.prompt {
+            abort!(
+                prompt.span(),
+                "`prompt` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(zeroize) = &res.zeroize {
+            abort!(
+                zeroize.span(),
+                "`zeroize` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(cached_app) = &res.cached_app {
+            abort!(
+                cached_app.span(),
+                "`cached_app` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some((ident, _)) = res.error_exit_code.as_ref().or(res.usage_exit_code.as_ref()) {
+            abort!(
+                ident.span(),
+                "`{}` is only allowed on top of structs and enums", ident
+            );
+        }
+
+        if let Some(help_template) = &res.help_template {
+            abort!(
+                help_template.span(),
+                "`help_template` is only allowed on top of structs, enums and variants"
+            );
+        }
+
+        if let Some(m) = res.find_method("override_usage") {
+            abort!(
+                m.name.span(),
+                "`override_usage` is only allowed on top of structs, enums and variants"
+            );
+        }
+
+        if let Some(message) = res.help_message.as_ref().or(res.version_message.as_ref()) {
+            abort!(
+                message.span(),
+                "`help_message` and `version_message` are only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some(source_order) = &res.source_order {
+            abort!(
+                source_order.span(),
+                "`source_order` is only allowed on top of structs, enums and variants"
+            );
+        }
+
+        if let Some(show) = res.show_defaults.as_ref().or_else(|| res.show_env.as_ref()) {
+            abort!(show.span(), "`{}` is only allowed on top of structs", show);
+        }
+
+        if let Some(case_insensitive_longs) = &res.case_insensitive_longs {
+            abort!(
+                case_insensitive_longs.span(),
+                "`case_insensitive_longs` is only allowed on top of structs"
+            );
+        }
+
+        if let Some(lazy) = &res.lazy {
+            abort!(lazy.span(), "`lazy` is only allowed on top of enums");
+        }
+
+        if let Some((localizer, _)) = &res.localizer {
+            abort!(
+                localizer.span(),
+                "`localizer` is only allowed on top of structs and enums"
+            );
+        }
+
+        if let Some((example, _)) = res.examples.first() {
+            match *res.kind {
+                Kind::Arg(_) => res.push_examples_to_help(),
+                _ => abort!(
+                    example.span(),
+                    "`example` is only allowed on top of structs, enums and variants, \
+                     and on arguments"
+                ),
+            }
+        }
+
+        if let Some(long_prefix) = &res.long_prefix {
+            abort!(
+                long_prefix.span(),
+                "`long_prefix` is only allowed on top of structs and variants"
+            );
+        }
+
+        if let Some(env_prefix) = &res.env_prefix {
+            match &*res.kind {
+                Kind::FlattenStruct => (),
+                _ => abort!(
+                    env_prefix.span(),
+                    "`env_prefix` is only allowed on flattened fields"
+                ),
+            }
+        }
+
+        if let Some(prefix) = &res.prefix {
+            match &*res.kind {
+                Kind::FlattenStruct => (),
+                _ => abort!(prefix.span(), "`prefix` is only allowed on flattened fields"),
+            }
+            if prefix.contains(':') {
+                abort!(prefix.span(), "`prefix` can't contain `:`");
+            }
+            if let Some(env_prefix) = &res.env_prefix {
+                abort!(
+                    env_prefix.span(),
+                    "`prefix` and `env_prefix` can't be used together";
+                    help = "`prefix` already puts the prefix in front of the environment variables";
+                );
+            }
+        }
+
+        match &*res.kind {
+            Kind::FlattenStruct => {
+                if res.has_custom_parser {
+                    abort!(
+                        res.parser.span(),
+                        "parse attribute is not allowed for flattened entry"
+                    );
+                }
+                // relations between flattened fields go to the group made of
+                // their arguments
+                let (relations, methods) = res.methods.drain(..).partition::<Vec<_>, _>(|m| {
+                    m.name == "conflicts_with" || m.name == "requires"
+                });
+                res.methods = methods;
+                res.flatten_relations = relations
+                    .into_iter()
+                    .map(|m| match syn::parse2::<LitStr>(m.args.clone()) {
+                        Ok(lit) => (m.name, lit),
+                        Err(_) => abort!(
+                            m.name.span(),
+                            "`{}` on a flattened field expects a field name, \
+                             like `{} = \"other_field\"`",
+                            m.name,
+                            m.name
+                        ),
+                    })
+                    .collect();
+                if res.has_explicit_methods() || res.has_doc_methods() {
+                    abort!(
+                        res.kind.span(),
                         "methods and doc comments are not allowed for flattened entry"
                     );
                 }
@@ -528,10 +2017,12 @@ impl Attrs {
                         }
                     }
                     Ty::OptionOption => {
-                        if res.is_positional() {
+                        if res.is_positional() && !res.has_method("default_missing_value") {
                             abort!(
                                 ty.span(),
-                                "Option<Option<T>> type is meaningless for positional argument"
+                                "Option<Option<T>> type is meaningless for positional argument";
+                                help = "add `default_missing_value = \"...\"` to name the value \
+                                    standing for a missing one, or add `long` or `short`";
                             )
                         }
                     }
@@ -546,10 +2037,188 @@ impl Attrs {
 
                     _ => (),
                 }
+
+                if cfg!(feature = "log")
+                    && !res.has_custom_parser
+                    && !res.has_method("possible_values")
+                {
+                    if let Some(levels) = log_levels(&field.ty) {
+                        let span = field.ty.span();
+                        res.methods.push(Method::new(
+                            Ident::new("possible_values", span),
+                            quote!(&[#( #levels ),*]),
+                        ));
+                        res.methods.push(Method::new(
+                            Ident::new("case_insensitive", span),
+                            quote!(true),
+                        ));
+                    }
+                    // a filter string of `tracing_subscriber`, which is only
+                    // read from `RUST_LOG` when asked with `env = "RUST_LOG"`
+                    if is_log_filter(&field.ty) && !res.has_method("value_name") {
+                        let span = field.ty.span();
+                        res.methods
+                            .push(Method::new(Ident::new("value_name", span), quote!("FILTER")));
+                    }
+                }
+
                 res.kind = Sp::new(Kind::Arg(ty), orig_ty.span());
             }
         }
 
+        if let Some((max_occurrences, _)) = &res.max_occurrences {
+            let repeated = match &*res.kind {
+                Kind::Arg(ty) => match **ty {
+                    Ty::Vec | Ty::OptionVec => true,
+                    Ty::Other => *res.parser.kind == ParserKind::FromOccurrences,
+                    _ => false,
+                },
+                _ => false,
+            };
+            if !repeated {
+                abort!(
+                    max_occurrences.span(),
+                    "`max_occurrences` is only allowed on repeated arguments";
+                    help = "count a flag with `parse(from_occurrences)` or repeat an option \
+                        with a `Vec` field";
+                )
+            }
+        }
+
+        if let Some(explicit_bool) = &res.explicit_bool {
+            match &*res.kind {
+                Kind::Arg(ty) if **ty == Ty::Bool => (),
+                _ => abort!(
+                    explicit_bool.span(),
+                    "`explicit_bool` is only allowed on `bool` flags";
+                    help = "a `bool` with a custom parser already takes a value"
+                ),
+            }
+        }
+
+        if let Some(secret) = &res.secret {
+            match &*res.kind {
+                Kind::Arg(_) if res.is_positional() => abort!(
+                    secret.span(),
+                    "`secret` is not allowed on positional arguments";
+                    help = "add `long` or `short`, the values of positional arguments \
+                        can't be told apart on the command line"
+                ),
+                _ => (),
+            }
+        }
+
+        if let Some(warn_legacy_env) = &res.warn_legacy_env {
+            if res.env_fallbacks.is_empty() {
+                abort!(
+                    warn_legacy_env.span(),
+                    "`warn_legacy_env` needs legacy environment variables";
+                    help = "list them after the current one: `env = [\"NAME\", \"LEGACY_NAME\"]`"
+                );
+            }
+        }
+
+        if let Some((ident, names)) = res.value_names.take() {
+            // a tuple or an array, bare or in an `Option`, takes one value
+            // per element, parsed in turn
+            let shape = match &*res.kind {
+                Kind::Arg(ty) => match **ty {
+                    Ty::Vec | Ty::OptionVec | Ty::OptionOption => None,
+                    Ty::Option => sub_type(&field.ty),
+                    Ty::Bool => abort!(ident.span(), "`value_names` is meaningless for bool"),
+                    Ty::Other => match *res.parser.kind {
+                        ParserKind::FromOccurrences | ParserKind::FromFlag => abort!(
+                            ident.span(),
+                            "`value_names` is only allowed on arguments taking a value"
+                        ),
+                        _ => Some(&field.ty),
+                    },
+                },
+                _ => abort!(ident.span(), "`value_names` is only allowed on arguments"),
+            };
+            let multiple = match &*res.kind {
+                Kind::Arg(ty) => **ty == Ty::Vec || **ty == Ty::OptionVec,
+                _ => false,
+            };
+            let fixed = shape
+                .filter(|_| !res.has_custom_parser)
+                .and_then(fixed_values)
+                .map(|(_, count)| count);
+            if let Some(count) = fixed {
+                if names.len() != count {
+                    abort!(
+                        ident.span(),
+                        "`value_names` needs {} names for this field, one per value", count
+                    );
+                }
+                res.has_fixed_values = true;
+            } else if names.len() > 1 && !multiple {
+                abort!(
+                    ident.span(),
+                    "`value_names` with more than one name needs a `Vec<T>`, \
+                     a tuple or an array field";
+                    help = "only the first value would be kept otherwise"
+                );
+            }
+            if let Some(m) = res.find_method("value_name") {
+                abort!(
+                    m.name.span(),
+                    "`value_name` and `value_names` can't be used together"
+                );
+            }
+            // each occurrence takes one value per name
+            if names.len() > 1 && !res.has_method("number_of_values") {
+                let count = proc_macro2::Literal::u64_unsuffixed(names.len() as u64);
+                res.methods.push(Method::new(
+                    Ident::new("number_of_values", ident.span()),
+                    quote!(#count),
+                ));
+            }
+            res.methods.push(Method::new(ident, quote!(&[#( #names ),*])));
+        }
+
+        if let Some((ident, func)) = &res.complete_with {
+            let takes_value = match &*res.kind {
+                Kind::Arg(ty) => match **ty {
+                    Ty::Bool => false,
+                    _ => match *res.parser.kind {
+                        ParserKind::FromOccurrences | ParserKind::FromFlag => false,
+                        _ => true,
+                    },
+                },
+                _ => abort!(ident.span(), "`complete_with` is only allowed on arguments"),
+            };
+            if !takes_value {
+                abort!(
+                    ident.span(),
+                    "`complete_with` is only allowed on arguments taking a value"
+                );
+            }
+            match func {
+                syn::Expr::Path(_) => (),
+                _ => abort!(func.span(), "`complete_with` argument must be a function path"),
+            }
+        }
+
+        res.cased_name = Some(res.name.clone().translate(*res.casing));
+
+        if let Some(id) = &res.id {
+            let takes_value = match &*res.kind {
+                Kind::Arg(ty) => {
+                    **ty != Ty::Bool
+                        && *res.parser.kind != ParserKind::FromOccurrences
+                        && *res.parser.kind != ParserKind::FromFlag
+                }
+                _ => abort!(id.span(), "`id` is only allowed on arguments"),
+            };
+            // clap shows the id as the value name by default
+            if takes_value && !res.has_method("value_name") && !res.has_method("value_names") {
+                let value_name = res.cased_name();
+                let method = syn::Ident::new("value_name", id.span());
+                res.methods.push(Method::new(method, quote!(#value_name)));
+            }
+            res.cased_name = Some(id.clone());
+        }
         res
     }
 
@@ -572,6 +2241,17 @@ impl Attrs {
         self.methods.iter().find(|m| m.name == name)
     }
 
+    /// Remove the `name` method, returning it, if any.
+    pub fn take_method(&mut self, name: &str) -> Option<Method> {
+        let i = self.methods.iter().position(|m| m.name == name)?;
+        Some(self.methods.remove(i))
+    }
+
+    /// The arguments given to the `name` method, if any.
+    pub fn method_args(&self, name: &str) -> Option<&proc_macro2::TokenStream> {
+        self.find_method(name).map(|m| &m.args)
+    }
+
     /// generate methods from attributes on top of struct or enum
     pub fn top_level_methods(&self) -> proc_macro2::TokenStream {
         let version = match (&self.no_version, &self.version) {
@@ -582,6 +2262,8 @@ impl Attrs {
 
             (None, Some(m)) => m.to_token_stream(),
 
+            (None, None) if self.no_auto_version.is_some() => quote!(),
+
             (None, None) => std::env::var("CARGO_PKG_VERSION")
                 .map(|version| quote!( .version(#version) ))
                 .unwrap_or_default(),
@@ -603,7 +2285,10 @@ impl Attrs {
     }
 
     pub fn cased_name(&self) -> LitStr {
-        self.name.clone().translate(*self.casing)
+        match &self.cased_name {
+            Some(cased_name) => cased_name.clone(),
+            None => self.name.clone().translate(*self.casing),
+        }
     }
 
     pub fn parser(&self) -> &Sp<Parser> {
@@ -618,12 +2303,631 @@ impl Attrs {
         self.casing.clone()
     }
 
+    pub fn env_casing(&self) -> Sp<CasingStyle> {
+        self.env_casing.clone()
+    }
+
+    /// Use the `rename_all_value` of `parent` unless one is given here, so
+    /// that the one on top of an enum reaches the fields of its variants.
+    pub fn inherit_value_casing(mut self, parent: &Attrs) -> Self {
+        if self.value_casing.is_none() {
+            self.value_casing = parent.value_casing.clone();
+        }
+        self
+    }
+
+    /// Count a `rename_all` given on `parent` as given here, so that the one
+    /// on top of an enum keeps propagated casings off its variants.
+    pub fn inherit_explicit_casing(mut self, parent: &Attrs) -> Self {
+        self.explicit_casing |= parent.explicit_casing;
+        self
+    }
+
+    /// The value name derived from the field name by `rename_all_value`,
+    /// given on the field or on `parent`, unless `value_name` is explicit.
+    pub fn derived_value_name(&self, parent: &Attrs) -> Option<LitStr> {
+        if self.has_method("value_name") || self.has_method("value_names") {
+            return None;
+        }
+        let casing = self.value_casing.as_ref().or(parent.value_casing.as_ref())?;
+        Some(self.name.clone().translate(**casing))
+    }
+
+    pub fn config_file(&self) -> Option<&Sp<String>> {
+        self.config_file.as_ref()
+    }
+
+    pub fn partial(&self) -> Option<&syn::Ident> {
+        self.partial.as_ref()
+    }
+
+    /// Whether the variants of the enum are alternative sets of arguments
+    /// to flatten, rather than subcommands.
+    pub fn untagged(&self) -> Option<&syn::Ident> {
+        self.untagged.as_ref()
+    }
+
+    pub fn parse_output(&self) -> Option<&syn::Ident> {
+        self.parse_output.as_ref()
+    }
+
+    pub fn dotenv(&self) -> Option<&Sp<Option<String>>> {
+        self.dotenv.as_ref()
+    }
+
+    /// The name of the module holding the ids of the arguments, given with
+    /// `arg_ids`, if any: `<type>_args` unless named there.
+    pub fn arg_ids(&self) -> Option<&Sp<Option<String>>> {
+        self.arg_ids.as_ref()
+    }
+
+    pub fn env_prefix(&self) -> Option<&Sp<String>> {
+        self.env_prefix.as_ref()
+    }
+
+    /// The prefix given at a flatten site, if any.
+    pub fn prefix(&self) -> Option<&Sp<String>> {
+        self.prefix.as_ref()
+    }
+
+    /// The casing given with `rename_all` at a flatten site, if any, as
+    /// named in runtime prefixes.
+    pub fn flatten_casing(&self) -> Option<&'static str> {
+        match *self.kind {
+            Kind::FlattenStruct if self.explicit_casing => Some(self.casing.key()),
+            _ => None,
+        }
+    }
+
+    /// The casing given with `rename_all` along with `propagate_rename_all`,
+    /// if any, as named in runtime prefixes.
+    pub fn propagated_casing(&self) -> Option<&'static str> {
+        self.propagate_rename_all
+            .as_ref()
+            .map(|_| self.casing.key())
+    }
+
+    /// The name under the casing carried by the runtime `prefix`, with
+    /// `long_prefix` in front, each casing changing it getting its literal.
+    ///
+    /// A casing coming from `propagate_rename_all` is ignored when
+    /// `cased_here`, and so is any casing when the name is given with `name`.
+    fn gen_recased(
+        &self,
+        prefix: &syn::Ident,
+        long_prefix: Option<&str>,
+        cased_here: bool,
+    ) -> TokenStream {
+        let with_long_prefix = |name: LitStr| match long_prefix {
+            Some(long_prefix) => {
+                LitStr::new(&format!("{}-{}", long_prefix, name.value()), name.span())
+            }
+            None => name,
+        };
+        let name = with_long_prefix(self.cased_name());
+        if let Name::Assigned(_) = self.name {
+            return quote!(#name);
+        }
+
+        let mut arms: Vec<(Vec<&'static str>, LitStr)> = Vec::new();
+        for casing in &CasingStyle::ALL {
+            let cased = with_long_prefix(self.name.clone().translate(*casing));
+            if cased.value() == name.value() {
+                continue;
+            }
+            match arms.iter_mut().find(|(_, lit)| lit.value() == cased.value()) {
+                Some((keys, _)) => keys.push(casing.key()),
+                None => arms.push((vec![casing.key()], cased)),
+            }
+        }
+        if arms.is_empty() {
+            return quote!(#name);
+        }
+
+        let inherited = if cased_here { quote!(false) } else { quote!(_) };
+        let arms = arms.iter().map(|(keys, cased)| {
+            quote!( #( Some((#keys, #inherited)) )|* => #cased, )
+        });
+        quote! {
+            match #prefix.2 {
+                #( #arms )*
+                _ => #name,
+            }
+        }
+    }
+
+    /// The name of this subcommand under the casing of the runtime
+    /// `prefix`, if any, unless it was renamed with `rename_all`.
+    pub fn gen_runtime_cased_name(&self, prefix: &syn::Ident) -> TokenStream {
+        self.gen_recased(prefix, None, self.explicit_casing)
+    }
+
+    /// Compute the long flag and the environment variable of this field at
+    /// runtime, prefixed with the value of the `prefix` variable.
+    ///
+    /// A long flag derived from the field name follows the casing given at
+    /// the flatten site, if any, unless the field has a `rename_all` itself,
+    /// and the one given with `propagate_rename_all` above, unless `parent`
+    /// has one too.
+    pub fn with_runtime_prefix(mut self, prefix: &syn::Ident, parent: &Attrs) -> Self {
+        let recased = if self.derived_long && !self.explicit_casing {
+            let long_prefix = self.long_prefix.as_ref().map(|prefix| &***prefix);
+            Some(self.gen_recased(prefix, long_prefix, parent.explicit_casing))
+        } else {
+            None
+        };
+        for method in &mut self.methods {
+            let args = &method.args;
+            if method.name == "long" {
+                method.args = match &recased {
+                    Some(recased) => quote!( Self::__clap_prefixed(#prefix, #recased) ),
+                    None => quote!( Self::__clap_prefixed(#prefix, #args) ),
+                };
+            }
+        }
+        self.with_env_var(Some(prefix))
+    }
+
+    /// Read the first set variable among the one of this field and its
+    /// fallbacks, prefixed with the value of `prefix`, if any.
+    pub fn with_env_var(mut self, prefix: Option<&syn::Ident>) -> Self {
+        if let Some(env) = self.gen_env_var(prefix) {
+            for method in self.methods.iter_mut().filter(|m| m.name == "env") {
+                method.args = env.clone();
+            }
+        }
+        self
+    }
+
+    /// The expression naming the environment variable the argument reads,
+    /// if any: the first set one among its own and its fallbacks, prefixed
+    /// with the value of `prefix`, if any.
+    pub fn gen_env_var(&self, prefix: Option<&syn::Ident>) -> Option<TokenStream> {
+        let prefixed = |env: TokenStream| match prefix {
+            Some(prefix) => quote!( Self::__clap_prefixed_env(#prefix, #env) ),
+            None => env,
+        };
+        let env = prefixed(self.env_var()?);
+        if self.env_fallbacks.is_empty() {
+            return Some(env);
+        }
+        let fallbacks = self.env_fallbacks.iter().map(|env| prefixed(quote!(#env)));
+        Some(quote!( Self::__clap_first_env(&[#env, #( #fallbacks ),*]) ))
+    }
+
+    /// The long flag this field had under the `legacy_rename_all` casing,
+    /// given on the field or on `parent`, when it differs from the current
+    /// one.
+    ///
+    /// Only long flags derived from the field name have one.
+    pub fn legacy_long_alias(&self, parent: &Attrs) -> Option<LitStr> {
+        if !self.derived_long {
+            return None;
+        }
+        let casing = self.legacy_casing.as_ref().or(parent.legacy_casing.as_ref())?;
+        let legacy = self.name.clone().translate(**casing).value();
+        if legacy == self.name.clone().translate(*self.casing).value() {
+            return None;
+        }
+        let legacy = match &parent.long_prefix {
+            Some(prefix) => format!("{}-{}", **prefix, legacy),
+            None => legacy,
+        };
+        Some(LitStr::new(&legacy, casing.span()))
+    }
+
+    /// Show the value of the environment variable this field declares, if
+    /// any, when `parent` has `show_env` and the field doesn't hide it itself.
+    pub fn with_shown_env(mut self, parent: &Attrs) -> Self {
+        let show_env = match &parent.show_env {
+            Some(show_env) => show_env,
+            None => return self,
+        };
+        if self.has_method("env") && !self.has_method("hide_env_values") {
+            let name = syn::Ident::new("hide_env_values", show_env.span());
+            self.methods.push(Method::new(name, quote!(false)));
+        }
+        self
+    }
+
+    /// The expression given to `max_occurrences`, if any.
+    pub fn max_occurrences(&self) -> Option<&Expr> {
+        self.max_occurrences.as_ref().map(|(_, max)| max)
+    }
+
+    /// Whether the fields of this struct show the value they fall back to.
+    pub fn show_defaults(&self) -> bool {
+        self.show_defaults.is_some()
+    }
+
+    /// Take the values of `possible_values_fn`, if given, from a function of
+    /// the type of `parent` named after it and this field, see
+    /// `possible_values_fn()`.
+    pub fn with_possible_values_fn(mut self, parent: &Attrs) -> Self {
+        let ident = match &self.possible_values_fn {
+            Some((ident, _)) => ident.clone(),
+            None => return self,
+        };
+        let snake = |name: &Name| -> String {
+            name.clone()
+                .translate(CasingStyle::Snake)
+                .value()
+                .to_snake_case()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect()
+        };
+        let name = Ident::new(
+            &format!("__clap_possible_values_{}_{}", snake(&parent.name), snake(&self.name)),
+            ident.span(),
+        );
+        self.methods.push(Method::new(
+            Ident::new("possible_values", ident.span()),
+            quote!( Self::#name() ),
+        ));
+        self.possible_values_fn_name = Some(name);
+        self
+    }
+
+    /// The name of the function giving the values of `possible_values_fn`,
+    /// along with the user function.
+    pub fn possible_values_fn(&self) -> Option<(&Ident, &syn::Expr)> {
+        match (&self.possible_values_fn_name, &self.possible_values_fn) {
+            (Some(name), Some((_, func))) => Some((name, func)),
+            _ => None,
+        }
+    }
+
+    /// Put the `long_prefix` of `parent`, if any, in front of the long flag
+    /// of this field.
+    pub fn with_long_prefix(mut self, parent: &Attrs) -> Self {
+        let prefix = match &parent.long_prefix {
+            Some(prefix) => prefix,
+            None => return self,
+        };
+        self.long_prefix = Some(prefix.clone());
+        let prefix = &**prefix;
+        if let Some(long) = self.methods.iter_mut().find(|m| m.name == "long") {
+            long.args = match syn::parse2::<LitStr>(long.args.clone()) {
+                Ok(lit) => {
+                    let prefixed = LitStr::new(&format!("{}-{}", prefix, lit.value()), lit.span());
+                    quote!(#prefixed)
+                }
+                Err(_) => {
+                    let args = &long.args;
+                    quote!( Self::__clap_interned(format!("{}-{}", #prefix, #args)) )
+                }
+            };
+        }
+        self
+    }
+
+    pub fn help_key(&self) -> Option<&LitStr> {
+        self.help_key.as_ref()
+    }
+
+    /// The text going after the long help: the `after_long_help` given
+    /// by the user followed by the EXAMPLES section, if there are examples.
+    ///
+    /// Without `after_long_help`, clap shows `after_help` in the long help
+    /// too, so it is kept there.
+    pub fn after_long_help_text(&self) -> String {
+        let mut text = self
+            .find_lit("after_long_help")
+            .or_else(|| self.find_lit("after_help"))
+            .map(|lit| lit.value())
+            .unwrap_or_default();
+
+        if !self.examples.is_empty() {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str("EXAMPLES:");
+            for (_, example) in &self.examples {
+                text.push_str("\n    ");
+                text.push_str(&example.value());
+            }
+        }
+
+        text
+    }
+
+    /// Whether the arguments are only shown in the long help.
+    pub fn hide_short_help(&self) -> Option<&syn::Ident> {
+        self.hide_short_help.as_ref()
+    }
+
+    /// Whether the field is the level given by `-v` and `-q` flags.
+    pub fn is_verbosity(&self) -> bool {
+        self.verbosity.is_some()
+    }
+
+    /// The span of the `verbosity` attribute.
+    pub fn verbosity_span(&self) -> Span {
+        self.verbosity
+            .as_ref()
+            .map_or_else(Span::call_site, |verbosity| verbosity.span())
+    }
+
+    /// The id of the `-q` flag going with a `verbosity` field.
+    pub fn quiet_id(&self) -> LitStr {
+        let name = self.cased_name();
+        LitStr::new(&format!("{}-quiet", name.value()), name.span())
+    }
+
+    /// The short of the `-q` flag going with a `verbosity` field: `q` when
+    /// the field is `-v`, none otherwise.
+    pub fn quiet_short(&self) -> Option<char> {
+        match self.short_name() {
+            Some('v') => Some('q'),
+            _ => None,
+        }
+    }
+
+    /// The long of the `-q` flag going with a `verbosity` field: `quiet`
+    /// when the field is `--verbose` or has no long, `<long>-quiet`
+    /// otherwise, under the `long_prefix` of the field.
+    pub fn quiet_long(&self) -> LitStr {
+        let prefix = self.long_prefix.as_ref().map(|prefix| format!("{}-", **prefix));
+        let long = self.long_name().map(|long| match &prefix {
+            Some(prefix) if long.starts_with(prefix) => long[prefix.len()..].to_string(),
+            _ => long,
+        });
+        let quiet = match long {
+            Some(ref long) if long != "verbose" => format!("{}-quiet", long),
+            _ => "quiet".to_string(),
+        };
+        let quiet = match prefix {
+            Some(prefix) => format!("{}{}", prefix, quiet),
+            None => quiet,
+        };
+        LitStr::new(&quiet, self.verbosity_span())
+    }
+
+    /// The function given with `mut_quiet`, run on the `-q` flag of a
+    /// `verbosity` field.
+    pub fn mut_quiet(&self) -> Option<&syn::Expr> {
+        self.mut_quiet.as_ref().map(|(_, expr)| expr)
+    }
+
+    /// The `impl_from_str` given on top of an `ArgEnum`, if any.
+    pub fn impl_from_str(&self) -> Option<&syn::Ident> {
+        self.impl_from_str.as_ref()
+    }
+
+    /// The `impl_display` given on top of an `ArgEnum`, if any.
+    pub fn impl_display(&self) -> Option<&syn::Ident> {
+        self.impl_display.as_ref()
+    }
+
+    /// The `case_sensitive` given on top of an `ArgEnum`, if any.
+    pub fn case_sensitive(&self) -> Option<&syn::Ident> {
+        self.case_sensitive.as_ref()
+    }
+
+    /// The fields given with `required_unless_any` and
+    /// `required_unless_all`, resolved to arguments by the parent.
+    pub fn required_unless(&self) -> &[(syn::Ident, Vec<LitStr>)] {
+        &self.required_unless
+    }
+
+    /// The `(field, value, default)` conditions given with
+    /// `default_value_ifs`, resolved to arguments by the parent.
+    pub fn default_value_ifs(&self) -> &[(syn::Ident, Vec<(LitStr, Option<LitStr>, LitStr)>)] {
+        &self.default_value_ifs
+    }
+
+    /// The `requires_if(value, field)` and `required_if(field, value)`
+    /// conditions, resolved to arguments by the parent.
+    pub fn relations_if(&self) -> &[(syn::Ident, LitStr, LitStr)] {
+        &self.relations_if
+    }
+
+    /// The sets of fields given with `requires_all` on top of a struct,
+    /// which must be present together.
+    pub fn requires_all(&self) -> &[(syn::Ident, Vec<LitStr>)] {
+        &self.requires_all
+    }
+
+    /// The groups declared with `group(...)` on top of a struct.
+    pub fn groups(&self) -> &[(syn::Ident, GroupSpec)] {
+        &self.groups
+    }
+
+    /// The `conflicts_with` and `requires` given on a flattened field, naming
+    /// other flattened fields.
+    pub fn flatten_relations(&self) -> &[(syn::Ident, LitStr)] {
+        &self.flatten_relations
+    }
+
+    pub fn has_examples(&self) -> bool {
+        !self.examples.is_empty()
+    }
+
+    /// The field whose occurrences this field counts, if any.
+    pub fn occurrences_of(&self) -> Option<(&syn::Ident, &LitStr)> {
+        self.occurrences_of
+            .as_ref()
+            .map(|(ident, field)| (ident, field))
+    }
+
+    /// Whether the field holds the name of the binary as invoked.
+    pub fn bin_name(&self) -> Option<&Ident> {
+        self.bin_name.as_ref()
+    }
+
+    /// Whether the field holds the whole command line, untouched.
+    pub fn raw_argv(&self) -> Option<&Ident> {
+        self.raw_argv.as_ref()
+    }
+
+    /// Whether the field holds the `ArgMatches` it is built from.
+    pub fn is_arg_matches(&self) -> bool {
+        self.arg_matches.is_some()
+    }
+
+    /// The function given with `mut_arg`, run on the generated `Arg`.
+    pub fn mut_arg(&self) -> Option<&syn::Expr> {
+        self.mut_arg.as_ref().map(|(_, expr)| expr)
+    }
+
+    pub fn localizer(&self) -> Option<&syn::Expr> {
+        self.localizer.as_ref().map(|(_, expr)| expr)
+    }
+
+    pub fn plugin(&self) -> Option<&syn::Ident> {
+        self.plugin.as_ref()
+    }
+
+    pub fn registered(&self) -> Option<&syn::Ident> {
+        self.registered.as_ref()
+    }
+
+    pub fn is_color_arg(&self) -> bool {
+        self.color_arg.is_some()
+    }
+
+    pub fn has_dynamic_completion(&self) -> bool {
+        self.dynamic_completion.is_some()
+    }
+
+    pub fn has_docs(&self) -> bool {
+        self.docs.is_some()
+    }
+
+    pub fn has_parse_from_str(&self) -> bool {
+        self.parse_from_str.is_some()
+    }
+
+    pub fn has_argfile(&self) -> bool {
+        self.argfile.is_some()
+    }
+
+    pub fn has_arbitrary(&self) -> bool {
+        self.arbitrary.is_some()
+    }
+
+    pub fn has_prompt(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    pub fn has_zeroize(&self) -> bool {
+        self.zeroize.is_some()
+    }
+
+    pub fn has_cached_app(&self) -> bool {
+        self.cached_app.is_some()
+    }
+
+    /// The exit code given with `error_exit_code`, if any.
+    pub fn error_exit_code(&self) -> Option<&Expr> {
+        self.error_exit_code.as_ref().map(|(_, code)| code)
+    }
+
+    /// The exit code given with `usage_exit_code`, if any.
+    pub fn usage_exit_code(&self) -> Option<&Expr> {
+        self.usage_exit_code.as_ref().map(|(_, code)| code)
+    }
+
+    /// The `help_template` of the command, applied to its own `App` only,
+    /// not to the ones it is flattened into.
+    pub fn help_template(&self) -> Option<&LitStr> {
+        self.help_template.as_ref()
+    }
+
+    /// The help of the `--help` flag, for the command and its subcommands.
+    pub fn help_message(&self) -> Option<&LitStr> {
+        self.help_message.as_ref()
+    }
+
+    /// The help of the `--version` flag, for the command and its
+    /// subcommands.
+    pub fn version_message(&self) -> Option<&LitStr> {
+        self.version_message.as_ref()
+    }
+
+    pub fn case_insensitive_longs(&self) -> Option<&syn::Ident> {
+        self.case_insensitive_longs.as_ref()
+    }
+
+    pub fn lazy(&self) -> Option<&syn::Ident> {
+        self.lazy.as_ref()
+    }
+
+    /// The literal aliases given with `alias` and `visible_alias`.
+    pub fn aliases(&self) -> Vec<LitStr> {
+        self.methods
+            .iter()
+            .filter(|m| m.name == "alias" || m.name == "visible_alias")
+            .filter_map(|m| syn::parse2(m.args.clone()).ok())
+            .collect()
+    }
+
+    /// Whether the method `name` is given the literal `true`.
+    pub fn has_true_method(&self, name: &str) -> bool {
+        self.find_method(name)
+            .map_or(false, |m| m.args.to_string() == "true")
+    }
+
+    /// Whether the `AppSettings` variant `setting` is given with `setting`,
+    /// `settings` or `global_setting`.
+    pub fn has_setting(&self, setting: &str) -> bool {
+        self.methods
+            .iter()
+            .filter(|m| m.name == "setting" || m.name == "settings" || m.name == "global_setting")
+            .any(|m| {
+                m.args
+                    .to_string()
+                    .split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .any(|word| word == setting)
+            })
+    }
+
+    pub fn is_secret(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Whether the flag also takes an explicit `true` or `false` value.
+    pub fn is_explicit_bool(&self) -> bool {
+        self.explicit_bool.is_some()
+    }
+
     pub fn is_positional(&self) -> bool {
         self.methods
             .iter()
             .all(|m| m.name != "long" && m.name != "short")
     }
 
+    /// Whether the argument is required, as far as can be told at
+    /// expansion time.
+    pub fn is_required(&self) -> bool {
+        let explicitly_required = self
+            .find_method("required")
+            .map_or(false, |m| m.args.to_string() == "true");
+
+        explicitly_required
+            || match &*self.kind {
+                Kind::Arg(ty) if **ty == Ty::Other => match *self.parser.kind {
+                    ParserKind::FromOccurrences | ParserKind::FromFlag => false,
+                    _ => !self.has_method("default_value") && !self.is_explicitly_optional(),
+                },
+                _ => false,
+            }
+    }
+
+    /// Whether the argument is given `required = false`, a plain field then
+    /// falling back to `Default::default()` when it is absent.
+    pub fn is_explicitly_optional(&self) -> bool {
+        self.find_method("required")
+            .map_or(false, |m| m.args.to_string() == "false")
+    }
+
+    /// Whether the parser is given with `parse(...)`, or comes from
+    /// `arg_enum`, rather than being the default one.
+    pub fn has_custom_parser(&self) -> bool {
+        self.has_custom_parser
+    }
+
     pub fn has_explicit_methods(&self) -> bool {
         self.methods
             .iter()
@@ -631,9 +2935,111 @@ impl Attrs {
     }
 
     pub fn has_doc_methods(&self) -> bool {
-        self.methods
-            .iter()
-            .any(|m| m.name == "help" || m.name == "long_help")
+        self.doc_exprs.is_some()
+            || self
+                .methods
+                .iter()
+                .any(|m| m.name == "help" || m.name == "long_help")
+    }
+
+    /// The help of a field, or the about of a struct or variant, given by
+    /// doc attributes some of which are expressions, unless one is given
+    /// explicitly.
+    pub fn doc_exprs(&self) -> Option<&DocExprs> {
+        let explicit = self.about.is_some()
+            || ["help", "long_help", "about", "long_about"]
+                .iter()
+                .any(|name| self.has_method(name));
+        self.doc_exprs.as_ref().filter(|_| !explicit)
+    }
+
+    /// The string literal passed to the method `name`, if any.
+    pub fn find_lit(&self, name: &str) -> Option<LitStr> {
+        self.find_method(name)
+            .and_then(|m| syn::parse2(m.args.clone()).ok())
+    }
+
+    pub fn short_name(&self) -> Option<char> {
+        self.find_lit("short")
+            .and_then(|lit| lit.value().chars().next())
+    }
+
+    pub fn long_name(&self) -> Option<String> {
+        self.find_lit("long").map(|lit| lit.value())
+    }
+
+    /// First line of the help message, as known at expansion time.
+    pub fn help_text(&self) -> Option<String> {
+        self.find_lit("help")
+            .map(|lit| lit.value().lines().next().unwrap_or("").to_string())
+    }
+
+    /// First line of the about message, as known at expansion time.
+    pub fn about_text(&self) -> Option<String> {
+        self.about
+            .as_ref()
+            .and_then(|m| syn::parse2::<LitStr>(m.args.clone()).ok())
+            .or_else(|| self.find_lit("about"))
+            .map(|lit| lit.value().lines().next().unwrap_or("").to_string())
+    }
+
+    /// The environment variable backing the argument, if any.
+    pub fn env_name(&self) -> Option<String> {
+        self.find_lit("env").map(|lit| lit.value())
+    }
+
+    /// The expression naming the environment variable of the argument, if
+    /// any.
+    pub fn env_var(&self) -> Option<proc_macro2::TokenStream> {
+        self.find_method("env").map(|m| m.args.clone())
+    }
+
+    /// The variables read, in order, when the one of `env_name` is unset.
+    pub fn env_fallbacks(&self) -> &[LitStr] {
+        &self.env_fallbacks
+    }
+
+    /// Whether a warning tells that the variable read is deprecated when
+    /// the value of one of `env_fallbacks` is used.
+    pub fn warns_legacy_env(&self) -> bool {
+        self.warn_legacy_env.is_some()
+    }
+
+    /// The expression given to `default_value`, if any.
+    pub fn default_value(&self) -> Option<proc_macro2::TokenStream> {
+        self.find_method("default_value").map(|m| m.args.clone())
+    }
+
+    /// The value standing for "given without a value" on a positional
+    /// `Option<Option<T>>`, which has no other way to tell.
+    pub fn missing_value_marker(&self) -> Option<LitStr> {
+        match &*self.kind {
+            Kind::Arg(ty) if **ty == Ty::OptionOption && self.is_positional() => {
+                self.find_lit("default_missing_value")
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the field is a tuple or an array taking one value per element,
+    /// named with `value_names`.
+    pub fn has_fixed_values(&self) -> bool {
+        self.has_fixed_values
+    }
+
+    /// The function given to `complete_with`, if any.
+    pub fn complete_with(&self) -> Option<&syn::Expr> {
+        self.complete_with.as_ref().map(|(_, func)| func)
+    }
+
+    /// The expression given to `possible_values`, if any.
+    pub fn possible_values(&self) -> Option<proc_macro2::TokenStream> {
+        self.find_method("possible_values").map(|m| m.args.clone())
+    }
+
+    /// The expression given to `help_heading`, if any.
+    pub fn help_heading(&self) -> Option<proc_macro2::TokenStream> {
+        self.find_method("help_heading").map(|m| m.args.clone())
     }
 }
 
@@ -661,3 +3067,51 @@ fn process_author_str(author: &str) -> String {
 
     res
 }
+
+/// Whether `name(args)` lists the values of a method taking a slice, like
+/// `possible_values("json", "yaml")`, which is then given a static slice of
+/// them.
+fn is_literal_list(name: &Ident, args: &[Expr]) -> bool {
+    let list_method = match &*name.to_string() {
+        "possible_values" | "aliases" | "visible_aliases" | "value_names" => true,
+        _ => false,
+    };
+    list_method
+        && args.iter().all(|arg| match arg {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(_),
+                ..
+            }) => true,
+            _ => false,
+        })
+}
+
+/// The text of a doc comment line, with the comment markers removed, or an
+/// empty line standing for a paragraph break.
+fn doc_line(value: &str) -> String {
+    let text = value
+        .trim_start_matches("//!")
+        .trim_start_matches("///")
+        .trim_start_matches("/*!")
+        .trim_start_matches("/**")
+        .trim_end_matches("*/")
+        .trim();
+    if text.is_empty() {
+        "\n\n".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// The value of a `#[doc = ...]` attribute which is an expression, like
+/// `include_str!("help.md")`, instead of a string literal.
+fn doc_expr(attr: &syn::Attribute) -> Option<Expr> {
+    if !attr.path.is_ident("doc") || attr.parse_meta().is_ok() {
+        return None;
+    }
+    let parser = |input: syn::parse::ParseStream| {
+        input.parse::<syn::Token![=]>()?;
+        input.parse::<Expr>()
+    };
+    syn::parse::Parser::parse2(parser, attr.tokens.clone()).ok()
+}
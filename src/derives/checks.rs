@@ -0,0 +1,522 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Consistency checks of the derived `App`, run by `into_app()` in debug
+//! builds.
+//!
+//! Flattened structs are only known at runtime, so most mistakes can't be
+//! reported at expansion time.
+
+use proc_macro2;
+use proc_macro_error::abort;
+use syn::{self, spanned::Spanned};
+
+use super::{config, from_argmatches, plugin, sub_type, Attrs, Kind, ParserKind, Ty, VariantAttrs};
+
+/// Abort on positional arguments of `fields` clap could never fill: a
+/// required one after an optional one, or any one after a multi-valued one,
+/// be it a `Vec<T>`, an `Option<Vec<T>>` or an argument with `multiple`,
+/// `min_values` or `max_values` above 1.
+///
+/// Positionals with an explicit `index` or `last` are left to clap.
+pub fn check_positional_order(fields: &[(&syn::Field, Attrs)]) {
+    let mut optional: Option<&Attrs> = None;
+    let mut multiple: Option<(&Attrs, proc_macro2::Span)> = None;
+
+    for (field, attrs) in fields {
+        let ty = match &*attrs.kind() {
+            Kind::Arg(ty) => **ty,
+            _ => continue,
+        };
+        let parses_value = match *attrs.parser().kind {
+            ParserKind::FromOccurrences | ParserKind::FromFlag => false,
+            _ => ty != Ty::Bool,
+        };
+        if !attrs.is_positional()
+            || !parses_value
+            || attrs.has_method("index")
+            || attrs.has_method("last")
+        {
+            continue;
+        }
+
+        let name = attrs.cased_name().value();
+        let is_multiple = ty == Ty::Vec
+            || ty == Ty::OptionVec
+            || attrs.has_true_method("multiple")
+            || attrs.has_method("min_values")
+            || attrs
+                .find_method("max_values")
+                .map_or(false, |m| m.args.to_string() != "1");
+        if let Some((multiple, multiple_span)) = &multiple {
+            let kind = if is_multiple {
+                "multi-valued positional argument"
+            } else {
+                "positional argument"
+            };
+            abort!(
+                field.span(),
+                "{} `{}` comes after multi-valued positional `{}`",
+                kind,
+                name,
+                multiple.cased_name().value();
+                help = "only the last positional argument can take multiple values";
+                help = "move `{}` after `{}`, or give it `long` or `short`",
+                    multiple.cased_name().value(), name;
+                note = *multiple_span => "`{}` takes multiple values here",
+                    multiple.cased_name().value()
+            );
+        }
+        if attrs.is_required() {
+            if let Some(optional) = &optional {
+                abort!(
+                    field.span(),
+                    "required positional argument `{}` comes after optional positional `{}`",
+                    name,
+                    optional.cased_name().value();
+                    help = "declare it before the optional one, or make it optional"
+                );
+            }
+        } else if optional.is_none() {
+            optional = Some(attrs);
+        }
+        if is_multiple {
+            multiple = Some((attrs, field.span()));
+        }
+    }
+}
+
+/// Abort on digit short flags of `fields`, like `-1`, that clap would take
+/// for negative numbers: when the command allows negative numbers or leading
+/// hyphens, or when another argument accepts values starting with a hyphen.
+pub fn check_digit_shorts(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) {
+    let mut digit = None;
+    let mut hyphen_values = None;
+
+    for (field, attrs) in fields {
+        match &*attrs.kind() {
+            Kind::Arg(_) => (),
+            _ => continue,
+        }
+        match attrs.short_name() {
+            Some(short) if short.is_ascii_digit() && digit.is_none() => {
+                let span = attrs.find_lit("short").map_or(field.span(), |lit| lit.span());
+                digit = Some((span, short, attrs.cased_name().value()));
+            }
+            _ if attrs.has_true_method("allow_hyphen_values") && hyphen_values.is_none() => {
+                hyphen_values = Some(attrs.cased_name().value());
+            }
+            _ => (),
+        }
+    }
+
+    let (span, short, name) = match digit {
+        Some(digit) => digit,
+        None => return,
+    };
+    for setting in &["AllowNegativeNumbers", "AllowLeadingHyphen"] {
+        if parent_attribute.has_setting(setting) {
+            abort!(
+                span,
+                "short flag `-{}` of `{}` can't be told from a negative number",
+                short,
+                name;
+                help = "remove the `{}` setting, or pick a short flag that is not a digit", setting
+            );
+        }
+    }
+    if let Some(other) = hyphen_values {
+        abort!(
+            span,
+            "short flag `-{}` of `{}` can't be told from a negative number",
+            short,
+            name;
+            help = "`{}` allows values starting with a hyphen and would take it as a value", other
+        );
+    }
+}
+
+/// Generate the check functions of a struct, the assertions existing in
+/// debug builds only.
+pub fn gen_debug_fns_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let no_prefix = from_argmatches::no_prefix();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let args = gen_debug_args(fields, parent_attribute, &prefix);
+    let children = gen_debug_children(fields);
+    let command = parent_attribute.cased_name();
+    let defaults = gen_debug_defaults(&command, fields);
+    let check_fn = gen_debug_check_fn();
+
+    quote! {
+        pub fn __clap_debug_args(
+            args: &mut Vec<(&'static str, Option<char>, Option<&'static str>, bool, bool)>,
+            #prefix: #prefix_type,
+        ) {
+            #propagated_casing
+            #args
+        }
+
+        #[cfg(debug_assertions)]
+        pub fn __clap_debug_assert() {
+            let mut args = Vec::new();
+            Self::__clap_debug_args(&mut args, #no_prefix);
+            Self::__clap_debug_check(#command, &args);
+            #defaults
+            #children
+        }
+
+        #check_fn
+    }
+}
+
+/// Generate the check functions of an enum, every variant being a
+/// subcommand.
+pub fn gen_debug_fns_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let command = parent_attribute.cased_name();
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let no_prefix = from_argmatches::no_prefix();
+    let checks = subcommand_variants.map(|(variant, attrs, fields)| {
+        let name = attrs.cased_name();
+        let check = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                let args = gen_debug_args(fields, attrs, &prefix);
+                let children = gen_debug_children(fields);
+                let defaults = gen_debug_defaults(&name, fields);
+                quote! {
+                    let mut args: Vec<(&'static str, Option<char>, Option<&'static str>, bool, bool)> =
+                        Vec::new();
+                    {
+                        let args = &mut args;
+                        let #prefix = #no_prefix;
+                        #args
+                    }
+                    Self::__clap_debug_check(#name, &args);
+                    #defaults
+                    #children
+                }
+            }
+            (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                quote!( <#ty>::__clap_debug_assert(); )
+            }
+            _ => quote!(),
+        };
+
+        quote! {
+            assert!(
+                !names.contains(&#name),
+                "Command `{}`: subcommand name `{}` is used twice",
+                #command,
+                #name
+            );
+            names.push(#name);
+            #check
+        }
+    });
+    let check_fn = gen_debug_check_fn();
+
+    quote! {
+        pub fn __clap_debug_args(
+            args: &mut Vec<(&'static str, Option<char>, Option<&'static str>, bool, bool)>,
+            #prefix: #prefix_type,
+        ) {
+        }
+
+        #[cfg(debug_assertions)]
+        pub fn __clap_debug_assert() {
+            let mut names: Vec<&'static str> = Vec::new();
+            #( #checks )*
+        }
+
+        #check_fn
+    }
+}
+
+/// Generate `__clap_debug_check()`, asserting that the `(name, short, long,
+/// positional, required)` arguments of `command` are consistent, in debug
+/// builds only like its callers.
+fn gen_debug_check_fn() -> proc_macro2::TokenStream {
+    quote! {
+        #[cfg(debug_assertions)]
+        pub fn __clap_debug_check(
+            command: &str,
+            args: &[(&'static str, Option<char>, Option<&'static str>, bool, bool)],
+        ) {
+            for (i, arg) in args.iter().enumerate() {
+                for other in &args[..i] {
+                    assert!(
+                        arg.0 != other.0,
+                        "Command `{}`: argument name `{}` is used twice",
+                        command,
+                        arg.0
+                    );
+                    if let Some(short) = arg.1 {
+                        assert!(
+                            arg.1 != other.1,
+                            "Command `{}`: short `-{}` is used by both `{}` and `{}`",
+                            command,
+                            short,
+                            other.0,
+                            arg.0
+                        );
+                    }
+                    if let Some(long) = arg.2 {
+                        assert!(
+                            arg.2 != other.2,
+                            "Command `{}`: long `--{}` is used by both `{}` and `{}`",
+                            command,
+                            long,
+                            other.0,
+                            arg.0
+                        );
+                    }
+                }
+            }
+
+            let mut optional: Option<&str> = None;
+            for arg in args.iter().filter(|arg| arg.3) {
+                match optional {
+                    Some(optional) if arg.4 => panic!(
+                        "Command `{}`: required positional `{}` comes after optional positional `{}`",
+                        command,
+                        arg.0,
+                        optional
+                    ),
+                    None if !arg.4 => optional = Some(arg.0),
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+/// Code pushing the arguments of `fields` into `args`, named and flagged
+/// under the runtime `prefix` as `__clap_augment_app_prefixed()` declares
+/// them.
+fn gen_debug_args(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let entries = fields.iter().map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                quote!( <#ty>::__clap_debug_args(args, #flatten_prefix); )
+            }
+            Kind::Arg(_) => {
+                let name = attrs.cased_name();
+                let short = match attrs.short_name() {
+                    Some(short) => quote! {
+                        if #prefix.0.is_empty() { Some(#short) } else { None }
+                    },
+                    None => quote!(None),
+                };
+                let long = match attrs.long_name() {
+                    Some(_) => {
+                        let attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+                        let long = attrs.method_args("long");
+                        quote!( Some(#long) )
+                    }
+                    None => quote!(None),
+                };
+                let positional = attrs.is_positional();
+                let required = attrs.is_required();
+                quote! {
+                    args.push((
+                        Self::__clap_prefixed(#prefix, #name),
+                        #short,
+                        #long,
+                        #positional,
+                        #required,
+                    ));
+                }
+            }
+            Kind::Subcommand(_) | Kind::Skip(_) => quote!(),
+        }
+    });
+
+    quote!( #( #entries )* )
+}
+
+/// Code running the parsers of the arguments of `fields` against their
+/// `default_value` or `default_value_os`, which would otherwise only fail
+/// once the default is used.
+///
+/// Defaults are split on the value delimiter the way clap splits them.
+fn gen_debug_defaults(
+    command: &syn::LitStr,
+    fields: &[(&syn::Field, Attrs)],
+) -> proc_macro2::TokenStream {
+    let checks = fields.iter().filter_map(|(field, attrs)| {
+        let ty = match &*attrs.kind() {
+            Kind::Arg(ty) => **ty,
+            _ => return None,
+        };
+        if attrs.has_fixed_values() {
+            return None;
+        }
+        let default = match (attrs.default_value(), attrs.method_args("default_value_os")) {
+            (Some(default), _) => quote!( ::std::ffi::OsStr::new(#default) ),
+            (None, Some(default)) => quote!( ::std::ffi::OsStr::new(#default) ),
+            (None, None) => return None,
+        };
+        let convert_type = match ty {
+            Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
+            Ty::OptionOption | Ty::OptionVec => {
+                sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
+            }
+            _ => &field.ty,
+        };
+        let parser = attrs.parser();
+        let func = &parser.func;
+        let name = attrs.cased_name();
+        let parsed = match *parser.kind {
+            ParserKind::TryFromStr => quote! {
+                match value.to_str() {
+                    Some(value) => {
+                        #func(value).map(|_: #convert_type| ()).map_err(|e| e.to_string())
+                    }
+                    None => Err(String::from("invalid UTF-8")),
+                }
+            },
+            ParserKind::TryFromOsStr => quote! {
+                #func(value)
+                    .map(|_: #convert_type| ())
+                    .map_err(|e| e.to_string_lossy().into_owned())
+            },
+            _ => return None,
+        };
+
+        // clap splits the defaults of multi-valued arguments as it does
+        // their values
+        let delimited = match attrs.method_args("use_delimiter") {
+            Some(arg) => arg.to_string() == "true",
+            None => match ty {
+                Ty::Vec | Ty::OptionVec => true,
+                _ => attrs.has_method("require_delimiter"),
+            },
+        };
+        let values = if delimited {
+            let delimiter = match attrs.method_args("value_delimiter") {
+                Some(delimiter) => quote!( (#delimiter).to_string() ),
+                None => quote!( String::from(",") ),
+            };
+            quote! {
+                match default.to_str() {
+                    Some(default) => default
+                        .split(#delimiter.as_str())
+                        .map(::std::ffi::OsStr::new)
+                        .collect(),
+                    None => vec![default],
+                }
+            }
+        } else {
+            quote!( vec![default] )
+        };
+
+        Some(quote! {
+            {
+                let default: &::std::ffi::OsStr = #default;
+                let values: Vec<&::std::ffi::OsStr> = #values;
+                for value in values {
+                    if let Err(e) = #parsed {
+                        panic!(
+                            "Command `{}`: default value `{}` of `{}` is invalid: {}",
+                            #command,
+                            value.to_string_lossy(),
+                            #name,
+                            e
+                        );
+                    }
+                }
+            }
+        })
+    });
+
+    quote!( #( #checks )* )
+}
+
+/// Code checking the subcommands reachable through `fields`.
+fn gen_debug_children(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let children = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                Some(quote!( <#subcmd_type>::__clap_debug_assert(); ))
+            }
+            _ => None,
+        }
+    });
+
+    quote!( #( #children )* )
+}
+
+/// Check that the `conflicts_with` and `requires` of flattened fields name
+/// other flattened fields, and return the names of all the fields involved,
+/// which get a group made of their arguments.
+pub fn check_flatten_relations(fields: &[(&syn::Field, Attrs)]) -> Vec<syn::Ident> {
+    let flattened: Vec<_> = fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::FlattenStruct => Some((field.ident.clone().unwrap(), attrs)),
+                _ => None,
+            }
+        })
+        .collect();
+
+    let mut grouped = Vec::new();
+    for (name, attrs) in &flattened {
+        for (method, other) in attrs.flatten_relations() {
+            let target = match flattened.iter().find(|(n, _)| *n == other.value()) {
+                Some((target, _)) => target,
+                None => abort!(
+                    other.span(),
+                    "`{}` is not a flattened field", other.value();
+                    help = "`{}` on a flattened field names another flattened field \
+                        of the same struct", method;
+                ),
+            };
+            if target == name {
+                abort!(other.span(), "a flattened field can't refer to itself");
+            }
+            for ident in &[name, target] {
+                if !grouped.contains(*ident) {
+                    grouped.push((*ident).clone());
+                }
+            }
+        }
+    }
+    grouped
+}
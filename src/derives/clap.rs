@@ -11,21 +11,56 @@
 // This work was derived from Structopt (https://github.com/TeXitoi/structopt)
 // commit#ea76fa1b1b273e65e3b0b1046643715b49bec51f which is licensed under the
 // MIT/Apache 2.0 license.
+use heck::{ShoutySnakeCase, SnakeCase};
 use proc_macro2;
 use proc_macro_error::{abort, abort_call_site, set_dummy};
 use syn::{self, punctuated, spanned::Spanned, token};
 
-use super::{from_argmatches, into_app, sub_type, Attrs, Kind, Name, ParserKind, Ty};
+use super::{
+    argv, checks, color, completion, config, docs, fixed_values, from_argmatches, fuzz, into_app,
+    is_integer_ty, localize, nonzero_integer_ty, occurrences, plugin, prompt, sub_type, untagged,
+    Attrs, Kind, ParserKind, Ty, VariantAttrs,
+};
+
+/// Error message of a `secret` argument failing to parse, in place of the
+/// parser's own one which may quote the value.
+pub const SECRET_ERROR: &str = "invalid value (not shown, the argument is secret)";
+
+/// Code asserting that `ty` derives `Clap`, through a function named
+/// `assert_fn` spanned on `ty`.
+///
+/// Without it, a type missing the derive is reported as a wall of missing
+/// items inside the generated code; this way the first error points at the
+/// offending field and reads as what to do.
+fn gen_derive_assertion(ty: &syn::Type, assert_fn: &str) -> proc_macro2::TokenStream {
+    let assert_fn = syn::Ident::new(assert_fn, ty.span());
+    quote_spanned! { ty.span()=>
+        {
+            fn #assert_fn<T: ::clap::Clap>() {}
+            #assert_fn::<#ty>();
+        }
+    }
+}
 
 /// Generate a block of code to add arguments/subcommands corresponding to
 /// the `fields` to an app.
-fn gen_app_augmentation(
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+///
+/// With a `prefix` variable, the ids, long flags and environment variables
+/// of the arguments are prefixed with its value at runtime.
+///
+/// Arguments, groups and subcommands are added in the order of the source,
+/// so the help generated with `source_order` lists them the same way on
+/// every build.
+pub fn gen_app_augmentation(
+    fields: &[(&syn::Field, Attrs)],
     app_var: &syn::Ident,
     parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
 ) -> proc_macro2::TokenStream {
-    let mut subcmds = fields.iter().filter_map(|field| {
-        let attrs = Attrs::from_field(&field, parent_attribute.casing());
+    checks::check_positional_order(fields);
+    checks::check_digit_shorts(fields, parent_attribute);
+
+    let mut subcmds = fields.iter().filter_map(|(field, attrs)| {
         let kind = attrs.kind();
         if let Kind::Subcommand(ty) = &*kind {
             let subcmd_type = match (**ty, sub_type(&field.ty)) {
@@ -42,9 +77,19 @@ fn gen_app_augmentation(
                 }
             };
 
+            let casing_prefix = match prefix {
+                Some(prefix) => quote!( Self::__clap_casing_prefix(#prefix) ),
+                None => from_argmatches::no_prefix(),
+            };
             let span = field.span();
+            let assertion = gen_derive_assertion(subcmd_type, "subcommand_type_must_derive_clap");
             let ts = quote! {
-                let #app_var = <#subcmd_type>::augment_app( #app_var );
+                #assertion
+                let #app_var = <#subcmd_type>::__clap_augment_app_prefixed(
+                    #app_var,
+                    words,
+                    #casing_prefix,
+                );
                 #required
             };
             Some((span, ts))
@@ -60,15 +105,61 @@ fn gen_app_augmentation(
         );
     }
 
-    let args = fields.iter().filter_map(|field| {
-        let attrs = Attrs::from_field(field, parent_attribute.casing());
+    let grouped = checks::check_flatten_relations(fields);
+
+    let args = fields.iter().filter_map(|(field, attrs)| {
         let kind = attrs.kind();
         match &*kind {
             Kind::Subcommand(_) | Kind::Skip(_) => None,
             Kind::FlattenStruct => {
                 let ty = &field.ty;
+                let env_prefix = config::gen_env_prefix(app_var, ty, &attrs);
+                let assertion = gen_derive_assertion(ty, "flattened_type_must_derive_clap");
+                let augment = match config::gen_flatten_prefix(prefix, &attrs) {
+                    Some(prefix) => quote_spanned! { kind.span()=>
+                        <#ty>::__clap_augment_app_prefixed(#app_var, words, #prefix)
+                    },
+                    None => quote_spanned! { kind.span()=>
+                        <#ty>::__clap_augment_app_with(#app_var, words)
+                    },
+                };
+                let group = if grouped.contains(field.ident.as_ref().unwrap()) {
+                    let prefixed = |name: &syn::LitStr| match prefix {
+                        Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #name) ),
+                        None => quote!(#name),
+                    };
+                    let field_name = field.ident.as_ref().unwrap().to_string();
+                    let group_name = prefixed(&syn::LitStr::new(&field_name, field.span()));
+                    let flatten_prefix =
+                        config::gen_flatten_prefix(prefix, &attrs)
+                            .unwrap_or_else(from_argmatches::no_prefix);
+                    let relations = attrs.flatten_relations().iter().map(|(method, other)| {
+                        let other = prefixed(other);
+                        quote!( .#method(#other) )
+                    });
+                    quote_spanned! { kind.span()=>
+                        let #app_var = #app_var.group(
+                            ::clap::ArgGroup::with_name(#group_name)
+                                .multiple(true)
+                                .args(&<#ty>::__clap_arg_ids(#flatten_prefix))
+                                #( #relations )*
+                        );
+                    }
+                } else {
+                    quote!()
+                };
+                let hide_short_help = attrs.hide_short_help().map(|_| {
+                    let flatten_prefix =
+                        config::gen_flatten_prefix(prefix, &attrs)
+                            .unwrap_or_else(from_argmatches::no_prefix);
+                    gen_hide_short_help(app_var, quote!( <#ty>::__clap_arg_ids(#flatten_prefix) ))
+                });
                 Some(quote_spanned! { kind.span()=>
-                    let #app_var = <#ty>::augment_app(#app_var);
+                    #assertion
+                    let #app_var = #augment;
+                    #group
+                    #hide_short_help
+                    #env_prefix
                     let #app_var = if <#ty>::is_subcommand() {
                         #app_var.setting(::clap::AppSettings::SubcommandRequiredElseHelp)
                     } else {
@@ -77,34 +168,105 @@ fn gen_app_augmentation(
                 })
             }
             Kind::Arg(ty) => {
-                let convert_type = match **ty {
-                    Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
-                    Ty::OptionOption | Ty::OptionVec => {
-                        sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
-                    }
-                    _ => &field.ty,
-                };
+                let convert_type = convert_type(field, &attrs, **ty);
 
                 let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
                 let flag = *attrs.parser().kind == ParserKind::FromFlag;
 
                 let parser = attrs.parser();
                 let func = &parser.func;
+                let skip_marker = match attrs.missing_value_marker() {
+                    Some(marker) => quote!( if s == #marker { return Ok(()); } ),
+                    None => quote!(),
+                };
+                // Values equal to the one of the environment variable are
+                // left to `__clap_check_occurrences()`, which tells them apart
+                // from the command line ones by their occurrences to name the
+                // variable in their errors.
+                let (env_var, env_deferred, env_deferred_os) = match attrs.gen_env_var(prefix) {
+                    Some(env) => (
+                        quote!( let env = ::std::string::String::from(#env); ),
+                        quote! {
+                            if ::std::env::var_os(&env)
+                                .map_or(false, |v| v == ::std::ffi::OsStr::new(&s))
+                            {
+                                return Ok(());
+                            }
+                        },
+                        quote! {
+                            if ::std::env::var_os(&env).map_or(false, |v| v.as_os_str() == s) {
+                                return Ok(());
+                            }
+                        },
+                    ),
+                    None => (quote!(), quote!(), quote!()),
+                };
+                let parse_error = gen_parse_error(&attrs, convert_type);
                 let validator = match *parser.kind {
+                    ParserKind::TryFromStr if attrs.is_secret() => quote_spanned! { func.span()=>
+                        .validator({
+                            #env_var
+                            move |s| {
+                                #skip_marker
+                                #env_deferred
+                                #func(s.as_str())
+                                .map(|_: #convert_type| ())
+                                .map_err(|_| String::from(#SECRET_ERROR))
+                            }
+                        })
+                    },
                     ParserKind::TryFromStr => quote_spanned! { func.span()=>
-                        .validator(|s| {
-                            #func(s.as_str())
-                            .map(|_: #convert_type| ())
-                            .map_err(|e| e.to_string())
+                        .validator({
+                            #env_var
+                            move |s| {
+                                #skip_marker
+                                #env_deferred
+                                #func(s.as_str())
+                                .map(|_: #convert_type| ())
+                                .map_err(|e| #parse_error)
+                            }
+                        })
+                    },
+                    ParserKind::TryFromOsStr if attrs.is_secret() => quote_spanned! { func.span()=>
+                        .validator_os({
+                            #env_var
+                            move |s| {
+                                #skip_marker
+                                #env_deferred_os
+                                #func(&s)
+                                .map(|_: #convert_type| ())
+                                .map_err(|_| ::std::ffi::OsString::from(#SECRET_ERROR))
+                            }
                         })
                     },
                     ParserKind::TryFromOsStr => quote_spanned! { func.span()=>
-                        .validator_os(|s| #func(&s).map(|_: #convert_type| ()))
+                        .validator_os({
+                            #env_var
+                            move |s| {
+                                #skip_marker
+                                #env_deferred_os
+                                #func(&s)
+                                .map(|_: #convert_type| ())
+                            }
+                        })
                     },
                     _ => quote!(),
                 };
+                let secret = if attrs.is_secret() {
+                    quote!( .hide_default_value(true).hide_env_values(true) )
+                } else {
+                    quote!()
+                };
 
                 let modifier = match **ty {
+                    Ty::Bool if attrs.is_explicit_bool() => quote_spanned! { ty.span()=>
+                        .takes_value(true)
+                        .min_values(0)
+                        .max_values(1)
+                        .require_equals(true)
+                        .possible_values(&["true", "false"])
+                    },
+
                     Ty::Bool => quote!(),
 
                     Ty::Option => quote_spanned! { ty.span()=>
@@ -143,75 +305,589 @@ fn gen_app_augmentation(
                     },
 
                     Ty::Other => {
-                        let required = !attrs.has_method("default_value");
+                        let required = attrs.is_required();
+                        let shown_default = if parent_attribute.show_defaults()
+                            && attrs.is_explicitly_optional()
+                            && !attrs.has_method("default_value")
+                        {
+                            let field_ty = &field.ty;
+                            quote_spanned! { field_ty.span()=>
+                                .default_value(Self::__clap_interned(
+                                    <#field_ty as Default>::default().to_string(),
+                                ))
+                            }
+                        } else {
+                            quote!()
+                        };
                         quote_spanned! { ty.span()=>
                             .takes_value(true)
                             .required(#required)
+                            #shown_default
                             #validator
                         }
                     }
                 };
 
+                let value_name = match (**ty, attrs.derived_value_name(parent_attribute)) {
+                    (Ty::Bool, _) => quote!(),
+                    (Ty::Other, _) if occurrences || flag => quote!(),
+                    (_, Some(value_name)) => quote!( .value_name(#value_name) ),
+                    (_, None) => quote!(),
+                };
+
+                let quiet = gen_quiet_arg(fields, app_var, &attrs, prefix);
+                let legacy_alias = attrs.legacy_long_alias(parent_attribute);
                 let name = attrs.cased_name();
+                // Shorts can't be prefixed: a struct flattened twice would
+                // declare them twice, so they're dropped under a prefix.
+                let (name, legacy_alias, short, attrs) = match prefix {
+                    Some(prefix) => {
+                        let mut attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+                        let short = attrs.take_method("short").map(|short| {
+                            quote! {
+                                let arg = if #prefix.0.is_empty() { arg #short } else { arg };
+                            }
+                        });
+                        (
+                            quote!( Self::__clap_prefixed(#prefix, #name) ),
+                            legacy_alias.map(|alias| {
+                                quote!( .alias(Self::__clap_prefixed(#prefix, #alias)) )
+                            }),
+                            short,
+                            attrs,
+                        )
+                    }
+                    None => (
+                        quote!(#name),
+                        legacy_alias.map(|alias| quote!( .alias(#alias) )),
+                        None,
+                        attrs.clone().with_env_var(None),
+                    ),
+                };
                 let methods = attrs.field_methods();
+                let required_unless = attrs.required_unless().iter().map(|(ident, names)| {
+                    let ids = names.iter().map(|name| {
+                        gen_field_arg_id(fields, name, ident, prefix)
+                    });
+                    let method = if ident == "required_unless_any" {
+                        quote_spanned!(ident.span()=> required_unless_one)
+                    } else {
+                        quote_spanned!(ident.span()=> required_unless_all)
+                    };
+                    quote!( .#method(&[ #( #ids ),* ]) )
+                });
+                let default_value_ifs = attrs.default_value_ifs().iter().map(|(ident, conditions)| {
+                    let conditions = conditions.iter().map(|(field, value, default)| {
+                        let id = gen_field_arg_id(fields, field, ident, prefix);
+                        let value = match value {
+                            Some(value) => quote!( Some(#value) ),
+                            None => quote!(None),
+                        };
+                        quote!( (#id, #value, #default) )
+                    });
+                    quote_spanned!(ident.span()=> .default_value_ifs(&[ #( #conditions ),* ]))
+                });
+                let relations_if = attrs.relations_if().iter().map(|(ident, first, second)| {
+                    if ident == "requires_if" {
+                        let id = gen_field_or_arg_id(fields, second, prefix);
+                        quote_spanned!(ident.span()=> .requires_if(#first, #id))
+                    } else {
+                        let id = gen_field_or_arg_id(fields, first, prefix);
+                        quote_spanned!(ident.span()=> .required_if(#id, #second))
+                    }
+                });
+                let doc_help = docs::gen_doc_exprs(app_var, Some(&name), &attrs);
+                let localized_help = localize::gen_localized_help(app_var, &name, &attrs, prefix);
+                let arg = quote! {
+                    ::clap::Arg::with_name(#name)
+                        #modifier
+                        #value_name
+                        #secret
+                        #methods
+                        #( #required_unless )*
+                        #( #default_value_ifs )*
+                        #( #relations_if )*
+                        #legacy_alias
+                };
+                let arg = match short {
+                    Some(short) => quote!({
+                        let arg = #arg;
+                        #short
+                        arg
+                    }),
+                    None => arg,
+                };
+                let arg = match attrs.mut_arg() {
+                    Some(mut_arg) => quote_spanned!(mut_arg.span()=> (#mut_arg)(#arg)),
+                    None => arg,
+                };
 
                 Some(quote_spanned! { field.span()=>
-                    let #app_var = #app_var.arg(
-                        ::clap::Arg::with_name(#name)
-                            #modifier
-                            #methods
-                    );
+                    let #app_var = #app_var.arg(#arg);
+                    #doc_help
+                    #localized_help
+                    #quiet
                 })
             }
         }
     });
 
+    let requires_all = gen_requires_all(fields, app_var, parent_attribute, prefix);
+    let groups = gen_groups(fields, app_var, parent_attribute, prefix);
+
     let app_methods = parent_attribute.top_level_methods();
+    let doc_about = docs::gen_doc_exprs(app_var, None, parent_attribute);
+    let localized_about = localize::gen_localized_about(app_var, parent_attribute, prefix);
+    let config_file_arg = config::gen_config_file_arg(app_var, parent_attribute);
+    let propagated_casing = prefix
+        .map(|prefix| from_argmatches::gen_propagated_casing(prefix, parent_attribute))
+        .unwrap_or_default();
     quote! {{
+        #propagated_casing
         let #app_var = #app_var#app_methods;
+        #doc_about
+        #localized_about
+        #config_file_arg
         #( #args )*
+        #requires_all
+        #groups
         #subcmd
         #app_var
     }}
 }
 
+/// The id of the argument held by the field `name` among `fields`, named
+/// by the attribute `attr`, prefixed with the value of the `prefix` variable,
+/// if any.
+pub fn gen_field_arg_id(
+    fields: &[(&syn::Field, Attrs)],
+    name: &syn::LitStr,
+    attr: &syn::Ident,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    match field_arg_id(fields, name, prefix) {
+        Some(id) => id,
+        None => abort!(
+            name.span(),
+            "`{}` is not an argument of this struct", name.value();
+            help = "`{}` names fields holding arguments", attr
+        ),
+    }
+}
+
+/// Generate the `-q` flag going with a `verbosity` field, taking the short
+/// and long of its `-v` flag: `-q` along `-v`, `--quiet` along `--verbose`
+/// and `--<long>-quiet` along another long.
+///
+/// The flag is checked against the other fields of the struct unless
+/// `mut_quiet` is given, which is then run on it.
+fn gen_quiet_arg(
+    fields: &[(&syn::Field, Attrs)],
+    app_var: &syn::Ident,
+    attrs: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    if !attrs.is_verbosity() {
+        return quote!();
+    }
+
+    let (id, long, short) = (attrs.quiet_id(), attrs.quiet_long(), attrs.quiet_short());
+    if attrs.mut_quiet().is_none() {
+        for (_, other) in fields {
+            if short.is_some() && other.short_name() == short {
+                abort!(
+                    other.method_args("short").span(),
+                    "`-{}` is taken already by the quiet flag of `verbosity`", short.unwrap();
+                    help = "use `mut_quiet` to give the quiet flag another short";
+                    note = attrs.verbosity_span() => "`verbosity` given here";
+                );
+            }
+            if other.long_name().map_or(false, |other| other == long.value()) {
+                abort!(
+                    other.method_args("long").span(),
+                    "`--{}` is taken already by the quiet flag of `verbosity`", long.value();
+                    help = "use `mut_quiet` to give the quiet flag another long";
+                    note = attrs.verbosity_span() => "`verbosity` given here";
+                );
+            }
+        }
+    }
+
+    let short = short.map(|c| syn::LitChar::new(c, long.span()));
+    let (id, long, short) = match prefix {
+        Some(prefix) => (
+            quote!( Self::__clap_prefixed(#prefix, #id) ),
+            quote!( Self::__clap_prefixed(#prefix, #long) ),
+            short.map(|c| quote!( let quiet = if #prefix.0.is_empty() { quiet.short(#c) } else { quiet }; )),
+        ),
+        None => (
+            quote!(#id),
+            quote!(#long),
+            short.map(|c| quote!( let quiet = quiet.short(#c); )),
+        ),
+    };
+    let mut_quiet = attrs
+        .mut_quiet()
+        .map(|mut_quiet| quote_spanned!(mut_quiet.span()=> let quiet = (#mut_quiet)(quiet); ));
+    let localized_help = attrs.help_key().map(|key| {
+        let key = format!("{}.quiet", key.value());
+        let prefix = localize::localize_prefix(prefix);
+        quote! {
+            let quiet = match Self::__clap_localize(#prefix, #key) {
+                Some(help) => quiet.help(help),
+                None => quiet,
+            };
+        }
+    });
+
+    quote_spanned! { attrs.verbosity_span()=>
+        let quiet = ::clap::Arg::with_name(#id)
+            .long(#long)
+            .multiple_occurrences(true)
+            .help("Less output per occurrence");
+        #short
+        #localized_help
+        #mut_quiet
+        let #app_var = #app_var.arg(quiet);
+    }
+}
+
+/// Like `gen_field_arg_id`, but a name matching no argument field is kept
+/// as the id of an argument declared elsewhere, e.g. in a flattened struct.
+fn gen_field_or_arg_id(
+    fields: &[(&syn::Field, Attrs)],
+    name: &syn::LitStr,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    field_arg_id(fields, name, prefix).unwrap_or_else(|| quote!(#name))
+}
+
+fn field_arg_id(
+    fields: &[(&syn::Field, Attrs)],
+    name: &syn::LitStr,
+    prefix: Option<&syn::Ident>,
+) -> Option<proc_macro2::TokenStream> {
+    let (_, attrs) = fields
+        .iter()
+        .find(|(field, _)| field.ident.as_ref().map_or(false, |ident| *ident == name.value()))
+        .filter(|(_, attrs)| match *attrs.kind() {
+            Kind::Arg(_) => true,
+            _ => false,
+        })?;
+    let id = attrs.cased_name();
+    Some(match prefix {
+        Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #id) ),
+        None => quote!(#id),
+    })
+}
+
+/// Generate the module asked for with `arg_ids` on top of a struct, holding
+/// an `ARG_<FIELD>` constant with the id of each of its arguments.
+///
+/// The ids of a flattened struct are in its own module, a `<field>(id)`
+/// function giving them the prefix they get at the flatten site, if any.
+fn gen_arg_ids_mod_for_struct(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let mod_name = match arg_ids_mod_name(name, parent_attribute) {
+        Some(mod_name) => mod_name,
+        None => return quote!(),
+    };
+    let items = gen_arg_ids(&quote!(super::#name), fields);
+
+    quote! {
+        /// The ids of the arguments of the command.
+        #[allow(dead_code)]
+        #vis mod #mod_name {
+            #( #items )*
+        }
+    }
+}
+
+/// Generate the module asked for with `arg_ids` on top of an enum, holding a
+/// module named after each variant with fields, like the one of a struct.
+fn gen_arg_ids_mod_for_enum(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let mod_name = match arg_ids_mod_name(name, parent_attribute) {
+        Some(mod_name) => mod_name,
+        None => return quote!(),
+    };
+    let modules = variants.iter().filter_map(|(variant, _, fields)| {
+        let fields = fields.as_ref()?;
+        let snake = variant.ident.to_string().to_snake_case();
+        let module = syn::parse_str::<syn::Ident>(&snake)
+            .or_else(|_| syn::parse_str::<syn::Ident>(&format!("r#{}", snake)))
+            .unwrap_or_else(|_| {
+                abort!(
+                    variant.ident.span(),
+                    "`arg_ids` can't name a module `{}`", snake
+                )
+            });
+        let items = gen_arg_ids(&quote!(super::super::#name), fields);
+        Some(quote! {
+            /// The ids of the arguments of the subcommand.
+            pub mod #module {
+                #( #items )*
+            }
+        })
+    });
+
+    quote! {
+        /// The ids of the arguments of the subcommands.
+        #[allow(dead_code)]
+        #vis mod #mod_name {
+            #( #modules )*
+        }
+    }
+}
+
+/// The name of the module asked for with `arg_ids`: the one given there,
+/// `<name>_args` by default.
+fn arg_ids_mod_name(name: &syn::Ident, parent_attribute: &Attrs) -> Option<syn::Ident> {
+    let arg_ids = parent_attribute.arg_ids()?;
+    let mod_name = match &**arg_ids {
+        Some(mod_name) => mod_name.clone(),
+        None => format!("{}_args", name.to_string().to_snake_case()),
+    };
+    match syn::parse_str::<syn::Ident>(&mod_name) {
+        Ok(_) => Some(syn::Ident::new(&mod_name, arg_ids.span())),
+        Err(_) => abort!(
+            arg_ids.span(),
+            "`{}` isn't a valid module name", mod_name;
+            help = "use `arg_ids = \"...\"` to name the module"
+        ),
+    }
+}
+
+/// The `ARG_<FIELD>` constants of the arguments in `fields` and the
+/// functions prefixing the ids of the flattened ones, `ty` being the path of
+/// the type holding the fields from there.
+fn gen_arg_ids(
+    ty: &proc_macro2::TokenStream,
+    fields: &[(&syn::Field, Attrs)],
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            let field_name = field.ident.as_ref().unwrap();
+            match *attrs.kind() {
+                Kind::Arg(_) => {
+                    let const_name = syn::Ident::new(
+                        &format!(
+                            "ARG_{}",
+                            field_name
+                                .to_string()
+                                .trim_start_matches("r#")
+                                .to_shouty_snake_case()
+                        ),
+                        field.span(),
+                    );
+                    let id = attrs.cased_name();
+                    Some(quote!( pub const #const_name: &str = #id; ))
+                }
+                Kind::FlattenStruct => {
+                    let id = match attrs.prefix() {
+                        Some(own) => {
+                            let own = &**own;
+                            quote!( <#ty>::__clap_joined(#own, "-", id) )
+                        }
+                        None => quote!(id),
+                    };
+                    Some(quote! {
+                        /// The id of an argument of the struct flattened
+                        /// here, given its id in that struct.
+                        pub fn #field_name(id: &'static str) -> &'static str {
+                            #id
+                        }
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Generate the code making the arguments of each `requires_all` set on
+/// top of the struct require each other.
+fn gen_requires_all(
+    fields: &[(&syn::Field, Attrs)],
+    app_var: &syn::Ident,
+    parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let sets = parent_attribute.requires_all().iter().map(|(ident, names)| {
+        if names.len() < 2 {
+            abort!(ident.span(), "`requires_all` needs at least two fields");
+        }
+        let ids: Vec<_> = names
+            .iter()
+            .map(|name| gen_field_arg_id(fields, name, ident, prefix))
+            .collect();
+
+        let requirements = ids.iter().enumerate().map(|(i, id)| {
+            let others = ids
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| other);
+            quote! {
+                let #app_var = #app_var.mut_arg(#id, |arg| arg.requires_all(&[ #( #others ),* ]));
+            }
+        });
+        quote!( #( #requirements )* )
+    });
+
+    quote!( #( #sets )* )
+}
+
+/// Generate the groups declared with `group(...)` on top of the struct,
+/// whose `requires` and `conflicts_with` name either fields holding arguments
+/// or other groups of the struct.
+fn gen_groups(
+    fields: &[(&syn::Field, Attrs)],
+    app_var: &syn::Ident,
+    parent_attribute: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let groups = parent_attribute.groups();
+    let prefixed = |name: &syn::LitStr| match prefix {
+        Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #name) ),
+        None => quote!(#name),
+    };
+    let arg_or_group_id = |name: &syn::LitStr, ident: &syn::Ident| {
+        if groups.iter().any(|(_, spec)| spec.name.value() == name.value()) {
+            prefixed(name)
+        } else {
+            gen_field_arg_id(fields, name, ident, prefix)
+        }
+    };
+
+    let groups = groups.iter().map(|(ident, spec)| {
+        let name = prefixed(&spec.name);
+        let args = spec
+            .args
+            .iter()
+            .map(|arg| gen_field_arg_id(fields, arg, ident, prefix));
+        let multiple = spec.multiple.iter();
+        let required = spec.required.iter();
+        let requires = spec.requires.iter().map(|other| arg_or_group_id(other, ident));
+        let conflicts_with = spec
+            .conflicts_with
+            .iter()
+            .map(|other| arg_or_group_id(other, ident));
+        quote_spanned! { ident.span()=>
+            let #app_var = #app_var.group(
+                ::clap::ArgGroup::with_name(#name)
+                    .args(&[ #( #args ),* ])
+                    #( .multiple(#multiple) )*
+                    #( .required(#required) )*
+                    #( .requires(#requires) )*
+                    #( .conflicts_with(#conflicts_with) )*
+            );
+        }
+    });
+
+    quote!( #( #groups )* )
+}
+
 fn gen_augment_app_fn(
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    fields: &[(&syn::Field, Attrs)],
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     let app_var = syn::Ident::new("app", proc_macro2::Span::call_site());
-    let augmentation = gen_app_augmentation(fields, &app_var, parent_attribute);
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let (prefix_type, no_prefix) = (from_argmatches::prefix_type(), from_argmatches::no_prefix());
+    let augmentation = gen_app_augmentation(fields, &app_var, parent_attribute, Some(&prefix));
+    let long_help_sections = docs::gen_long_help_sections(&app_var, Some(fields), parent_attribute);
+    let hide_short_help = parent_attribute
+        .hide_short_help()
+        .map(|_| gen_hide_short_help(&app_var, quote!( Self::__clap_arg_ids(#prefix) )));
     quote! {
         pub fn augment_app<'b>(
             #app_var: ::clap::App<'b>
         ) -> ::clap::App<'b> {
-            #augmentation
+            Self::__clap_augment_app_with(#app_var, None)
+        }
+        /// Same as `augment_app`, the subcommands of `lazy` enums that are
+        /// not among `words` being left without arguments.
+        pub fn __clap_augment_app_with<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+        ) -> ::clap::App<'b> {
+            Self::__clap_augment_app_prefixed(#app_var, words, #no_prefix)
+        }
+        /// Same as `__clap_augment_app_with`, the ids, long flags and
+        /// environment variables of the arguments being prefixed with
+        /// `prefix` unless it is empty.
+        pub fn __clap_augment_app_prefixed<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+            #prefix: #prefix_type,
+        ) -> ::clap::App<'b> {
+            let #prefix = Self::__clap_localizing_prefix(#prefix);
+            let #app_var = #augmentation;
+            #hide_short_help
+            #long_help_sections
+            #app_var
         }
     }
 }
 
+/// Code hiding the arguments with the ids returned by `ids` from the short
+/// help of the app in `app_var`, leaving them in the long one.
+fn gen_hide_short_help(
+    app_var: &syn::Ident,
+    ids: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        let #app_var = #ids
+            .into_iter()
+            .fold(#app_var, |app, id| app.mut_arg(id, |arg| arg.hidden_short_help(true)));
+    }
+}
+
 fn gen_augment_app_for_enum(
-    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    name: &syn::Ident,
+    variants: &[VariantAttrs],
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     use syn::Fields::*;
 
-    let subcommands = variants.iter().map(|variant| {
-        let attrs = Attrs::from_struct(
-            variant.span(),
-            &variant.attrs,
-            Name::Derived(variant.ident.clone()),
-            parent_attribute.casing(),
-        );
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let (prefix_type, no_prefix) = (from_argmatches::prefix_type(), from_argmatches::no_prefix());
+    let plugin = plugin::find_plugin_variant(variants).map(|_| {
+        quote!( .setting(::clap::AppSettings::AllowExternalSubcommands) )
+    });
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let subcommands = subcommand_variants.map(|(variant, attrs, fields)| {
         let app_var = syn::Ident::new("subcommand", proc_macro2::Span::call_site());
-        let arg_block = match variant.fields {
-            Named(ref fields) => gen_app_augmentation(&fields.named, &app_var, &attrs),
-            Unit => quote!( #app_var ),
-            Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
+        let arg_block = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                gen_app_augmentation(fields, &app_var, attrs, Some(&prefix))
+            }
+            (Unit, _) => quote!( #app_var ),
+            (Unnamed(syn::FieldsUnnamed { ref unnamed, .. }), _) if unnamed.len() == 1 => {
                 let ty = &unnamed[0];
+                let assertion = gen_derive_assertion(&ty.ty, "variant_type_must_derive_clap");
                 quote_spanned! { ty.span() =>
                     {
-                        let #app_var = <#ty>::augment_app(#app_var);
+                        #assertion
+                        let #app_var = <#ty>::__clap_augment_app_prefixed(
+                            #app_var,
+                            words,
+                            Self::__clap_casing_prefix(#prefix),
+                        );
                         if <#ty>::is_subcommand() {
                             #app_var.setting(
                                 ::clap::AppSettings::SubcommandRequiredElseHelp
@@ -222,87 +898,268 @@ fn gen_augment_app_for_enum(
                     }
                 }
             }
-            Unnamed(..) => abort_call_site!("{}: tuple enums are not supported", variant.ident),
+            _ => abort_call_site!("{}: tuple enums are not supported", variant.ident),
         };
 
-        let name = attrs.cased_name();
+        let name = attrs.gen_runtime_cased_name(&prefix);
         let from_attrs = attrs.top_level_methods();
+        let doc_about = docs::gen_doc_exprs(&app_var, None, attrs);
+        let localized_about = localize::gen_localized_about(&app_var, attrs, Some(&prefix));
+        let long_help_sections =
+            docs::gen_long_help_sections(&app_var, fields.as_ref().map(Vec::as_slice), attrs);
+        let help_template = match (attrs.help_template(), &variant.fields) {
+            (Some(template), _) => quote!( let #app_var = #app_var.help_template(#template); ),
+            (None, Unnamed(syn::FieldsUnnamed { ref unnamed, .. })) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                quote!( let #app_var = <#ty>::__clap_apply_help_template(#app_var); )
+            }
+            (None, _) => quote!(),
+        };
+
+        if let Some(lazy) = attrs.lazy() {
+            abort!(lazy.span(), "`lazy` is only allowed on top of enums");
+        }
+        let arg_block = if parent_attribute.lazy().is_some() {
+            let aliases = attrs.aliases();
+            quote! {
+                if words.map_or(true, |words| {
+                    words
+                        .iter()
+                        .any(|word| word == #name #( || word == #aliases )*)
+                }) {
+                    #arg_block
+                } else {
+                    #app_var
+                }
+            }
+        } else {
+            arg_block
+        };
 
         quote! {
             .subcommand({
                 let #app_var = ::clap::App::new(#name);
                 let #app_var = #arg_block;
-                #app_var#from_attrs
+                let #app_var = #app_var#from_attrs;
+                #help_template
+                #doc_about
+                #localized_about
+                #long_help_sections
+                #app_var
             })
         }
     });
 
+    let app_var = syn::Ident::new("app", proc_macro2::Span::call_site());
     let app_methods = parent_attribute.top_level_methods();
+    let doc_about = docs::gen_doc_exprs(&app_var, None, parent_attribute);
+    let localized_about = localize::gen_localized_about(&app_var, parent_attribute, Some(&prefix));
+    let long_help_sections = docs::gen_long_help_sections(&app_var, None, parent_attribute);
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let registered = plugin::gen_registered_subcommands(
+        name,
+        &app_var,
+        plugin::find_registered_variant(variants),
+    );
 
     quote! {
         pub fn augment_app<'b>(
-            app: ::clap::App<'b>
+            #app_var: ::clap::App<'b>
         ) -> ::clap::App<'b> {
-            app #app_methods #( #subcommands )*
+            Self::__clap_augment_app_with(#app_var, None)
+        }
+        /// Same as `augment_app`, the subcommands of `lazy` enums that are
+        /// not among `words` being left without arguments.
+        pub fn __clap_augment_app_with<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+        ) -> ::clap::App<'b> {
+            Self::__clap_augment_app_prefixed(#app_var, words, #no_prefix)
+        }
+        /// Same as `__clap_augment_app_with`, the subcommands and their
+        /// long flags following the casing carried by `prefix`, if any.
+        pub fn __clap_augment_app_prefixed<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+            #prefix: #prefix_type,
+        ) -> ::clap::App<'b> {
+            let #prefix = Self::__clap_localizing_prefix(#prefix);
+            #propagated_casing
+            let #app_var = #app_var #app_methods;
+            #doc_about
+            #localized_about
+            let #app_var = #app_var #plugin #( #subcommands )*;
+            #registered
+            #long_help_sections
+            #app_var
         }
     }
 }
 
 fn gen_from_subcommand(
     name: &syn::Ident,
-    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    variants: &[VariantAttrs],
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     use syn::Fields::*;
 
-    let match_arms = variants.iter().map(|variant| {
-        let attrs = Attrs::from_struct(
-            variant.span(),
-            &variant.attrs,
-            Name::Derived(variant.ident.clone()),
-            parent_attribute.casing(),
-        );
-        let sub_name = attrs.cased_name();
+    let plugin_variant = plugin::find_plugin_variant(variants);
+    let plugin_arm = plugin::gen_plugin_arm(name, plugin_variant);
+    let registered_variant = plugin::find_registered_variant(variants);
+    let registered_arm = plugin::gen_registered_arm(name, registered_variant);
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let (prefix_type, no_prefix) = (from_argmatches::prefix_type(), from_argmatches::no_prefix());
+    let match_arms = subcommand_variants.map(|(variant, attrs, fields)| {
+        let sub_name = attrs.gen_runtime_cased_name(&prefix);
         let variant_name = &variant.ident;
-        let constructor_block = match variant.fields {
-            Named(ref fields) => from_argmatches::gen_constructor(&fields.named, &attrs),
-            Unit => quote!(),
-            Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+        let constructor_block = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                let constructor = from_argmatches::gen_constructor(fields, Some(&prefix));
+                quote!( { #propagated_casing #name :: #variant_name #constructor } )
+            }
+            (Unit, _) => quote!( #name :: #variant_name ),
+            (Unnamed(fields), _) if fields.unnamed.len() == 1 => {
                 let ty = &fields.unnamed[0];
-                quote!( ( <#ty as ::clap::FromArgMatches>::from_argmatches(matches) ) )
+                quote! {
+                    #name :: #variant_name (
+                        <#ty>::__clap_try_from_argmatches_prefixed(
+                            matches,
+                            Self::__clap_casing_prefix(#prefix),
+                        )?
+                    )
+                }
             }
-            Unnamed(..) => abort_call_site!("{}: tuple enums are not supported", variant.ident),
+            _ => abort_call_site!("{}: tuple enums are not supported", variant.ident),
         };
 
         quote! {
-            (#sub_name, Some(matches)) =>
-                Some(#name :: #variant_name #constructor_block)
+            (sub_name, Some(matches)) if sub_name == #sub_name => Some(#constructor_block)
         }
     });
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let infallible_fn = from_argmatches::gen_infallible_from_argmatches_fn();
 
     quote! {
         pub fn from_subcommand<'b>(
             sub: (&'b str, Option<&'b ::clap::ArgMatches>)
         ) -> Option<Self> {
-            match sub {
+            Self::__clap_from_subcommand_prefixed(sub, #no_prefix)
+        }
+        /// Same as `from_subcommand`, the subcommands being named after the
+        /// casing carried by `prefix`, if any.
+        pub fn __clap_from_subcommand_prefixed<'b>(
+            sub: (&'b str, Option<&'b ::clap::ArgMatches>),
+            #prefix: #prefix_type,
+        ) -> Option<Self> {
+            match Self::__clap_try_from_subcommand_prefixed(sub, #prefix) {
+                Ok(value) => value,
+                Err(e) => panic!("{}", e.message),
+            }
+        }
+        /// Same as `__clap_from_subcommand_prefixed`, failing when the
+        /// matches of the subcommand don't fit its variant.
+        pub fn __clap_try_from_subcommand_prefixed<'b>(
+            sub: (&'b str, Option<&'b ::clap::ArgMatches>),
+            #prefix: #prefix_type,
+        ) -> ::std::result::Result<Option<Self>, ::clap::Error> {
+            #propagated_casing
+            Ok(match sub {
                 #( #match_arms ),*,
+                #registered_arm
+                #plugin_arm
                 _ => None
-            }
+            })
         }
+        /// The subcommand of `matches`, for enums flattened into a struct.
+        pub fn __clap_try_from_argmatches_prefixed(
+            matches: &::clap::ArgMatches,
+            #prefix: #prefix_type,
+        ) -> ::std::result::Result<Self, ::clap::Error> {
+            Self::__clap_try_from_subcommand_prefixed(matches.subcommand(), #prefix)?
+                .ok_or_else(Self::__clap_missing_subcommand)
+        }
+
+        #infallible_fn
     }
 }
 
 fn clap_impl_for_struct(
     name: &syn::Ident,
+    vis: &syn::Visibility,
     fields: &punctuated::Punctuated<syn::Field, token::Comma>,
     attrs: &[syn::Attribute],
 ) -> proc_macro2::TokenStream {
     let into_app_impl = into_app::gen_into_app_impl_for_struct(name, attrs);
+    if let Some(lazy) = into_app_impl.attrs.lazy() {
+        abort!(lazy.span(), "`lazy` is only allowed on top of enums");
+    }
+    if let Some(plugin) = into_app_impl.attrs.plugin() {
+        abort!(plugin.span(), "`plugin` is only allowed on enum variants");
+    }
+    if let Some(ident) = into_app_impl
+        .attrs
+        .impl_from_str()
+        .or_else(|| into_app_impl.attrs.impl_display())
+        .or_else(|| into_app_impl.attrs.case_sensitive())
+    {
+        abort!(
+            ident.span(),
+            "`{}` is only allowed on top of enums deriving `ArgEnum`", ident
+        );
+    }
+    if let Some(registered) = into_app_impl.attrs.registered() {
+        abort!(
+            registered.span(),
+            "`registered` is only allowed on enum variants"
+        );
+    }
+    if let Some(untagged) = into_app_impl.attrs.untagged() {
+        abort!(untagged.span(), "`untagged` is only allowed on top of enums");
+    }
+    let fields = Attrs::from_fields(fields, &into_app_impl.attrs);
     let into_app_impl_tokens = into_app_impl.tokens;
-    let augment_app_fn = gen_augment_app_fn(fields, &into_app_impl.attrs);
+    let augment_app_fn = gen_augment_app_fn(&fields, &into_app_impl.attrs);
     let from_argmatches_impl =
-        from_argmatches::gen_from_argmatches_impl_for_struct(name, fields, &into_app_impl.attrs);
-    let parse_fns = gen_parse_fns(name);
+        from_argmatches::gen_from_argmatches_impl_for_struct(name, &fields, &into_app_impl.attrs);
+    let try_from_impl = from_argmatches::gen_try_from_impl(name);
+    let (partial_struct, merge_fn) = config::gen_partial(name, vis, &fields, &into_app_impl.attrs);
+    let (output_enum, parse_output_fns) = gen_parse_output(name, vis, &into_app_impl.attrs);
+    let arg_ids_mod = gen_arg_ids_mod_for_struct(name, vis, &fields, &into_app_impl.attrs);
+
+    let public_fns = vec![
+        docs::gen_public_docs_fns(name, &into_app_impl.attrs),
+        completion::gen_carapace_spec_fn(name, &into_app_impl.attrs),
+        merge_fn,
+        prompt::gen_public_prompt_fns(name, &into_app_impl.attrs),
+        parse_output_fns,
+        fuzz::gen_arbitrary_args_fn(name, &into_app_impl.attrs),
+    ];
+
+    let parse_fns = gen_parse_fns(name, &into_app_impl.attrs);
+    let config_args = config::gen_config_args_for_struct(&fields, &into_app_impl.attrs);
+    let arg_ids = config::gen_arg_ids_for_struct(&fields);
+    let env_args = config::gen_env_args_for_struct(&fields);
+    let localize_fn = localize::gen_localize_fn(&into_app_impl.attrs);
+    let prompt_args = prompt::gen_prompt_args_for_struct(&fields, &into_app_impl.attrs);
+    let subcommand_paths = docs::gen_subcommand_paths_for_struct(&fields, &into_app_impl.attrs);
+    let docs_fns = docs::gen_docs_fns(name, &into_app_impl.attrs);
+    let carapace_fns = completion::gen_carapace_fns_for_struct(&fields, &into_app_impl.attrs);
+    let completion_scripts_fn = completion::gen_completion_scripts_fn(&into_app_impl.attrs);
+    let complete_fns = completion::gen_complete_fns_for_struct(&fields, &into_app_impl.attrs);
+    let debug_fns = checks::gen_debug_fns_for_struct(&fields, &into_app_impl.attrs);
+    let arbitrary_fns = fuzz::gen_arbitrary_fns_for_struct(&fields, &into_app_impl.attrs);
+    let color_arg_fn = color::gen_color_arg_fn_for_struct(&fields, &into_app_impl.attrs);
+    let check_occurrences_fn =
+        occurrences::gen_check_occurrences_fn_for_struct(&fields, &into_app_impl.attrs);
+    let fill_argv_fn = argv::gen_fill_argv_fn_for_struct(&fields);
+    let secret_args_fn = gen_secret_args_fn_for_struct(&fields, &into_app_impl.attrs);
+    let possible_values_fns = gen_possible_values_fns(&fields);
 
     quote! {
         #[allow(unused_variables)]
@@ -312,13 +1169,60 @@ fn clap_impl_for_struct(
 
         #from_argmatches_impl
 
-        #[allow(dead_code, unreachable_code)]
+        #try_from_impl
+
+        #partial_struct
+
+        #output_enum
+
+        #arg_ids_mod
+
+        #[allow(dead_code)]
+        impl #name {
+            #( #public_fns )*
+        }
+
+        #[allow(unused_variables, dead_code, unreachable_code)]
         #[doc(hidden)]
         impl #name {
             #augment_app_fn
 
             #parse_fns
 
+            #config_args
+
+            #arg_ids
+
+            #env_args
+
+            #localize_fn
+
+            #prompt_args
+
+            #subcommand_paths
+
+            #docs_fns
+
+            #carapace_fns
+
+            #completion_scripts_fn
+
+            #complete_fns
+
+            #debug_fns
+
+            #arbitrary_fns
+
+            #color_arg_fn
+
+            #check_occurrences_fn
+
+            #fill_argv_fn
+
+            #secret_args_fn
+
+            #possible_values_fns
+
             pub fn is_subcommand() -> bool { false }
         }
     }
@@ -326,15 +1230,94 @@ fn clap_impl_for_struct(
 
 fn clap_impl_for_enum(
     name: &syn::Ident,
+    vis: &syn::Visibility,
     variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
     attrs: &[syn::Attribute],
 ) -> proc_macro2::TokenStream {
     let into_app_impl = into_app::gen_into_app_impl_for_enum(name, attrs);
+    if into_app_impl.attrs.untagged().is_some() {
+        return clap_impl_for_untagged_enum(name, vis, variants, attrs);
+    }
+    if let Some(partial) = into_app_impl.attrs.partial() {
+        abort!(partial.span(), "`partial` is only allowed on structs");
+    }
+    if let Some(case_insensitive_longs) = into_app_impl.attrs.case_insensitive_longs() {
+        abort!(
+            case_insensitive_longs.span(),
+            "`case_insensitive_longs` is only allowed on top of structs"
+        );
+    }
+    if let Some(hide_short_help) = into_app_impl.attrs.hide_short_help() {
+        abort!(
+            hide_short_help.span(),
+            "`hide_short_help` is only allowed on top of structs and on flattened fields"
+        );
+    }
+    if let Some(plugin) = into_app_impl.attrs.plugin() {
+        abort!(plugin.span(), "`plugin` is only allowed on enum variants");
+    }
+    if let Some(ident) = into_app_impl
+        .attrs
+        .impl_from_str()
+        .or_else(|| into_app_impl.attrs.impl_display())
+        .or_else(|| into_app_impl.attrs.case_sensitive())
+    {
+        abort!(
+            ident.span(),
+            "`{}` is only allowed on top of enums deriving `ArgEnum`", ident
+        );
+    }
+    if let Some(registered) = into_app_impl.attrs.registered() {
+        abort!(
+            registered.span(),
+            "`registered` is only allowed on enum variants"
+        );
+    }
+    let variants = Attrs::from_variants(variants, &into_app_impl.attrs);
+    let variants = variants.as_slice();
     let into_app_impl_tokens = into_app_impl.tokens;
-    let augment_app_fn = gen_augment_app_for_enum(variants, &into_app_impl.attrs);
+    let augment_app_fn = gen_augment_app_for_enum(name, variants, &into_app_impl.attrs);
     let from_argmatches_impl = from_argmatches::gen_from_argmatches_impl_for_enum(name);
+    let try_from_impl = from_argmatches::gen_try_from_impl(name);
+    let registration = plugin::gen_registration(
+        name,
+        vis,
+        plugin::find_registered_variant(variants),
+    );
+    let (output_enum, parse_output_fns) = gen_parse_output(name, vis, &into_app_impl.attrs);
+    let arg_ids_mod = gen_arg_ids_mod_for_enum(name, vis, variants, &into_app_impl.attrs);
+    let public_fns = vec![
+        docs::gen_public_docs_fns(name, &into_app_impl.attrs),
+        completion::gen_carapace_spec_fn(name, &into_app_impl.attrs),
+        prompt::gen_public_prompt_fns(name, &into_app_impl.attrs),
+        parse_output_fns,
+        fuzz::gen_arbitrary_args_fn(name, &into_app_impl.attrs),
+    ];
+
     let from_subcommand = gen_from_subcommand(name, variants, &into_app_impl.attrs);
-    let parse_fns = gen_parse_fns(name);
+    let parse_fns = gen_parse_fns(name, &into_app_impl.attrs);
+    let config_args = config::gen_config_args_for_enum(variants, &into_app_impl.attrs);
+    let arg_ids = config::gen_arg_ids_for_enum();
+    let env_args = config::gen_env_args_for_enum();
+    let localize_fn = localize::gen_localize_fn(&into_app_impl.attrs);
+    let prompt_args = prompt::gen_prompt_args_for_enum(variants, &into_app_impl.attrs);
+    let find_plugin_fn = plugin::find_plugin_variant(variants)
+        .map(|_| plugin::gen_find_plugin_fn(&into_app_impl.attrs));
+    let subcommand_paths = docs::gen_subcommand_paths_for_enum(variants, &into_app_impl.attrs);
+    let docs_fns = docs::gen_docs_fns(name, &into_app_impl.attrs);
+    let carapace_fns = completion::gen_carapace_fns_for_enum(variants, &into_app_impl.attrs);
+    let completion_scripts_fn = completion::gen_completion_scripts_fn(&into_app_impl.attrs);
+    let complete_fns = completion::gen_complete_fns_for_enum(variants, &into_app_impl.attrs);
+    let debug_fns = checks::gen_debug_fns_for_enum(variants, &into_app_impl.attrs);
+    let arbitrary_fns = fuzz::gen_arbitrary_fns_for_enum(variants, &into_app_impl.attrs);
+    let color_arg_fn = color::gen_color_arg_fn_for_enum();
+    let check_occurrences_fn =
+        occurrences::gen_check_occurrences_fn_for_enum(variants, &into_app_impl.attrs);
+    let fill_argv_fn = argv::gen_fill_argv_fn_for_enum(name, variants);
+    let secret_args_fn = gen_secret_args_fn_for_enum(variants, &into_app_impl.attrs);
+    let possible_values_fns = gen_possible_values_fns_for_enum(variants);
+    let prefixed_fns = from_argmatches::gen_prefixed_fns();
+    let error_fns = from_argmatches::gen_error_fns();
 
     quote! {
         #[allow(unused_variables)]
@@ -344,6 +1327,19 @@ fn clap_impl_for_enum(
 
         #from_argmatches_impl
 
+        #try_from_impl
+
+        #registration
+
+        #output_enum
+
+        #arg_ids_mod
+
+        #[allow(dead_code)]
+        impl #name {
+            #( #public_fns )*
+        }
+
         #[allow(unused_variables, dead_code, unreachable_code)]
         #[doc(hidden)]
         impl #name {
@@ -353,75 +1349,1031 @@ fn clap_impl_for_enum(
 
             #parse_fns
 
-            pub fn is_subcommand() -> bool { true }
-        }
-    }
-}
+            #config_args
 
-pub fn derive_clap(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
-    use syn::Data::*;
+            #arg_ids
 
-    let struct_name = &input.ident;
+            #env_args
 
-    set_dummy(quote! {
-        impl ::clap::Clap for #struct_name {}
+            #localize_fn
 
-        impl ::clap::IntoApp for #struct_name {
-            fn into_app<'b>() -> ::clap::App<'b> {
-                unimplemented!()
-            }
-        }
+            #prompt_args
 
-        impl ::clap::FromArgMatches for #struct_name {
-            fn from_argmatches(m: &::clap::ArgMatches) -> Self {
-                unimplemented!()
-            }
-        }
+            #find_plugin_fn
 
-        impl #struct_name {
-            fn parse() -> Self {
-                unimplemented!();
-            }
-        }
-    });
+            #subcommand_paths
 
-    match input.data {
-        Struct(syn::DataStruct {
+            #docs_fns
+
+            #carapace_fns
+
+            #completion_scripts_fn
+
+            #complete_fns
+
+            #debug_fns
+
+            #arbitrary_fns
+
+            #color_arg_fn
+
+            #check_occurrences_fn
+
+            #fill_argv_fn
+
+            #secret_args_fn
+
+            #possible_values_fns
+
+            #prefixed_fns
+
+            #error_fns
+
+            pub fn is_subcommand() -> bool { true }
+        }
+    }
+}
+
+/// Same as `clap_impl_for_enum`, for an `untagged` enum: it is built like a
+/// struct whose arguments are those of all the variants, and the functions
+/// walking subcommands see none.
+fn clap_impl_for_untagged_enum(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    variants: &punctuated::Punctuated<syn::Variant, token::Comma>,
+    attrs: &[syn::Attribute],
+) -> proc_macro2::TokenStream {
+    let into_app_impl = into_app::gen_into_app_impl_for_struct(name, attrs);
+    if let Some(ident) = into_app_impl
+        .attrs
+        .partial()
+        .or_else(|| into_app_impl.attrs.lazy())
+        .or_else(|| into_app_impl.attrs.plugin())
+        .or_else(|| into_app_impl.attrs.registered())
+    {
+        abort!(
+            ident.span(),
+            "`{}` can't be used with `untagged`", ident
+        );
+    }
+    if let Some(arg_ids) = into_app_impl.attrs.arg_ids() {
+        abort!(arg_ids.span(), "`arg_ids` can't be used with `untagged`");
+    }
+    let variants = untagged::variants_with_attrs(variants, &into_app_impl.attrs);
+    let variants = variants.as_slice();
+    let into_app_impl_tokens = into_app_impl.tokens;
+    let from_argmatches_impl =
+        untagged::gen_from_argmatches_impl(name, variants);
+    let try_from_impl = from_argmatches::gen_try_from_impl(name);
+    let (output_enum, parse_output_fns) = gen_parse_output(name, vis, &into_app_impl.attrs);
+    let no_variants: &[VariantAttrs] = &[];
+    let public_fns = vec![
+        docs::gen_public_docs_fns(name, &into_app_impl.attrs),
+        completion::gen_carapace_spec_fn(name, &into_app_impl.attrs),
+        prompt::gen_public_prompt_fns(name, &into_app_impl.attrs),
+        parse_output_fns,
+        fuzz::gen_arbitrary_args_fn(name, &into_app_impl.attrs),
+    ];
+
+    let augment_app_fn = untagged::gen_augment_app_fn(variants, &into_app_impl.attrs);
+    let parse_fns = gen_parse_fns(name, &into_app_impl.attrs);
+    let config_args = config::gen_config_args_for_enum(no_variants, &into_app_impl.attrs);
+    let arg_ids = untagged::gen_arg_ids_fn(variants);
+    let env_args = config::gen_env_args_for_enum();
+    let localize_fn = localize::gen_localize_fn(&into_app_impl.attrs);
+    let prompt_args = prompt::gen_prompt_args_for_untagged(&into_app_impl.attrs);
+    let subcommand_paths = docs::gen_subcommand_paths_for_enum(no_variants, &into_app_impl.attrs);
+    let docs_fns = docs::gen_docs_fns(name, &into_app_impl.attrs);
+    let carapace_fns = completion::gen_carapace_fns_for_enum(no_variants, &into_app_impl.attrs);
+    let completion_scripts_fn = completion::gen_completion_scripts_fn(&into_app_impl.attrs);
+    let complete_fns = completion::gen_complete_fns_for_enum(no_variants, &into_app_impl.attrs);
+    let debug_fns = checks::gen_debug_fns_for_enum(no_variants, &into_app_impl.attrs);
+    let arbitrary_fns = fuzz::gen_arbitrary_fns_for_enum(no_variants, &into_app_impl.attrs);
+    let color_arg_fn = color::gen_color_arg_fn_for_enum();
+    let check_occurrences_fn =
+        occurrences::gen_check_occurrences_fn_for_enum(no_variants, &into_app_impl.attrs);
+    let fill_argv_fn = argv::gen_fill_argv_fn_for_enum(name, variants);
+    let secret_args_fn = gen_secret_args_fn_for_enum(variants, &into_app_impl.attrs);
+
+    quote! {
+        #[allow(unused_variables)]
+        impl ::clap::Clap for #name { }
+
+        #into_app_impl_tokens
+
+        #from_argmatches_impl
+
+        #try_from_impl
+
+        #output_enum
+
+        #[allow(dead_code)]
+        impl #name {
+            #( #public_fns )*
+        }
+
+        #[allow(unused_variables, dead_code, unreachable_code)]
+        #[doc(hidden)]
+        impl #name {
+            #augment_app_fn
+
+            #parse_fns
+
+            #config_args
+
+            #arg_ids
+
+            #env_args
+
+            #localize_fn
+
+            #prompt_args
+
+            #subcommand_paths
+
+            #docs_fns
+
+            #carapace_fns
+
+            #completion_scripts_fn
+
+            #complete_fns
+
+            #debug_fns
+
+            #arbitrary_fns
+
+            #color_arg_fn
+
+            #check_occurrences_fn
+
+            #fill_argv_fn
+
+            #secret_args_fn
+
+            pub fn is_subcommand() -> bool { false }
+        }
+    }
+}
+
+pub fn derive_clap(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    use syn::Data::*;
+
+    let struct_name = &input.ident;
+
+    set_dummy(quote! {
+        impl ::clap::Clap for #struct_name {}
+
+        impl ::clap::IntoApp for #struct_name {
+            fn into_app<'b>() -> ::clap::App<'b> {
+                unimplemented!()
+            }
+        }
+
+        impl ::clap::FromArgMatches for #struct_name {
+            fn from_argmatches(m: &::clap::ArgMatches) -> Self {
+                unimplemented!()
+            }
+        }
+
+        impl #struct_name {
+            fn parse() -> Self {
+                unimplemented!();
+            }
+        }
+    });
+
+    match input.data {
+        Struct(syn::DataStruct {
             fields: syn::Fields::Named(ref fields),
             ..
-        }) => clap_impl_for_struct(struct_name, &fields.named, &input.attrs),
-        Enum(ref e) => clap_impl_for_enum(struct_name, &e.variants, &input.attrs),
+        }) => clap_impl_for_struct(struct_name, &input.vis, &fields.named, &input.attrs),
+        Enum(ref e) => clap_impl_for_enum(struct_name, &input.vis, &e.variants, &input.attrs),
         _ => abort_call_site!("clap_derive only supports non-tuple structs and enums"),
     }
 }
 
-fn gen_parse_fns(name: &syn::Ident) -> proc_macro2::TokenStream {
+fn gen_parse_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    let dotenv = config::gen_load_dotenv(parent_attribute);
+    let config_file = config::gen_apply_config_file(name, parent_attribute);
+    let prompt_fns = prompt::gen_prompt_fns(name, parent_attribute);
+    let parse_from_str_fns = gen_parse_from_str_fns(name, parent_attribute);
+    let dynamic_completion = completion::gen_dynamic_completion(name, parent_attribute);
+    let color_choice = color::gen_apply_color_choice(name);
+    let expand_argfiles_fn = config::gen_expand_argfiles_fn(parent_attribute);
+    let fold_long_case_fn = config::gen_fold_long_case_fn(parent_attribute);
+    let exit = gen_exit(name, parent_attribute);
+    let exit_fn = gen_exit_fn(parent_attribute);
+    let redact_fn = gen_redact_fn(parent_attribute);
+    let zeroize_args_fn = gen_zeroize_args_fn(parent_attribute);
+    let zeroize_args = gen_zeroize_args(name, parent_attribute, quote!(&mut args));
+    let replace_args = gen_replace_args(
+        name,
+        parent_attribute,
+        quote!(args),
+        quote!(#name::__clap_fold_long_case(expanded)),
+    );
+    let no_prefix = from_argmatches::no_prefix();
+    // the cached `App` is a full one, the environment and configuration
+    // files being applied to a clone of it on each parse
+    let app = if parent_attribute.has_cached_app() {
+        let app = into_app::gen_app_expr(name, parent_attribute);
+        quote!( let app = #app; )
+    } else {
+        let words = if parent_attribute.has_zeroize() {
+            quote!( ::zeroize::Zeroizing::new(words) )
+        } else {
+            quote!(words)
+        };
+        quote! {
+            let words: Vec<String> = args
+                .iter()
+                .skip(1)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            let words = #words;
+            let app = #name::__clap_lazy_app(&words);
+        }
+    };
+    // with `zeroize`, the copy of the command line kept for the fields
+    // capturing it is wiped on every return
+    let try_parse_args = quote! {
+        let expanded = #name::__clap_expand_argfiles(raw_args.clone())?;
+        #replace_args
+        let app = #name::__clap_app_from(args)?;
+        let matches = app.try_get_matches_from(&*args)?;
+        #name::__clap_check_occurrences(&matches, #no_prefix)?;
+        let mut parsed = #name::from_argmatches(&matches);
+        parsed.__clap_fill_argv(&raw_args);
+        Ok(parsed)
+    };
+    let try_parse_args = if parent_attribute.has_zeroize() {
+        quote! {
+            let mut raw_args = args.clone();
+            let parsed = (|| -> ::std::result::Result<#name, ::clap::Error> {
+                #try_parse_args
+            })();
+            #name::__clap_zeroize_args(&mut raw_args);
+            parsed
+        }
+    } else {
+        quote! {
+            let raw_args = args.clone();
+            #try_parse_args
+        }
+    };
+
     quote! {
         #[allow(unreachable_pub)]
         pub fn parse() -> #name {
-            use ::clap::{FromArgMatches, IntoApp};
-            #name::from_argmatches(&#name::into_app().get_matches())
+            #name::parse_from(::std::env::args_os())
         }
         #[allow(unreachable_pub)]
         pub fn try_parse() -> ::std::result::Result<#name, ::clap::Error> {
-            use ::clap::{FromArgMatches, IntoApp};
-            Ok(#name::from_argmatches(&#name::into_app().try_get_matches()?))
+            #name::try_parse_from(::std::env::args_os())
         }
         #[allow(unreachable_pub)]
         pub fn parse_from<I, T>(itr: I) -> #name
         where
             I: ::std::iter::IntoIterator<Item = T>,
             T: Into<::std::ffi::OsString> + Clone {
-            use ::clap::{FromArgMatches, IntoApp};
-            #name::from_argmatches(&#name::into_app().get_matches_from(itr))
+            #name::try_parse_from(itr).unwrap_or_else(|e| #exit)
         }
         #[allow(unreachable_pub)]
         pub fn try_parse_from<I, T>(itr: I) -> ::std::result::Result<#name, ::clap::Error>
         where
             I: ::std::iter::IntoIterator<Item = T>,
             T: Into<::std::ffi::OsString> + Clone {
-            use ::clap::{FromArgMatches, IntoApp};
-            Ok(#name::from_argmatches(&#name::into_app().try_get_matches_from(itr)?))
+            let mut args: Vec<::std::ffi::OsString> =
+                itr.into_iter().map(Into::into).collect();
+            #dynamic_completion
+            let parsed = #name::__clap_try_parse_args(&mut args)
+                .map_err(|e| #name::__clap_redact(e, &args));
+            #zeroize_args
+            parsed
+        }
+        /// Same as `try_parse_from`, the errors being left unredacted and
+        /// `args` being replaced by the command line as parsed, with its
+        /// argument files expanded.
+        pub fn __clap_try_parse_args(
+            args: &mut Vec<::std::ffi::OsString>,
+        ) -> ::std::result::Result<#name, ::clap::Error> {
+            use ::clap::FromArgMatches;
+            #try_parse_args
+        }
+        #parse_from_str_fns
+        /// Build the `App` which is going to parse `args`.
+        pub fn __clap_app_from(
+            args: &[::std::ffi::OsString],
+        ) -> ::std::result::Result<::clap::App<'static>, ::clap::Error> {
+            #dotenv
+            #app
+            #color_choice
+            #config_file
+            Ok(app)
+        }
+        #expand_argfiles_fn
+        #fold_long_case_fn
+        #exit_fn
+        #redact_fn
+        #zeroize_args_fn
+        #prompt_fns
+    }
+}
+
+/// Generate the expression printing the error `e` and exiting, for the
+/// parse functions which don't return it.
+///
+/// It's a plain `e.exit()` unless `error_exit_code` or `usage_exit_code` is
+/// given, `__clap_exit()` being called then.
+pub fn gen_exit(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    match (parent_attribute.error_exit_code(), parent_attribute.usage_exit_code()) {
+        (None, None) => quote!(e.exit()),
+        _ => quote!(#name::__clap_exit(e)),
+    }
+}
+
+/// Generate the `__clap_exit()` function, printing an error and exiting with
+/// the codes given with `error_exit_code` and `usage_exit_code`, if any.
+///
+/// Help and version requests still exit successfully, and usage errors exit
+/// with `error_exit_code` when there is no `usage_exit_code`.
+fn gen_exit_fn(parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    let error_code = parent_attribute.error_exit_code();
+    let usage_code = parent_attribute.usage_exit_code().or(error_code);
+    if error_code.is_none() && usage_code.is_none() {
+        return quote!();
+    }
+
+    let error_code = error_code.map_or_else(|| quote!(1), |code| quote!(#code));
+    let usage_code = usage_code.map_or_else(|| quote!(1), |code| quote!(#code));
+    quote! {
+        /// Print `e` and exit, successfully for help and version requests.
+        pub fn __clap_exit(e: ::clap::Error) -> ! {
+            match e.kind {
+                ::clap::ErrorKind::HelpDisplayed | ::clap::ErrorKind::VersionDisplayed => {
+                    e.exit()
+                }
+                ::clap::ErrorKind::Io | ::clap::ErrorKind::Format => {
+                    eprintln!("{}", e.message);
+                    ::std::process::exit(#error_code)
+                }
+                _ => {
+                    eprintln!("{}", e.message);
+                    ::std::process::exit(#usage_code)
+                }
+            }
+        }
+    }
+}
+
+/// The type the values of the argument of `field` are parsed into, the
+/// values of tuples and arrays being parsed one by one.
+pub fn convert_type<'a>(field: &'a syn::Field, attrs: &Attrs, ty: Ty) -> &'a syn::Type {
+    let convert_type = match ty {
+        Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
+        Ty::OptionOption | Ty::OptionVec => {
+            sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
+        }
+        _ => &field.ty,
+    };
+    match fixed_values(convert_type) {
+        Some((value_type, _)) if attrs.has_fixed_values() => value_type,
+        _ => convert_type,
+    }
+}
+
+/// The message of the error `e` of the parser of `attrs`.
+pub fn gen_parse_error(attrs: &Attrs, convert_type: &syn::Type) -> proc_macro2::TokenStream {
+    if attrs.has_custom_parser() {
+        return quote!(e.to_string());
+    }
+
+    // `ParseIntError` doesn't tell the bounds it is checking
+    if is_integer_ty(convert_type) {
+        quote! {
+            format!(
+                "{} (expected an integer from {} to {})",
+                e,
+                <#convert_type>::min_value(),
+                <#convert_type>::max_value()
+            )
+        }
+    } else if let Some(integer) = nonzero_integer_ty(convert_type) {
+        let integer = syn::Ident::new(integer, convert_type.span());
+        if integer.to_string().starts_with('u') {
+            quote!( format!("{} (expected an integer from 1 to {})", e, #integer::max_value()) )
+        } else {
+            quote! {
+                format!(
+                    "{} (expected a non-zero integer from {} to {})",
+                    e,
+                    #integer::min_value(),
+                    #integer::max_value()
+                )
+            }
+        }
+    } else {
+        quote!(e.to_string())
+    }
+}
+
+/// A closure checking a value of the argument of `field` with its fallible
+/// parser, if it has one, as its validator does, the error message being
+/// `Err`.
+pub fn gen_value_check(field: &syn::Field, attrs: &Attrs, ty: Ty) -> Option<proc_macro2::TokenStream> {
+    let convert_type = convert_type(field, attrs, ty);
+    let parser = attrs.parser();
+    let func = &parser.func;
+    let skip_marker = match attrs.missing_value_marker() {
+        Some(marker) => quote!( if s == #marker { return Ok(()); } ),
+        None => quote!(),
+    };
+    let check = match *parser.kind {
+        ParserKind::TryFromStr if attrs.is_secret() => quote_spanned! { func.span()=>
+            match s.to_str() {
+                Some(s) => #func(s).map(|_: #convert_type| ()).map_err(|_| String::from(#SECRET_ERROR)),
+                None => Ok(()),
+            }
+        },
+        ParserKind::TryFromStr => {
+            let parse_error = gen_parse_error(attrs, convert_type);
+            quote_spanned! { func.span()=>
+                match s.to_str() {
+                    Some(s) => #func(s).map(|_: #convert_type| ()).map_err(|e| #parse_error),
+                    None => Ok(()),
+                }
+            }
+        }
+        ParserKind::TryFromOsStr if attrs.is_secret() => quote_spanned! { func.span()=>
+            #func(s).map(|_: #convert_type| ()).map_err(|_| String::from(#SECRET_ERROR))
+        },
+        ParserKind::TryFromOsStr => quote_spanned! { func.span()=>
+            #func(s)
+                .map(|_: #convert_type| ())
+                .map_err(|e| e.to_string_lossy().into_owned())
+        },
+        _ => return None,
+    };
+
+    Some(quote! {
+        |s: &::std::ffi::OsStr| -> ::std::result::Result<(), String> {
+            #skip_marker
+            #check
+        }
+    })
+}
+
+/// Generate the functions giving the values of the `possible_values_fn` of
+/// `fields`, asked for the first time they're needed on the thread and kept
+/// for the following times.
+fn gen_possible_values_fns(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let fns = fields.iter().filter_map(|(_, attrs)| {
+        let (name, func) = attrs.possible_values_fn()?;
+        Some(quote_spanned! { func.span()=>
+            pub fn #name() -> &'static [&'static str] {
+                thread_local! {
+                    static VALUES: ::std::cell::Cell<Option<&'static [&'static str]>> =
+                        ::std::cell::Cell::new(None);
+                }
+
+                VALUES.with(|values| {
+                    if let Some(values) = values.get() {
+                        return values;
+                    }
+                    let interned = Self::__clap_interned_values(
+                        (#func)()
+                            .into_iter()
+                            .map(|value| Self::__clap_interned(String::from(value)))
+                            .collect(),
+                    );
+                    values.set(Some(interned));
+                    interned
+                })
+            }
+        })
+    });
+
+    quote!( #( #fns )* )
+}
+
+/// Generate the functions giving the values of the `possible_values_fn` of
+/// the fields of the variants of an enum.
+fn gen_possible_values_fns_for_enum(variants: &[VariantAttrs]) -> proc_macro2::TokenStream {
+    let fns = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .filter_map(|(_, _, fields)| fields.as_ref().map(|fields| gen_possible_values_fns(fields)));
+
+    quote!( #( #fns )* )
+}
+
+/// Generate the `__clap_secret_args()` function of a struct, listing the
+/// `(short, long, environment variable, multiple)` of its `secret`
+/// arguments, flattened ones and the ones of its subcommands included,
+/// `multiple` telling whether an occurrence can hold several values.
+fn gen_secret_args_fn_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let args = gen_secret_args(fields, parent_attribute, &prefix);
+
+    quote! {
+        pub fn __clap_secret_args(
+            #prefix: #prefix_type,
+        ) -> Vec<(Option<char>, Option<&'static str>, Option<&'static str>, bool)> {
+            #propagated_casing
+            let mut args = Vec::new();
+            #args
+            args
+        }
+    }
+}
+
+/// Generate the `__clap_secret_args()` function of an enum, listing the
+/// `secret` arguments of all its subcommands.
+fn gen_secret_args_fn_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let variant_args = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .map(|(variant, attrs, fields)| {
+            match (&variant.fields, fields) {
+                (Named(_), Some(fields)) => {
+                    let propagated_casing =
+                        from_argmatches::gen_propagated_casing(&prefix, attrs);
+                    let args = gen_secret_args(fields, attrs, &prefix);
+                    quote!({
+                        let #prefix = Self::__clap_casing_prefix(#prefix);
+                        #propagated_casing
+                        #args
+                    })
+                }
+                (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                    let ty = &unnamed[0].ty;
+                    quote! {
+                        args.extend(<#ty>::__clap_secret_args(Self::__clap_casing_prefix(#prefix)));
+                    }
+                }
+                _ => quote!(),
+            }
+        });
+
+    quote! {
+        pub fn __clap_secret_args(
+            #prefix: #prefix_type,
+        ) -> Vec<(Option<char>, Option<&'static str>, Option<&'static str>, bool)> {
+            #propagated_casing
+            let mut args = Vec::new();
+            #( #variant_args )*
+            args
+        }
+    }
+}
+
+/// Code pushing the `secret` arguments of `fields` into `args`, flagged
+/// under the runtime `prefix`.
+fn gen_secret_args(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let entries = fields.iter().map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                quote!( args.extend(<#ty>::__clap_secret_args(#flatten_prefix)); )
+            }
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                quote! {
+                    args.extend(<#subcmd_type>::__clap_secret_args(
+                        Self::__clap_casing_prefix(#prefix),
+                    ));
+                }
+            }
+            Kind::Arg(ty) if attrs.is_secret() => {
+                let multiple = match **ty {
+                    Ty::Vec | Ty::OptionVec => true,
+                    _ => ["multiple", "min_values", "max_values", "number_of_values"]
+                        .iter()
+                        .any(|method| attrs.has_method(method)),
+                };
+                let short = match attrs.short_name() {
+                    Some(short) => quote! {
+                        if #prefix.0.is_empty() { Some(#short) } else { None }
+                    },
+                    None => quote!(None),
+                };
+                let attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+                let long = match attrs.method_args("long") {
+                    Some(long) => quote!( Some(#long) ),
+                    None => quote!(None),
+                };
+                let env = match attrs.method_args("env") {
+                    Some(env) => quote!( Some(#env) ),
+                    None => quote!(None),
+                };
+                quote!( args.push((#short, #long, #env, #multiple)); )
+            }
+            _ => quote!(),
+        }
+    });
+
+    quote!( #( #entries )* )
+}
+
+/// Generate `__clap_redact()`, replacing the values of the `secret`
+/// arguments found in `args` or in their environment variables by
+/// `<secret>` in the message of an error, and `__clap_redact_values()`
+/// doing the replacement.
+///
+/// Every error the parse functions and `TryFrom<&ArgMatches>` return goes
+/// through it, including the ones clap makes itself, like a value not being
+/// one of the possible ones, where values are quoted. The help and version
+/// messages are left untouched.
+///
+/// With `#[clap(zeroize)]`, the copies of the values and the messages they
+/// are taken out of are wiped.
+fn gen_redact_fn(parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    let no_prefix = from_argmatches::no_prefix();
+    let (wipe_values, replace_message, replace_info) = if parent_attribute.has_zeroize() {
+        (
+            quote!( ::zeroize::Zeroize::zeroize(&mut values); ),
+            quote! {
+                let mut previous = ::std::mem::replace(&mut e.message, message);
+                ::zeroize::Zeroize::zeroize(&mut previous);
+            },
+            quote! {
+                let mut previous = ::std::mem::replace(info, String::from("<secret>"));
+                ::zeroize::Zeroize::zeroize(&mut previous);
+            },
+        )
+    } else {
+        (
+            quote!(),
+            quote!( e.message = message; ),
+            quote!( *info = String::from("<secret>"); ),
+        )
+    };
+
+    quote! {
+        pub fn __clap_redact(
+            e: ::clap::Error,
+            args: &[::std::ffi::OsString],
+        ) -> ::clap::Error {
+            match e.kind {
+                ::clap::ErrorKind::HelpDisplayed | ::clap::ErrorKind::VersionDisplayed => {
+                    return e
+                }
+                _ => (),
+            }
+            let secret_args = Self::__clap_secret_args(#no_prefix);
+            if secret_args.is_empty() {
+                return e;
+            }
+
+            let mut values: Vec<String> = secret_args
+                .iter()
+                .filter_map(|&(_, _, env, _)| env.and_then(|env| ::std::env::var(env).ok()))
+                .collect();
+            let mut words = args.iter().skip(1).map(|arg| arg.to_string_lossy()).peekable();
+            while let Some(word) = words.next() {
+                if word == "--" {
+                    break;
+                }
+                let mut occurrence = None;
+                for &(short, long, _, multiple) in &secret_args {
+                    if let Some(long) = long.map(|long| format!("--{}", long)) {
+                        if word == long {
+                            occurrence = Some((multiple, None));
+                            break;
+                        }
+                        if word.starts_with(&long) && word[long.len()..].starts_with('=') {
+                            occurrence = Some((multiple, Some(word[long.len() + 1..].to_string())));
+                            break;
+                        }
+                    }
+                    if let Some(short) = short {
+                        // the short flag may come after other ones, as in `-vt`
+                        let at = if word.starts_with("--") || !word.starts_with('-') {
+                            None
+                        } else {
+                            word[1..].find(short)
+                        };
+                        if let Some(at) = at {
+                            let value = word[1 + at + short.len_utf8()..].trim_start_matches('=');
+                            occurrence = Some((multiple, Some(value.to_string())));
+                            break;
+                        }
+                    }
+                }
+
+                // every value clap gives to the occurrence: the attached one
+                // or the next word, and the following ones if it takes several
+                if let Some((multiple, value)) = occurrence {
+                    match value.filter(|value| !value.is_empty()) {
+                        Some(value) => values.push(value),
+                        None => values.extend(words.next().map(|word| word.into_owned())),
+                    }
+                    while multiple
+                        && words.peek().map_or(false, |word| !word.starts_with('-') || word == "-")
+                    {
+                        values.extend(words.next().map(|word| word.into_owned()));
+                    }
+                }
+            }
+            let e = Self::__clap_redact_values(e, &values);
+            #wipe_values
+            e
+        }
+
+        /// Replace `values` by `<secret>` where they're quoted in the message
+        /// of `e`, colored or not, and where they make up an item of its info.
+        pub fn __clap_redact_values<S: AsRef<str>>(
+            mut e: ::clap::Error,
+            values: &[S],
+        ) -> ::clap::Error {
+            fn quoted_before(before: &str) -> bool {
+                let color = before.rfind("\x1b[").filter(|&start| {
+                    before.ends_with('m')
+                        && before[start + 2..before.len() - 1]
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || c == ';')
+                });
+                match color {
+                    Some(start) => before[..start].ends_with('\''),
+                    None => before.ends_with('\''),
+                }
+            }
+            fn quoted_after(after: &str) -> bool {
+                if after.starts_with("\x1b[") {
+                    match after.find('m') {
+                        Some(end) => after[end + 1..].starts_with('\''),
+                        None => false,
+                    }
+                } else {
+                    after.starts_with('\'')
+                }
+            }
+
+            for value in values.iter().map(AsRef::as_ref).filter(|value| !value.is_empty()) {
+                let mut message = String::with_capacity(e.message.len());
+                let mut rest = e.message.as_str();
+                while let Some(start) = rest.find(value) {
+                    let end = start + value.len();
+                    message.push_str(&rest[..start]);
+                    if quoted_before(&rest[..start]) && quoted_after(&rest[end..]) {
+                        message.push_str("<secret>");
+                    } else {
+                        message.push_str(value);
+                    }
+                    rest = &rest[end..];
+                }
+                message.push_str(rest);
+                #replace_message
+
+                if let Some(info) = e.info.as_mut() {
+                    for info in info.iter_mut().filter(|info| info.as_str() == value) {
+                        #replace_info
+                    }
+                }
+            }
+            e
+        }
+    }
+}
+
+/// Generate `__clap_zeroize_args()`, wiping the arguments of a command line
+/// before dropping them, none without `#[clap(zeroize)]`.
+///
+/// Only Unix gives access to the bytes of an `OsString`, the arguments which
+/// aren't UTF-8 being dropped as they are elsewhere.
+fn gen_zeroize_args_fn(parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_zeroize() {
+        return quote!();
+    }
+
+    quote! {
+        pub fn __clap_zeroize_args(args: &mut Vec<::std::ffi::OsString>) {
+            for arg in args.drain(..) {
+                match arg.into_string() {
+                    Ok(mut arg) => ::zeroize::Zeroize::zeroize(&mut arg),
+                    #[cfg(unix)]
+                    Err(arg) => {
+                        let mut arg = ::std::os::unix::ffi::OsStringExt::into_vec(arg);
+                        ::zeroize::Zeroize::zeroize(&mut arg);
+                    }
+                    #[cfg(not(unix))]
+                    Err(_) => (),
+                }
+            }
+        }
+    }
+}
+
+/// Generate the statement wiping the command line `args`, a mutable
+/// reference to a `Vec<OsString>`, with `#[clap(zeroize)]`, and nothing
+/// otherwise.
+pub fn gen_zeroize_args(
+    name: &syn::Ident,
+    parent_attribute: &Attrs,
+    args: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if parent_attribute.has_zeroize() {
+        quote!( #name::__clap_zeroize_args(#args); )
+    } else {
+        quote!()
+    }
+}
+
+/// Generate the statements putting `new_args` in place of the command line
+/// behind `args`, a mutable reference to a `Vec<OsString>`, the previous one
+/// being wiped with `#[clap(zeroize)]`.
+pub fn gen_replace_args(
+    name: &syn::Ident,
+    parent_attribute: &Attrs,
+    args: proc_macro2::TokenStream,
+    new_args: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if parent_attribute.has_zeroize() {
+        quote! {
+            let mut previous = ::std::mem::replace(#args, #new_args);
+            #name::__clap_zeroize_args(&mut previous);
+        }
+    } else {
+        quote!( *#args = #new_args; )
+    }
+}
+
+/// Generate the `<Name>Output` enum and the `try_parse_or_output` functions,
+/// handing the help and version messages back instead of printing them.
+fn gen_parse_output(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    parent_attribute: &Attrs,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let output_name = match parent_attribute.parse_output() {
+        Some(ident) => syn::Ident::new(&format!("{}Output", name), ident.span()),
+        None => return (quote!(), quote!()),
+    };
+
+    let output_enum = quote! {
+        /// What parsing a command line gave: the arguments, or the message
+        /// the user asked for.
+        #[derive(Debug)]
+        #vis enum #output_name {
+            Parsed(#name),
+            HelpRequested(String),
+            VersionRequested(String),
+        }
+    };
+    let fns = quote! {
+        /// Same as `try_parse`, returning the help and version messages
+        /// instead of printing them and exiting.
+        #[allow(unreachable_pub)]
+        pub fn try_parse_or_output() -> ::std::result::Result<#output_name, ::clap::Error> {
+            #name::try_parse_or_output_from(::std::env::args_os())
+        }
+        /// Same as `try_parse_from`, returning the help and version messages
+        /// instead of printing them and exiting.
+        #[allow(unreachable_pub)]
+        pub fn try_parse_or_output_from<I, T>(
+            itr: I,
+        ) -> ::std::result::Result<#output_name, ::clap::Error>
+        where
+            I: ::std::iter::IntoIterator<Item = T>,
+            T: Into<::std::ffi::OsString> + Clone {
+            match #name::try_parse_from(itr) {
+                Ok(parsed) => Ok(#output_name::Parsed(parsed)),
+                Err(e) => match e.kind {
+                    ::clap::ErrorKind::HelpDisplayed => Ok(#output_name::HelpRequested(e.message)),
+                    ::clap::ErrorKind::VersionDisplayed => {
+                        Ok(#output_name::VersionRequested(e.message))
+                    }
+                    _ => Err(e),
+                },
+            }
+        }
+    };
+
+    (output_enum, fns)
+}
+
+/// Generate the functions parsing a whole command line given as a single
+/// string, split into words the way a POSIX shell would, with
+/// `parse_from_str`.
+fn gen_parse_from_str_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_parse_from_str() {
+        return quote!();
+    }
+
+    let bin_name = parent_attribute.cased_name();
+    let split_words_fn = gen_split_words_fn();
+    let exit = gen_exit(name, parent_attribute);
+
+    quote! {
+        /// Parse `line`, the arguments of a command line without the binary
+        /// name, after splitting it into words like a shell would.
+        ///
+        /// Single quotes, double quotes and backslash escapes are supported.
+        #[allow(unreachable_pub)]
+        pub fn parse_from_str(line: &str) -> #name {
+            #name::try_parse_from_str(line).unwrap_or_else(|e| #exit)
+        }
+        /// Same as `parse_from_str`, returning an error instead of exiting.
+        #[allow(unreachable_pub)]
+        pub fn try_parse_from_str(line: &str) -> ::std::result::Result<#name, ::clap::Error> {
+            let words = #name::__clap_split_words(line)
+                .map_err(|e| #name::__clap_redact(e, &[]))?;
+            #name::try_parse_from(::std::iter::once(String::from(#bin_name)).chain(words))
+        }
+        #split_words_fn
+    }
+}
+
+/// Generate `__clap_split_words()`, splitting a command line into words like
+/// a POSIX shell, for `parse_from_str`.
+///
+/// The errors only give the byte offset of the faulty quote or backslash,
+/// the line possibly holding `secret` values.
+fn gen_split_words_fn() -> proc_macro2::TokenStream {
+    quote! {
+        pub fn __clap_split_words(line: &str) -> ::std::result::Result<Vec<String>, ::clap::Error> {
+            fn error(what: &str, offset: usize) -> ::clap::Error {
+                ::clap::Error::with_description(
+                    &format!("{} at byte {} of the command line", what, offset),
+                    ::clap::ErrorKind::InvalidValue,
+                )
+            }
+
+            let mut words = Vec::new();
+            let mut word: Option<String> = None;
+            let mut chars = line.char_indices();
+
+            while let Some((offset, c)) = chars.next() {
+                match c {
+                    ' ' | '\t' | '\n' | '\r' => {
+                        if let Some(word) = word.take() {
+                            words.push(word);
+                        }
+                    }
+                    '\'' => {
+                        let word = word.get_or_insert_with(String::new);
+                        loop {
+                            match chars.next() {
+                                Some((_, '\'')) => break,
+                                Some((_, c)) => word.push(c),
+                                None => return Err(error("unterminated single quote", offset)),
+                            }
+                        }
+                    }
+                    '"' => {
+                        let word = word.get_or_insert_with(String::new);
+                        loop {
+                            match chars.next() {
+                                Some((_, '"')) => break,
+                                Some((_, '\\')) => match chars.next() {
+                                    Some((_, c @ '"')) | Some((_, c @ '\\'))
+                                    | Some((_, c @ '$')) | Some((_, c @ '`')) => word.push(c),
+                                    Some((_, '\n')) => (),
+                                    Some((_, c)) => {
+                                        word.push('\\');
+                                        word.push(c);
+                                    }
+                                    None => (),
+                                },
+                                Some((_, c)) => word.push(c),
+                                None => return Err(error("unterminated double quote", offset)),
+                            }
+                        }
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, '\n')) => (),
+                        Some((_, c)) => word.get_or_insert_with(String::new).push(c),
+                        None => return Err(error("trailing backslash", offset)),
+                    },
+                    c => word.get_or_insert_with(String::new).push(c),
+                }
+            }
+
+            words.extend(word);
+            Ok(words)
         }
     }
 }
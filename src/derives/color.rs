@@ -0,0 +1,100 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Color choice given on the command line through a field marked with a
+//! bare `#[clap(color)]`.
+
+use proc_macro2;
+use syn;
+
+use super::{config, from_argmatches, Attrs, Kind};
+
+/// Generate the `__clap_color_arg()` function of a struct, returning the
+/// long flag of its color choice under the runtime `prefix`, flattened ones
+/// included.
+pub fn gen_color_arg_fn_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let candidates = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(&prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                Some(quote!( <#ty>::__clap_color_arg(#flatten_prefix) ))
+            }
+            Kind::Arg(_) if attrs.is_color_arg() => {
+                attrs.long_name().map(|_| {
+                    let attrs = attrs.clone().with_runtime_prefix(&prefix, parent_attribute);
+                    let long = attrs.method_args("long");
+                    quote!( Some(#long) )
+                })
+            }
+            _ => None,
+        }
+    });
+
+    quote! {
+        pub fn __clap_color_arg(#prefix: #prefix_type) -> Option<&'static str> {
+            #propagated_casing
+            let candidates: Vec<Option<&'static str>> = vec![ #( #candidates ),* ];
+            candidates.into_iter().flatten().next()
+        }
+    }
+}
+
+/// Generate the `__clap_color_arg()` function of an enum, which has no
+/// arguments of its own.
+pub fn gen_color_arg_fn_for_enum() -> proc_macro2::TokenStream {
+    let prefix_type = from_argmatches::prefix_type();
+
+    quote! {
+        pub fn __clap_color_arg(_prefix: #prefix_type) -> Option<&'static str> {
+            None
+        }
+    }
+}
+
+/// Generate the code applying the color choice found in `args` to `app`,
+/// before parsing so that errors are colored accordingly.
+pub fn gen_apply_color_choice(name: &syn::Ident) -> proc_macro2::TokenStream {
+    let no_prefix = from_argmatches::no_prefix();
+    quote! {
+        let app = match #name::__clap_color_arg(#no_prefix) {
+            Some(long) => {
+                let flag = format!("--{}", long);
+                let flag_eq = format!("{}=", flag);
+                let mut choice = None;
+                let mut iter = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+                while let Some(arg) = iter.next() {
+                    if arg == "--" {
+                        break;
+                    } else if arg == flag {
+                        choice = iter.next().map(|choice| choice.into_owned());
+                    } else if arg.starts_with(&flag_eq) {
+                        choice = Some(arg[flag_eq.len()..].to_string());
+                    }
+                }
+
+                match choice.as_ref().map(String::as_str) {
+                    Some("auto") => app.setting(::clap::AppSettings::ColorAuto),
+                    Some("always") => app.setting(::clap::AppSettings::ColorAlways),
+                    Some("never") => app.setting(::clap::AppSettings::ColorNever),
+                    _ => app,
+                }
+            }
+            None => app,
+        };
+    }
+}
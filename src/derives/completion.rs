@@ -0,0 +1,807 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of completion specs consumed by external completion engines.
+
+use proc_macro2;
+use syn::{self, spanned::Spanned};
+
+use super::{config, from_argmatches, plugin, sub_type, Attrs, Kind, ParserKind, Ty, VariantAttrs};
+
+/// Generate the carapace spec functions of a struct, its flags being
+/// named under the runtime `prefix` as `__clap_augment_app_prefixed()`
+/// declares them, none without `#[clap(docs)]`.
+pub fn gen_carapace_fns_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_docs() {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let flags = gen_carapace_flags(fields, parent_attribute, &prefix);
+    let commands = gen_carapace_commands(fields, &prefix);
+    let body = gen_carapace_body(fields, parent_attribute, &prefix);
+
+    quote! {
+        pub fn __clap_carapace_flags(
+            flags: &mut Vec<String>,
+            completions: &mut Vec<String>,
+            positionals: &mut Vec<String>,
+            #prefix: #prefix_type,
+        ) {
+            #propagated_casing
+            #flags
+        }
+
+        pub fn __clap_carapace_commands(commands: &mut String, indent: &str, #prefix: #prefix_type) {
+            #propagated_casing
+            #commands
+        }
+
+        pub fn __clap_carapace_body(spec: &mut String, indent: &str, #prefix: #prefix_type) {
+            #propagated_casing
+            #body
+        }
+    }
+}
+
+/// Generate the carapace spec functions of an enum, every variant being
+/// a subcommand, none without `#[clap(docs)]`.
+pub fn gen_carapace_fns_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    if !parent_attribute.has_docs() {
+        return quote!();
+    }
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let entries = subcommand_variants.map(|(variant, attrs, fields)| {
+        let name_line = format!("- name: {}\n", yaml_quote(&attrs.cased_name().value()));
+        let description = attrs.about_text().map(|about| {
+            let description_line = format!("  description: {}\n", yaml_quote(&about));
+            quote! {
+                commands.push_str(indent);
+                commands.push_str(#description_line);
+            }
+        });
+        let body = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                let body = gen_carapace_body(fields, attrs, &prefix);
+                quote! {
+                    let #prefix = Self::__clap_casing_prefix(#prefix);
+                    #propagated_casing
+                    #body
+                }
+            }
+            (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                quote!( <#ty>::__clap_carapace_body(spec, indent, Self::__clap_casing_prefix(#prefix)); )
+            }
+            _ => quote!(),
+        };
+
+        quote! {
+            commands.push_str(indent);
+            commands.push_str(#name_line);
+            #description
+            {
+                let spec = &mut *commands;
+                let indent: &str = &format!("{}  ", indent);
+                #body
+            }
+        }
+    });
+
+    quote! {
+        pub fn __clap_carapace_flags(
+            flags: &mut Vec<String>,
+            completions: &mut Vec<String>,
+            positionals: &mut Vec<String>,
+            #prefix: #prefix_type,
+        ) {
+        }
+
+        pub fn __clap_carapace_commands(commands: &mut String, indent: &str, #prefix: #prefix_type) {
+            #propagated_casing
+            #( #entries )*
+        }
+
+        pub fn __clap_carapace_body(spec: &mut String, indent: &str, #prefix: #prefix_type) {
+            let mut commands = String::new();
+            Self::__clap_carapace_commands(&mut commands, &format!("{}  ", indent), #prefix);
+            if !commands.is_empty() {
+                spec.push_str(indent);
+                spec.push_str("commands:\n");
+                spec.push_str(&commands);
+            }
+        }
+    }
+}
+
+/// Generate the public `carapace_spec()` function, none without
+/// `#[clap(docs)]`.
+pub fn gen_carapace_spec_fn(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_docs() {
+        return quote!();
+    }
+
+    let no_prefix = from_argmatches::no_prefix();
+    let mut header = format!("name: {}\n", yaml_quote(&parent_attribute.cased_name().value()));
+    if let Some(about) = parent_attribute.about_text() {
+        header.push_str(&format!("description: {}\n", yaml_quote(&about)));
+    }
+
+    quote! {
+        /// Render a [carapace](https://github.com/rsteube/carapace-spec) completion
+        /// spec of the whole command tree, as YAML.
+        ///
+        /// The `complete_with` functions of the arguments are called once, to
+        /// list their values in the spec: it is a snapshot of the values of
+        /// the time it is rendered, the dynamic completion calling them
+        /// afresh instead.
+        pub fn carapace_spec() -> String {
+            let mut spec = String::from(#header);
+            #name::__clap_carapace_body(&mut spec, "", #no_prefix);
+            spec
+        }
+    }
+}
+
+/// Code writing the `flags`, `completion` and `commands` sections of a
+/// command made of `fields` into `spec`, at `indent`.
+fn gen_carapace_body(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let flags = gen_carapace_flags(fields, parent_attribute, prefix);
+    let commands = gen_carapace_commands(fields, prefix);
+
+    quote! {
+        let mut flags_section: Vec<String> = Vec::new();
+        let mut completions_section: Vec<String> = Vec::new();
+        let mut positionals_section: Vec<String> = Vec::new();
+        {
+            let flags = &mut flags_section;
+            let completions = &mut completions_section;
+            let positionals = &mut positionals_section;
+            #flags
+        }
+
+        let mut commands_section = String::new();
+        {
+            let commands = &mut commands_section;
+            let indent: &str = &format!("{}  ", indent);
+            #commands
+        }
+
+        if !flags_section.is_empty() {
+            spec.push_str(indent);
+            spec.push_str("flags:\n");
+            for flag in flags_section {
+                spec.push_str(&format!("{}  {}\n", indent, flag));
+            }
+        }
+
+        if !completions_section.is_empty() || !positionals_section.is_empty() {
+            spec.push_str(indent);
+            spec.push_str("completion:\n");
+            if !completions_section.is_empty() {
+                spec.push_str(&format!("{}  flag:\n", indent));
+                for completion in completions_section {
+                    spec.push_str(&format!("{}    {}\n", indent, completion));
+                }
+            }
+            if !positionals_section.is_empty() {
+                spec.push_str(&format!("{}  positional:\n", indent));
+                for positional in positionals_section {
+                    spec.push_str(&format!("{}    - {}\n", indent, positional));
+                }
+            }
+        }
+
+        if !commands_section.is_empty() {
+            spec.push_str(indent);
+            spec.push_str("commands:\n");
+            spec.push_str(&commands_section);
+        }
+    }
+}
+
+/// Code pushing the flag, value completion and positional entries of
+/// `fields` into `flags`, `completions` and `positionals`, flagged under the
+/// runtime `prefix`.
+fn gen_carapace_flags(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let entries = fields.iter().map(|(field, attrs)| {
+        let kind = attrs.kind();
+        match &*kind {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                quote_spanned! { kind.span()=>
+                    <#ty>::__clap_carapace_flags(flags, completions, positionals, #flatten_prefix);
+                }
+            }
+
+            Kind::Arg(ty) => {
+                let values = gen_candidates(&attrs).map(|values| {
+                    quote! {{
+                        let values: Vec<String> =
+                            #values.iter().map(|v| format!("{:?}", v)).collect();
+                        format!("[{}]", values.join(", "))
+                    }}
+                });
+
+                if attrs.is_positional() {
+                    let values = values.unwrap_or_else(|| quote!(String::from("[]")));
+                    return quote!( positionals.push(#values); );
+                }
+
+                let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
+                let takes_value = takes_value(&attrs, **ty);
+                let repeatable = match **ty {
+                    Ty::Vec | Ty::OptionVec => true,
+                    Ty::Other => occurrences,
+                    _ => false,
+                };
+                let suffix = match (takes_value, repeatable) {
+                    (true, true) => "=*",
+                    (true, false) => "=",
+                    (false, true) => "*",
+                    (false, false) => "",
+                };
+                let (short, long) = gen_runtime_flags(&attrs, parent_attribute, prefix);
+                let help = yaml_quote(&attrs.help_text().unwrap_or_default());
+
+                let completion = values.map(|values| {
+                    quote! {
+                        if let Some(key) = long.map(String::from).or_else(|| short.map(|s| s.to_string())) {
+                            completions.push(format!("\"{}\": {}", key, #values));
+                        }
+                    }
+                });
+
+                // flags are made of letters, digits and dashes, which need
+                // no escaping in a YAML double-quoted scalar
+                quote! {{
+                    let short: Option<char> = #short;
+                    let long: Option<&'static str> = #long;
+                    let spec: Vec<String> = short
+                        .map(|short| format!("-{}", short))
+                        .into_iter()
+                        .chain(long.map(|long| format!("--{}", long)))
+                        .collect();
+                    flags.push(format!("\"{}{}\": {}", spec.join(", "), #suffix, #help));
+                    #completion
+                }}
+            }
+
+            Kind::Subcommand(_) | Kind::Skip(_) => quote!(),
+        }
+    });
+
+    quote!( #( #entries )* )
+}
+
+/// Code writing the subcommand entries contributed by `fields` into
+/// `commands`, at `indent`.
+fn gen_carapace_commands(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let entries = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                Some(quote! {
+                    <#subcmd_type>::__clap_carapace_commands(
+                        commands,
+                        indent,
+                        Self::__clap_casing_prefix(#prefix),
+                    );
+                })
+            }
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                Some(quote!( <#ty>::__clap_carapace_commands(commands, indent, #flatten_prefix); ))
+            }
+            _ => None,
+        }
+    });
+
+    quote!( #( #entries )* )
+}
+
+/// Generate the dynamic completion functions of a struct, its flags being
+/// named under the runtime `prefix`, none without
+/// `#[clap(dynamic_completion)]`.
+pub fn gen_complete_fns_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_dynamic_completion() {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let flags = gen_complete_flags(fields, parent_attribute, &prefix);
+    let subcommands = gen_complete_subcommands(fields, &prefix);
+    let complete_fn = gen_complete_fn();
+    let complete_with = gen_complete_with_fn();
+
+    quote! {
+        pub fn __clap_complete_flags(
+            flags: &mut Vec<(Option<char>, Option<&'static str>, bool, fn() -> Vec<String>)>,
+            positionals: &mut Vec<fn() -> Vec<String>>,
+            #prefix: #prefix_type,
+        ) {
+            #propagated_casing
+            #flags
+        }
+
+        pub fn __clap_complete_subcommands(
+            names: &mut Vec<&'static str>,
+            name: &str,
+            words: &[String],
+            #prefix: #prefix_type,
+        ) -> Option<Vec<String>> {
+            #propagated_casing
+            #subcommands
+        }
+
+        pub fn __clap_complete_prefixed(words: &[String], #prefix: #prefix_type) -> Vec<String> {
+            let mut flags = Vec::new();
+            let mut positionals = Vec::new();
+            let mut names = Vec::new();
+            Self::__clap_complete_flags(&mut flags, &mut positionals, #prefix);
+            Self::__clap_complete_subcommands(&mut names, "", &[], #prefix);
+            Self::__clap_complete_with(words, &flags, &positionals, &names, &|name, words| {
+                Self::__clap_complete_subcommands(&mut Vec::new(), name, words, #prefix)
+            })
+        }
+
+        #complete_fn
+
+        #complete_with
+    }
+}
+
+/// Generate the dynamic completion functions of an enum, every variant being
+/// a subcommand, none without `#[clap(dynamic_completion)]`.
+pub fn gen_complete_fns_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    if !parent_attribute.has_dynamic_completion() {
+        return quote!();
+    }
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let arms = subcommand_variants.map(|(variant, attrs, fields)| {
+        let name = attrs.cased_name();
+        let complete = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                let flags = gen_complete_flags(fields, attrs, &prefix);
+                let subcommands = gen_complete_subcommands(fields, &prefix);
+                quote! {{
+                    let #prefix = Self::__clap_casing_prefix(#prefix);
+                    #propagated_casing
+                    let mut flags: Vec<(Option<char>, Option<&'static str>, bool, fn() -> Vec<String>)> =
+                        Vec::new();
+                    let mut positionals: Vec<fn() -> Vec<String>> = Vec::new();
+                    {
+                        let flags = &mut flags;
+                        let positionals = &mut positionals;
+                        #flags
+                    }
+                    let children = |names: &mut Vec<&'static str>, name: &str, words: &[String]| {
+                        #subcommands
+                    };
+                    let mut names = Vec::new();
+                    children(&mut names, "", &[]);
+                    Self::__clap_complete_with(words, &flags, &positionals, &names, &|name, words| {
+                        children(&mut Vec::new(), name, words)
+                    })
+                }}
+            }
+            (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                quote!( <#ty>::__clap_complete_prefixed(words, Self::__clap_casing_prefix(#prefix)) )
+            }
+            _ => quote!( Self::__clap_complete_with(words, &[], &[], &[], &|_, _| None) ),
+        };
+
+        quote! {
+            names.push(#name);
+            if name == #name {
+                return Some(#complete);
+            }
+        }
+    });
+    let complete_fn = gen_complete_fn();
+    let complete_with = gen_complete_with_fn();
+
+    quote! {
+        pub fn __clap_complete_flags(
+            flags: &mut Vec<(Option<char>, Option<&'static str>, bool, fn() -> Vec<String>)>,
+            positionals: &mut Vec<fn() -> Vec<String>>,
+            #prefix: #prefix_type,
+        ) {
+        }
+
+        pub fn __clap_complete_subcommands(
+            names: &mut Vec<&'static str>,
+            name: &str,
+            words: &[String],
+            #prefix: #prefix_type,
+        ) -> Option<Vec<String>> {
+            #propagated_casing
+            #( #arms )*
+            None
+        }
+
+        pub fn __clap_complete_prefixed(words: &[String], #prefix: #prefix_type) -> Vec<String> {
+            let mut names = Vec::new();
+            Self::__clap_complete_subcommands(&mut names, "", &[], #prefix);
+            Self::__clap_complete_with(words, &[], &[], &names, &|name, words| {
+                Self::__clap_complete_subcommands(&mut Vec::new(), name, words, #prefix)
+            })
+        }
+
+        #complete_fn
+
+        #complete_with
+    }
+}
+
+/// Generate the code answering a completion request, if the type is marked
+/// with `#[clap(dynamic_completion)]`.
+///
+/// `bin complete -- <words>...` fails with a `HelpDisplayed` error listing
+/// the candidates for the last word, one per line, which `parse` prints
+/// before exiting successfully. The hidden `complete` subcommand is only
+/// taken as such when `--` follows it, so that a value named `complete`
+/// is still parsed as usual.
+pub fn gen_dynamic_completion(name: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    if !attrs.has_dynamic_completion() {
+        return quote!();
+    }
+
+    quote! {
+        if args.len() > 2 && args[1] == "complete" && args[2] == "--" {
+            let words: Vec<String> = args
+                .iter()
+                .skip(3)
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            let mut e = ::clap::Error::with_description("", ::clap::ErrorKind::HelpDisplayed);
+            e.message = #name::__clap_complete(&words).join("\n");
+            return Err(e);
+        }
+    }
+}
+
+/// Generate `__clap_completion_scripts()`, the bash, zsh and fish scripts
+/// completing the command through `bin complete --`, as `(file name,
+/// contents)` pairs, none without `#[clap(dynamic_completion)]`.
+pub fn gen_completion_scripts_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    if !attrs.has_dynamic_completion() {
+        return quote!();
+    }
+
+    let bin = attrs.cased_name().value();
+    let function = format!("_{}", bin.replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+    let scripts = vec![
+        (
+            format!("{}.bash", bin),
+            format!(
+                "{function}() {{\n    \
+                 local IFS=$'\\n'\n    \
+                 COMPREPLY=($({bin} complete -- \"${{COMP_WORDS[@]:1:COMP_CWORD}}\"))\n\
+                 }}\n\
+                 complete -o default -F {function} {bin}\n",
+                function = function,
+                bin = bin,
+            ),
+        ),
+        (
+            format!("_{}", bin),
+            format!(
+                "#compdef {bin}\n\n\
+                 local -a candidates\n\
+                 candidates=(${{(f)\"$({bin} complete -- \"${{(@)words[2,CURRENT]}}\")\"}})\n\
+                 compadd -a candidates\n",
+                bin = bin,
+            ),
+        ),
+        (
+            format!("{}.fish", bin),
+            format!(
+                "complete -c {bin} -a '({bin} complete -- (commandline -opc)[2..-1] (commandline -ct))'\n",
+                bin = bin,
+            ),
+        ),
+    ];
+    let scripts = scripts.iter().map(|(file_name, contents)| {
+        quote!( (String::from(#file_name), String::from(#contents)) )
+    });
+
+    quote! {
+        pub fn __clap_completion_scripts() -> Vec<(String, String)> {
+            vec![ #( #scripts ),* ]
+        }
+    }
+}
+
+/// Generate `__clap_complete()`, the candidates of the last of `words`, the
+/// arguments following the binary name on the command line being completed.
+fn gen_complete_fn() -> proc_macro2::TokenStream {
+    let no_prefix = from_argmatches::no_prefix();
+
+    quote! {
+        /// Completion candidates of the last of `words`, the arguments
+        /// following the binary name on the command line being completed.
+        pub fn __clap_complete(words: &[String]) -> Vec<String> {
+            Self::__clap_complete_prefixed(words, #no_prefix)
+        }
+    }
+}
+
+/// Generate `__clap_complete_with()`, computing the candidates of the last
+/// of `words` given the arguments and subcommands of a command.
+fn gen_complete_with_fn() -> proc_macro2::TokenStream {
+    quote! {
+        pub fn __clap_complete_with(
+            words: &[String],
+            flags: &[(Option<char>, Option<&'static str>, bool, fn() -> Vec<String>)],
+            positionals: &[fn() -> Vec<String>],
+            subcommands: &[&'static str],
+            subcommand: &dyn Fn(&str, &[String]) -> Option<Vec<String>>,
+        ) -> Vec<String> {
+            let (current, before) = match words.split_last() {
+                Some(split) => split,
+                None => return Vec::new(),
+            };
+
+            let mut positional = 0;
+            let mut expecting: Option<fn() -> Vec<String>> = None;
+            let mut options_done = false;
+            for (i, word) in before.iter().enumerate() {
+                if expecting.take().is_some() {
+                    continue;
+                }
+                if !options_done && word == "--" {
+                    options_done = true;
+                    continue;
+                }
+                if !options_done && word.starts_with('-') && word.len() > 1 {
+                    let flag = if word.starts_with("--") {
+                        flags.iter().find(|flag| flag.1 == Some(&word[2..]))
+                    } else if word.chars().count() == 2 {
+                        flags.iter().find(|flag| flag.0 == word.chars().nth(1))
+                    } else {
+                        None
+                    };
+                    expecting = flag.filter(|flag| flag.2).map(|flag| flag.3);
+                    continue;
+                }
+                if let Some(candidates) = subcommand(word, &words[i + 1..]) {
+                    return candidates;
+                }
+                positional += 1;
+            }
+
+            let mut candidates: Vec<String> = Vec::new();
+            if let Some(values) = expecting {
+                candidates.extend(values());
+            } else if !options_done && current.starts_with('-') {
+                for flag in flags {
+                    candidates.extend(flag.1.map(|long| format!("--{}", long)));
+                    candidates.extend(flag.0.map(|short| format!("-{}", short)));
+                }
+            } else {
+                if positional == 0 {
+                    candidates.extend(subcommands.iter().map(|name| name.to_string()));
+                }
+                if let Some(values) = positionals.get(positional) {
+                    candidates.extend(values());
+                }
+            }
+
+            candidates.retain(|candidate| candidate.starts_with(current.as_str()));
+            candidates
+        }
+    }
+}
+
+/// Code pushing the flag and positional entries of `fields` into `flags`
+/// and `positionals`, flagged under the runtime `prefix`.
+fn gen_complete_flags(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let entries = fields.iter().map(|(field, attrs)| {
+        let kind = attrs.kind();
+        match &*kind {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                quote_spanned! { kind.span()=>
+                    <#ty>::__clap_complete_flags(flags, positionals, #flatten_prefix);
+                }
+            }
+
+            Kind::Arg(ty) => {
+                // only called once the values of the argument are completed
+                let values = gen_candidates(&attrs).unwrap_or_else(|| quote!(Vec::<String>::new()));
+                let values = quote!( ((|| #values) as fn() -> Vec<String>) );
+
+                if attrs.is_positional() {
+                    return quote!( positionals.push(#values); );
+                }
+
+                let (short, long) = gen_runtime_flags(&attrs, parent_attribute, prefix);
+                let takes_value = takes_value(&attrs, **ty);
+
+                quote!( flags.push((#short, #long, #takes_value, #values)); )
+            }
+
+            Kind::Subcommand(_) | Kind::Skip(_) => quote!(),
+        }
+    });
+
+    quote!( #( #entries )* )
+}
+
+/// Code pushing the subcommand names contributed by `fields` into `names`,
+/// and returning the candidates of the subcommand `name` given `words`.
+fn gen_complete_subcommands(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let children = fields.iter().filter_map(|(field, attrs)| {
+        let (ty, prefix) = match &*attrs.kind() {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                (subcmd_type, quote!( Self::__clap_casing_prefix(#prefix) ))
+            }
+            Kind::FlattenStruct => {
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                (&field.ty, flatten_prefix)
+            }
+            _ => return None,
+        };
+
+        Some(quote! {
+            if let Some(candidates) = <#ty>::__clap_complete_subcommands(names, name, words, #prefix) {
+                return Some(candidates);
+            }
+        })
+    });
+
+    quote! {
+        #( #children )*
+        None
+    }
+}
+
+/// The expression building the candidate values of the argument described
+/// by `attrs`: the ones returned by its `complete_with` function, or else its
+/// possible values.
+fn gen_candidates(attrs: &Attrs) -> Option<proc_macro2::TokenStream> {
+    if let Some(func) = attrs.complete_with() {
+        return Some(quote_spanned! { func.span()=>
+            (#func)()
+                .into_iter()
+                .map(::std::convert::Into::into)
+                .collect::<Vec<String>>()
+        });
+    }
+    attrs.possible_values().map(|values| {
+        quote! {{
+            let values: &[&str] = #values;
+            values.iter().map(|v| v.to_string()).collect::<Vec<String>>()
+        }}
+    })
+}
+
+/// The expressions of the short and long flags of the argument described by
+/// `attrs`, as `Option<char>` and `Option<&'static str>`, the long one
+/// under the runtime `prefix` and the short one only when there's none.
+fn gen_runtime_flags(
+    attrs: &Attrs,
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let short = match attrs.short_name() {
+        Some(short) => quote! {
+            if #prefix.0.is_empty() { Some(#short) } else { None }
+        },
+        None => quote!(None),
+    };
+    let long = match attrs.long_name() {
+        Some(_) => {
+            let attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+            let long = attrs.method_args("long");
+            quote!( Some(#long) )
+        }
+        None => quote!(None),
+    };
+    (short, long)
+}
+
+/// Whether the argument described by `attrs` takes a value.
+pub fn takes_value(attrs: &Attrs, ty: Ty) -> bool {
+    let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
+    let flag = *attrs.parser().kind == ParserKind::FromFlag;
+    match ty {
+        Ty::Bool => false,
+        Ty::Other => !occurrences && !flag,
+        _ => true,
+    }
+}
+
+/// Quote `s` as a YAML double-quoted scalar.
+fn yaml_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
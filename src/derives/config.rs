@@ -0,0 +1,837 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Layering of configuration files beneath environment variables and
+//! command line arguments.
+
+use proc_macro2;
+use syn;
+
+use super::{from_argmatches, plugin, sub_type, Attrs, Kind, ParserKind, Ty, VariantAttrs};
+
+/// Generate the `__clap_config_args()` function of a struct, listing the
+/// `(name, kind)` pairs of the arguments a configuration file can give,
+/// flattened ones included, with `prefix` in front, and the
+/// `__clap_config_args_at()` one, listing those of the subcommand at a
+/// path.
+///
+/// The kind is `"value"`, `"values"` or `"flag"`, a `bool` flag being set
+/// by a `true` value.
+///
+/// None without the `toml` feature: `config_file` is only given on top of
+/// the command, and the subcommands and flattened structs below it carry
+/// nothing telling they are read from a configuration file.
+pub fn gen_config_args_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !cfg!(feature = "toml") {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let args = gen_config_args(fields, &prefix);
+    let args_at = gen_fields_config_args_at(fields, &prefix);
+
+    quote! {
+        pub fn __clap_config_args(
+            #prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str)> {
+            #propagated_casing
+            let args: Vec<Vec<(&'static str, &'static str)>> = vec![ #( #args ),* ];
+            args.into_iter().flatten().collect()
+        }
+
+        pub fn __clap_config_args_at(
+            path: &[&str],
+            #prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str)> {
+            if path.is_empty() {
+                return Self::__clap_config_args(#prefix);
+            }
+            #propagated_casing
+            let args: Vec<Vec<(&'static str, &'static str)>> = vec![ #( #args_at ),* ];
+            args.into_iter().flatten().collect()
+        }
+    }
+}
+
+/// Expressions evaluating to the `(name, kind)` pairs of the arguments of
+/// `fields` a configuration file can give, flattened ones included, with the
+/// value of the `prefix` variable in front of their names.
+fn gen_config_args(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            let kind = match &*attrs.kind() {
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    let prefix = gen_flatten_prefix(Some(prefix), &attrs);
+                    return Some(quote!( <#ty>::__clap_config_args(#prefix) ));
+                }
+                Kind::Arg(ty) => match **ty {
+                    Ty::Bool if attrs.is_explicit_bool() => "value",
+                    Ty::Bool => "flag",
+                    Ty::Vec | Ty::OptionVec => "values",
+                    Ty::Other => match *attrs.parser().kind {
+                        ParserKind::FromFlag => "flag",
+                        ParserKind::FromOccurrences => return None,
+                        _ => "value",
+                    },
+                    Ty::Option | Ty::OptionOption => "value",
+                },
+                Kind::Subcommand(_) | Kind::Skip(_) => return None,
+            };
+            let name = attrs.cased_name();
+            Some(quote!( vec![(Self::__clap_prefixed(#prefix, #name), #kind)] ))
+        })
+        .collect()
+}
+
+/// Expressions evaluating to the `__clap_config_args_at(path, prefix)`
+/// entries of the subcommands held by `fields`, directly or in flattened
+/// structs.
+fn gen_fields_config_args_at(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::Subcommand(ty) => {
+                    let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                        (Ty::Option, Some(sub_type)) => sub_type,
+                        _ => &field.ty,
+                    };
+                    Some(quote! {
+                        <#subcmd_type>::__clap_config_args_at(
+                            path,
+                            Self::__clap_casing_prefix(#prefix),
+                        )
+                    })
+                }
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    let prefix = gen_flatten_prefix(Some(prefix), &attrs);
+                    Some(quote!( <#ty>::__clap_config_args_at(path, #prefix) ))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Generate the `__clap_config_args()` function of an enum, which has no
+/// arguments of its own, and the `__clap_config_args_at()` one, finding
+/// those of the subcommand at a path among its variants, none without the
+/// `toml` feature.
+pub fn gen_config_args_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    if !cfg!(feature = "toml") {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let arms = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .map(|(variant, attrs, fields)| {
+            let name = attrs.cased_name();
+            let args = match (&variant.fields, fields) {
+                (Named(_), Some(fields)) => {
+                    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                    let args = gen_config_args(fields, &prefix);
+                    let args_at = gen_fields_config_args_at(fields, &prefix);
+                    quote! {{
+                        let #prefix = Self::__clap_casing_prefix(#prefix);
+                        #propagated_casing
+                        let args: Vec<Vec<(&'static str, &'static str)>> = if path.len() == 1 {
+                            vec![ #( #args ),* ]
+                        } else {
+                            let path = &path[1..];
+                            vec![ #( #args_at ),* ]
+                        };
+                        args.into_iter().flatten().collect()
+                    }}
+                }
+                (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                    let ty = &unnamed[0].ty;
+                    quote! {
+                        <#ty>::__clap_config_args_at(&path[1..], Self::__clap_casing_prefix(#prefix))
+                    }
+                }
+                _ => quote!(Vec::new()),
+            };
+            quote!( Some(&#name) => #args, )
+        });
+
+    quote! {
+        pub fn __clap_config_args(
+            #prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str)> {
+            Vec::new()
+        }
+
+        pub fn __clap_config_args_at(
+            path: &[&str],
+            #prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str)> {
+            #propagated_casing
+            match path.first() {
+                #( #arms )*
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+/// Generate the `__clap_arg_ids()` function of a struct, listing the ids of
+/// all its arguments, flattened ones included, with `prefix` in front.
+pub fn gen_arg_ids_for_struct(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let ids = gen_arg_ids(fields, &prefix);
+
+    quote! {
+        pub fn __clap_arg_ids(#prefix: #prefix_type) -> Vec<&'static str> {
+            #ids
+        }
+    }
+}
+
+/// Generate the expression listing the ids of the arguments of `fields`,
+/// flattened ones included, with the value of the `prefix` variable in front.
+pub fn gen_arg_ids(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let ids = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let prefix = gen_flatten_prefix(Some(prefix), &attrs);
+                Some(quote!( <#ty>::__clap_arg_ids(#prefix) ))
+            }
+            Kind::Arg(_) => {
+                let name = attrs.cased_name();
+                Some(quote!( vec![Self::__clap_prefixed(#prefix, #name)] ))
+            }
+            Kind::Subcommand(_) | Kind::Skip(_) => None,
+        }
+    });
+
+    quote! {{
+        let ids: Vec<Vec<&'static str>> = vec![ #( #ids ),* ];
+        ids.into_iter().flatten().collect::<Vec<&'static str>>()
+    }}
+}
+
+/// Generate the `__clap_arg_ids()` function of an enum, which has no
+/// arguments of its own.
+pub fn gen_arg_ids_for_enum() -> proc_macro2::TokenStream {
+    let prefix_type = from_argmatches::prefix_type();
+    quote! {
+        pub fn __clap_arg_ids(prefix: #prefix_type) -> Vec<&'static str> {
+            Vec::new()
+        }
+    }
+}
+
+/// Generate the `__clap_env_args()` function of a struct, listing the
+/// `(name, environment variable, help)` triples of its env-backed
+/// arguments, flattened ones included, and `__clap_env_fallbacks()`.
+pub fn gen_env_args_for_struct(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let env_args = gen_env_args(fields);
+    let env_fallbacks = gen_env_fallbacks(fields);
+
+    quote! {
+        pub fn __clap_env_args() -> Vec<(&'static str, &'static str, &'static str)> {
+            #env_args
+        }
+        #env_fallbacks
+    }
+}
+
+/// Generate the `__clap_env_fallbacks()` function of a struct, listing the
+/// `(name, environment variable, fallback variables)` triples of its
+/// arguments with `env = [...]`, flattened ones included.
+fn gen_env_fallbacks(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let mut own = Vec::new();
+    let mut flattened = Vec::new();
+    for (field, attrs) in fields {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                flattened.push(match attrs.env_prefix() {
+                    Some(prefix) => {
+                        let prefix = &**prefix;
+                        quote! {
+                            <#ty>::__clap_env_fallbacks()
+                                .into_iter()
+                                .map(|(name, env, fallbacks)| {
+                                    let prefixed = |env: &'static str| {
+                                        Self::__clap_joined(#prefix, "_", env)
+                                    };
+                                    let fallbacks = fallbacks.into_iter().map(prefixed).collect();
+                                    (name, prefixed(env), fallbacks)
+                                })
+                        }
+                    }
+                    None => quote!( <#ty>::__clap_env_fallbacks() ),
+                })
+            }
+            Kind::Arg(_) if !attrs.env_fallbacks().is_empty() => {
+                let name = attrs.cased_name();
+                let env = attrs.env_name();
+                let fallbacks = attrs.env_fallbacks();
+                own.push(quote!( (#name, #env, &[#( #fallbacks ),*]) ));
+            }
+            _ => (),
+        }
+    }
+
+    quote! {
+        pub fn __clap_env_fallbacks(
+        ) -> Vec<(&'static str, &'static str, Vec<&'static str>)> {
+            const OWN: &[(&str, &str, &[&str])] = &[ #( #own ),* ];
+            OWN.iter()
+                .map(|&(name, env, fallbacks)| (name, env, fallbacks.to_vec()))
+                #( .chain(#flattened) )*
+                .collect()
+        }
+    }
+}
+
+/// Expression listing the `(name, environment variable, help)` triples of
+/// the env-backed arguments of `fields`.
+///
+/// The arguments of `fields` themselves come from a static slice, only the
+/// flattened ones are collected at runtime.
+pub fn gen_env_args(fields: &[(&syn::Field, Attrs)]) -> proc_macro2::TokenStream {
+    let mut own = Vec::new();
+    let mut flattened = Vec::new();
+    for (field, attrs) in fields {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                flattened.push(match attrs.env_prefix() {
+                    Some(prefix) => {
+                        let prefix = &**prefix;
+                        quote! {
+                            <#ty>::__clap_env_args()
+                                .into_iter()
+                                .map(|(name, env, help)| {
+                                    (name, Self::__clap_joined(#prefix, "_", env), help)
+                                })
+                        }
+                    }
+                    None => quote!( <#ty>::__clap_env_args() ),
+                })
+            }
+            Kind::Arg(_) => {
+                if let Some(env) = attrs.env_name() {
+                    let name = attrs.cased_name();
+                    let help = attrs.help_text().unwrap_or_default();
+                    own.push(quote!( (#name, #env, #help) ));
+                }
+            }
+            Kind::Subcommand(_) | Kind::Skip(_) => (),
+        }
+    }
+
+    quote! {{
+        const OWN: &[(&str, &str, &str)] = &[ #( #own ),* ];
+        OWN.iter().cloned() #( .chain(#flattened) )* .collect::<Vec<_>>()
+    }}
+}
+
+/// The `(environment variable, help)` pairs of the env-backed arguments of
+/// `fields`, when known at expansion time, that is without flattened ones.
+pub fn static_env_args(fields: &[(&syn::Field, Attrs)]) -> Option<Vec<(String, String)>> {
+    let mut env_args = Vec::new();
+    for (field, attrs) in fields {
+        match &*attrs.kind() {
+            Kind::FlattenStruct => return None,
+            Kind::Arg(_) => {
+                if let Some(env) = attrs.env_name() {
+                    env_args.push((env, attrs.help_text().unwrap_or_default()));
+                }
+            }
+            Kind::Subcommand(_) | Kind::Skip(_) => (),
+        }
+    }
+    Some(env_args)
+}
+
+/// Generate the `__clap_env_args()` and `__clap_env_fallbacks()` functions
+/// of an enum, which has no arguments of its own.
+pub fn gen_env_args_for_enum() -> proc_macro2::TokenStream {
+    quote! {
+        pub fn __clap_env_args() -> Vec<(&'static str, &'static str, &'static str)> {
+            Vec::new()
+        }
+        pub fn __clap_env_fallbacks(
+        ) -> Vec<(&'static str, &'static str, Vec<&'static str>)> {
+            Vec::new()
+        }
+    }
+}
+
+/// Generate the code renaming the environment variables of the arguments
+/// flattened from `ty` according to `env_prefix`, if any.
+pub fn gen_env_prefix(
+    app_var: &syn::Ident,
+    ty: &syn::Type,
+    attrs: &Attrs,
+) -> proc_macro2::TokenStream {
+    let prefix = match attrs.env_prefix() {
+        Some(prefix) => &**prefix,
+        None => return quote!(),
+    };
+
+    quote! {
+        let env_fallbacks = <#ty>::__clap_env_fallbacks();
+        let #app_var = <#ty>::__clap_env_args().into_iter().fold(
+            #app_var,
+            |app, (name, env, _)| {
+                let fallbacks = env_fallbacks
+                    .iter()
+                    .find(|fallbacks| fallbacks.0 == name)
+                    .map_or(&[][..], |fallbacks| &fallbacks.2[..]);
+                let envs: Vec<&'static str> = ::std::iter::once(env)
+                    .chain(fallbacks.iter().cloned())
+                    .map(|env| Self::__clap_joined(#prefix, "_", env))
+                    .collect();
+                app.mut_arg(name, |arg| arg.env(Self::__clap_first_env(&envs)))
+            }
+        );
+    }
+}
+
+/// The runtime prefix of the arguments flattened at a field with `attrs`,
+/// combining the `prefix` given there with the one of the enclosing struct,
+/// held by the `prefix` variable when there is one, and carrying the casing
+/// given there with `rename_all`, if any.
+///
+/// The prefix of the environment variables is derived here, so that only
+/// concatenations are left to runtime. `None` when nothing is prefixed.
+pub fn gen_flatten_prefix(
+    prefix: Option<&syn::Ident>,
+    attrs: &Attrs,
+) -> Option<proc_macro2::TokenStream> {
+    let own = attrs.prefix().map(|own| &**own);
+    let own_env = own.map(|own| own.to_uppercase().replace('-', "_"));
+    let casing = attrs.flatten_casing();
+    let prefix = match (prefix, own) {
+        (Some(prefix), Some(own)) => quote!( Self::__clap_nested_prefix(#prefix, #own, #own_env) ),
+        (Some(prefix), None) => quote!(#prefix),
+        (None, Some(own)) => quote!( (#own, #own_env, None, None) ),
+        (None, None) if casing.is_some() => from_argmatches::no_prefix(),
+        (None, None) => return None,
+    };
+    Some(match casing {
+        Some(casing) => quote!( Self::__clap_cased_prefix(#prefix, #casing, false) ),
+        None => prefix,
+    })
+}
+
+/// Generate the argument naming the configuration file, if any.
+pub fn gen_config_file_arg(app_var: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    match attrs.config_file() {
+        Some(long) => {
+            let long = &**long;
+            quote! {
+                let #app_var = #app_var.arg(
+                    ::clap::Arg::with_name(#long)
+                        .long(#long)
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("Read default values from a TOML configuration file")
+                );
+            }
+        }
+        None => quote!(),
+    }
+}
+
+/// Generate the code loading the `.env` file requested with `#[clap(dotenv)]`.
+///
+/// `Arg::env` captures the variable when the argument is built, so this must
+/// run before the `App` is created. A missing file is not an error.
+pub fn gen_load_dotenv(attrs: &Attrs) -> proc_macro2::TokenStream {
+    match attrs.dotenv().map(|dotenv| &**dotenv) {
+        Some(Some(path)) => quote!( ::dotenv::from_path(#path).ok(); ),
+        Some(None) => quote!( ::dotenv::dotenv().ok(); ),
+        None => quote!(),
+    }
+}
+
+/// Generate the `__clap_expand_argfiles()` function, replacing every
+/// `@path` argument by the lines of the file at `path` when the command has
+/// the `argfile` attribute, and returning the arguments untouched otherwise.
+///
+/// Empty lines are skipped and nothing is expanded after a `--`.
+pub fn gen_expand_argfiles_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    let expand = if attrs.has_argfile() {
+        quote! {
+            let mut expanded = Vec::with_capacity(args.len());
+            let mut args = args.into_iter();
+            expanded.extend(args.next());
+            while let Some(arg) = args.next() {
+                let path = match arg.to_str() {
+                    Some("--") => {
+                        expanded.push(arg);
+                        expanded.extend(args);
+                        break;
+                    }
+                    Some(arg) if arg.len() > 1 && arg.starts_with('@') => {
+                        ::std::path::PathBuf::from(&arg[1..])
+                    }
+                    _ => {
+                        expanded.push(arg);
+                        continue;
+                    }
+                };
+
+                let contents = ::std::fs::read_to_string(&path).map_err(|e| {
+                    ::clap::Error::with_description(
+                        &format!("failed to read `{}`: {}", path.display(), e),
+                        ::clap::ErrorKind::Io,
+                    )
+                })?;
+                expanded.extend(
+                    contents
+                        .lines()
+                        .map(|line| line.trim_end_matches('\r'))
+                        .filter(|line| !line.is_empty())
+                        .map(::std::ffi::OsString::from),
+                );
+            }
+            Ok(expanded)
+        }
+    } else {
+        quote!(Ok(args))
+    };
+
+    quote! {
+        pub fn __clap_expand_argfiles(
+            args: Vec<::std::ffi::OsString>,
+        ) -> ::std::result::Result<Vec<::std::ffi::OsString>, ::clap::Error> {
+            #expand
+        }
+    }
+}
+
+/// Generate the `__clap_fold_long_case()` function, replacing the long flags
+/// given in another case by their declared spelling when the command has the
+/// `case_insensitive_longs` attribute, and returning the arguments untouched
+/// otherwise.
+///
+/// The flags of flattened structs are included; nothing is folded after a
+/// `--`.
+pub fn gen_fold_long_case_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    let no_prefix = from_argmatches::no_prefix();
+    let fold = if attrs.case_insensitive_longs().is_some() {
+        quote! {
+            let mut declared = Vec::new();
+            Self::__clap_debug_args(&mut declared, #no_prefix);
+            let longs: Vec<&str> = declared
+                .iter()
+                .filter_map(|&(_, _, long, _, _)| long)
+                .chain(vec!["help", "version"])
+                .collect();
+
+            let mut folded = Vec::with_capacity(args.len());
+            let mut args = args.into_iter();
+            folded.extend(args.next());
+            while let Some(arg) = args.next() {
+                let arg = match arg.to_str() {
+                    Some("--") => {
+                        folded.push(arg);
+                        folded.extend(args);
+                        break;
+                    }
+                    Some(given) if given.starts_with("--") => {
+                        let (flag, value) = match given.find('=') {
+                            Some(i) => given.split_at(i),
+                            None => (given, ""),
+                        };
+                        let flag = flag[2..].to_lowercase();
+                        match longs.iter().find(|long| long.to_lowercase() == flag) {
+                            Some(long) => ::std::ffi::OsString::from(format!("--{}{}", long, value)),
+                            None => arg,
+                        }
+                    }
+                    _ => arg,
+                };
+                folded.push(arg);
+            }
+            folded
+        }
+    } else {
+        quote!(args)
+    };
+
+    quote! {
+        pub fn __clap_fold_long_case(args: Vec<::std::ffi::OsString>) -> Vec<::std::ffi::OsString> {
+            #fold
+        }
+    }
+}
+
+/// Generate the code loading the configuration file named on the command
+/// line, if any, and using its values as defaults of the `app` arguments.
+///
+/// Defaults come last in clap's precedence, so values from the file only
+/// apply when neither the command line nor the environment provide one.
+/// Arrays give the values of arguments taking several, and `true` sets a
+/// flag.
+pub fn gen_apply_config_file(name: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    let long = match attrs.config_file() {
+        Some(long) => format!("--{}", &**long),
+        None => return quote!(),
+    };
+    let long_eq = format!("{}=", long);
+    let no_prefix = from_argmatches::no_prefix();
+
+    quote! {
+        let app = {
+            let mut path = None;
+            let mut iter = args.iter().skip(1);
+            while let Some(arg) = iter.next() {
+                match arg.to_str() {
+                    Some("--") => break,
+                    Some(#long) => path = iter.next().cloned(),
+                    Some(arg) if arg.starts_with(#long_eq) => {
+                        path = Some(arg[#long_eq.len()..].into())
+                    }
+                    _ => (),
+                }
+            }
+
+            // Subcommands read the table named after them, one level
+            // deeper; values are interned, as an `App` is built on each parse.
+            fn apply(
+                mut app: ::clap::App<'static>,
+                table: &::toml::value::Table,
+                path: &mut Vec<&'static str>,
+            ) -> ::std::result::Result<::clap::App<'static>, String> {
+                let get = |key: &str| table.get(key).or_else(|| table.get(&key.replace('-', "_")));
+                let qualified = |path: &[&str], key: &str| {
+                    [path, &[key][..]].concat().join(".")
+                };
+
+                let interned = |value: &::toml::Value| match value {
+                    ::toml::Value::String(s) => Some(#name::__clap_interned(s.clone())),
+                    ::toml::Value::Array(_) | ::toml::Value::Table(_) => None,
+                    value => Some(#name::__clap_interned(value.to_string())),
+                };
+                for (id, kind) in #name::__clap_config_args_at(path, #no_prefix) {
+                    let value = match get(id) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    app = match (kind, value) {
+                        // the flag takes an optional value so that it can
+                        // have a default one, its presence being what counts
+                        ("flag", ::toml::Value::Boolean(true)) => app.mut_arg(id, |arg| {
+                            arg.takes_value(true)
+                                .min_values(0)
+                                .max_values(1)
+                                .require_equals(true)
+                                .default_value("true")
+                        }),
+                        ("flag", ::toml::Value::Boolean(false)) => app,
+                        ("flag", _) => {
+                            return Err(format!("`{}` should be a boolean", qualified(path, id)));
+                        }
+                        ("values", ::toml::Value::Array(values)) => {
+                            let values = values
+                                .iter()
+                                .map(&interned)
+                                .collect::<Option<Vec<&'static str>>>()
+                                .ok_or_else(|| {
+                                    format!("`{}` should be an array of values", qualified(path, id))
+                                })?;
+                            let values = #name::__clap_interned_values(values);
+                            app.mut_arg(id, |arg| arg.default_values(values))
+                        }
+                        (_, value) => {
+                            let value = interned(value).ok_or_else(|| {
+                                format!("`{}` takes a single value", qualified(path, id))
+                            })?;
+                            app.mut_arg(id, |arg| arg.default_value(value))
+                        }
+                    };
+                }
+
+                let subcommands: Vec<&'static str> = #name::__clap_subcommand_paths()
+                    .into_iter()
+                    .filter(|sub| sub.len() == path.len() + 1 && sub.starts_with(path))
+                    .map(|sub| sub[path.len()])
+                    .collect();
+                for sub in subcommands {
+                    let section = match get(sub) {
+                        Some(::toml::Value::Table(section)) => section,
+                        Some(_) => {
+                            return Err(format!("`{}` should be a table", qualified(path, sub)));
+                        }
+                        None => continue,
+                    };
+                    path.push(sub);
+                    let mut error = None;
+                    app = app.mut_subcommand(sub, |subapp| {
+                        apply(subapp.clone(), section, path).unwrap_or_else(|e| {
+                            error = Some(e);
+                            subapp
+                        })
+                    });
+                    if let Some(e) = error {
+                        return Err(e);
+                    }
+                    path.pop();
+                }
+                Ok(app)
+            }
+
+            match path.map(::std::path::PathBuf::from) {
+                None => app,
+                Some(path) => {
+                    let contents = ::std::fs::read_to_string(&path).map_err(|e| {
+                        ::clap::Error::with_description(
+                            &format!("failed to read `{}`: {}", path.display(), e),
+                            ::clap::ErrorKind::Io,
+                        )
+                    })?;
+                    let invalid = |e: &dyn ::std::fmt::Display| {
+                        ::clap::Error::with_description(
+                            &format!("failed to parse `{}`: {}", path.display(), e),
+                            ::clap::ErrorKind::InvalidValue,
+                        )
+                    };
+                    let table: ::toml::value::Table =
+                        ::toml::from_str(&contents).map_err(|e| invalid(&e))?;
+                    apply(app, &table, &mut Vec::new()).map_err(|e| invalid(&e))?
+                }
+            }
+        };
+    }
+}
+
+/// Generate the `#[clap(partial)]` companion struct of `name`, holding an
+/// optional value for every argument, along with the `merge_from` function
+/// layering it beneath the values coming from the command line.
+///
+/// Returns the struct definition and the function, in that order.
+pub fn gen_partial(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    fields: &[(&syn::Field, Attrs)],
+    attrs: &Attrs,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let partial_name = match attrs.partial() {
+        Some(ident) => syn::Ident::new(&format!("{}Partial", name), ident.span()),
+        None => return (quote!(), quote!()),
+    };
+
+    let args: Vec<_> = fields
+        .iter()
+        .filter_map(|(field, field_attrs)| match &*field_attrs.kind() {
+            Kind::Arg(ty) => Some((field, field_attrs, **ty)),
+            _ => None,
+        })
+        .collect();
+
+    let partial_fields = args.iter().map(|(field, field_attrs, ty)| {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_ty = &field.ty;
+        let id = field_attrs.cased_name();
+        let alias = if id.value() != field_name.to_string() {
+            let field_name = field_name.to_string();
+            quote!( , alias = #field_name )
+        } else {
+            quote!()
+        };
+        let partial_ty = match ty {
+            Ty::Option | Ty::OptionOption | Ty::OptionVec => quote!( #field_ty ),
+            _ => quote!( ::std::option::Option<#field_ty> ),
+        };
+
+        quote! {
+            #[serde(rename = #id #alias)]
+            pub #field_name: #partial_ty
+        }
+    });
+
+    let merges = args.iter().map(|(field, field_attrs, ty)| {
+        let field_name = field.ident.as_ref().unwrap();
+        let id = field_attrs.cased_name();
+        let from_env = match field_attrs.env_name() {
+            Some(env) => {
+                let fallbacks = field_attrs.env_fallbacks();
+                quote! {
+                    [#env, #( #fallbacks ),*]
+                        .iter()
+                        .any(|env| ::std::env::var_os(env).is_some())
+                }
+            }
+            None => quote!(false),
+        };
+        let value = match ty {
+            Ty::Option | Ty::OptionOption | Ty::OptionVec => quote!(Some(value)),
+            _ => quote!(value),
+        };
+
+        quote! {
+            if let Some(value) = other.#field_name {
+                if matches.occurrences_of(#id) == 0 && !#from_env {
+                    self.#field_name = #value;
+                }
+            }
+        }
+    });
+
+    let doc = format!(
+        "Values of `{}` arguments read from another source, \
+         such as a configuration file.",
+        name
+    );
+
+    let partial = quote! {
+        #[doc = #doc]
+        #[derive(Default, ::serde::Deserialize)]
+        #[serde(default)]
+        #vis struct #partial_name {
+            #( #partial_fields ),*
+        }
+    };
+
+    let merge_fn = quote! {
+        /// Fill the arguments which were given neither on the command line
+        /// nor through the environment with the values of `other`.
+        pub fn merge_from(&mut self, matches: &::clap::ArgMatches, other: #partial_name) {
+            #( #merges )*
+        }
+    };
+
+    (partial, merge_fn)
+}
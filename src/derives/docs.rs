@@ -0,0 +1,441 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of reference documentation rendered from the derived `App`.
+
+use proc_macro2;
+use syn;
+
+use super::{config, into_app, plugin, sub_type, Attrs, Kind, Ty, VariantAttrs};
+
+/// Whether the `__clap_subcommand_paths()` function is generated: the
+/// documentation export functions, the prompts and the configuration file
+/// walk the subcommands with it.
+fn needs_subcommand_paths(parent_attribute: &Attrs) -> bool {
+    parent_attribute.has_docs() || parent_attribute.has_prompt() || cfg!(feature = "toml")
+}
+
+/// Generate the `__clap_subcommand_paths()` function of a struct, listing the
+/// paths of every subcommand reachable from it.
+pub fn gen_subcommand_paths_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !needs_subcommand_paths(parent_attribute) {
+        return quote!();
+    }
+
+    let children = gen_fields_subcommand_paths(fields);
+
+    quote! {
+        pub fn __clap_subcommand_paths() -> Vec<Vec<&'static str>> {
+            let children: Vec<Vec<Vec<&'static str>>> = vec![ #( #children ),* ];
+            children.into_iter().flatten().collect()
+        }
+    }
+}
+
+/// Generate the `__clap_subcommand_paths()` function of an enum, every
+/// variant being a subcommand.
+pub fn gen_subcommand_paths_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    if !needs_subcommand_paths(parent_attribute) {
+        return quote!();
+    }
+
+    let subcommand_variants = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs));
+
+    let subcommands = subcommand_variants.map(|(variant, attrs, fields)| {
+        let name = attrs.cased_name();
+        let children = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => gen_fields_subcommand_paths(fields),
+            (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                vec![quote!( <#ty>::__clap_subcommand_paths() )]
+            }
+            _ => vec![],
+        };
+
+        quote! {{
+            let children: Vec<Vec<Vec<&'static str>>> = vec![ #( #children ),* ];
+            let nested = children.into_iter().flatten().map(|path| {
+                let mut full = vec![#name];
+                full.extend(path);
+                full
+            });
+            ::std::iter::once(vec![#name]).chain(nested).collect::<Vec<_>>()
+        }}
+    });
+
+    quote! {
+        pub fn __clap_subcommand_paths() -> Vec<Vec<&'static str>> {
+            let subcommands: Vec<Vec<Vec<&'static str>>> = vec![ #( #subcommands ),* ];
+            subcommands.into_iter().flatten().collect()
+        }
+    }
+}
+
+/// Expressions evaluating to the subcommand paths contributed by `fields`.
+fn gen_fields_subcommand_paths(fields: &[(&syn::Field, Attrs)]) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::Subcommand(ty) => {
+                    let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                        (Ty::Option, Some(sub_type)) => sub_type,
+                        _ => &field.ty,
+                    };
+                    Some(quote!( <#subcmd_type>::__clap_subcommand_paths() ))
+                }
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    Some(quote!( <#ty>::__clap_subcommand_paths() ))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Generate the code adding the EXAMPLES section and, when `fields` are
+/// given, the ENVIRONMENT section listing the variables backing their
+/// arguments, to the long help of `app_var`.
+///
+/// Both go after the `after_long_help` given on top of the command, if any.
+pub fn gen_long_help_sections(
+    app_var: &syn::Ident,
+    fields: Option<&[(&syn::Field, Attrs)]>,
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let has_examples = parent_attribute.has_examples();
+    let after_long_help = parent_attribute.after_long_help_text();
+
+    // without flattened arguments, the whole text is known right now
+    let static_env_args = match fields {
+        Some(fields) => config::static_env_args(fields),
+        None => Some(Vec::new()),
+    };
+    if let Some(env_args) = static_env_args {
+        if env_args.is_empty() && !has_examples {
+            return quote!();
+        }
+
+        let width = env_args.iter().map(|(env, _)| env.len()).max().unwrap_or(0);
+        let mut section = after_long_help;
+        if !env_args.is_empty() {
+            if !section.is_empty() {
+                section.push_str("\n\n");
+            }
+            section.push_str("ENVIRONMENT:");
+        }
+        for (env, help) in env_args {
+            let line = format!("    {:width$}    {}", env, help, width = width);
+            section.push('\n');
+            section.push_str(line.trim_end());
+        }
+        return quote!( let #app_var = #app_var.after_long_help(#section); );
+    }
+
+    let env_args = config::gen_env_args(fields.unwrap());
+    quote! {
+        let #app_var = {
+            let env_args = #env_args;
+            if env_args.is_empty() && !#has_examples {
+                #app_var
+            } else {
+                let width = env_args.iter().map(|(_, env, _)| env.len()).max().unwrap_or(0);
+                let mut section = String::from(#after_long_help);
+                if !env_args.is_empty() {
+                    if !section.is_empty() {
+                        section.push_str("\n\n");
+                    }
+                    section.push_str("ENVIRONMENT:");
+                }
+                for (_, env, help) in env_args {
+                    let line = format!("    {:width$}    {}", env, help, width = width);
+                    section.push('\n');
+                    section.push_str(line.trim_end());
+                }
+                #app_var.after_long_help(Self::__clap_interned(section))
+            }
+        };
+    }
+}
+
+/// Generate the code setting the help of the argument `name`, or the about
+/// of `app_var` itself, from doc attributes some of which are expressions,
+/// like `#[doc = include_str!("help.md")]`.
+///
+/// As with doc comments, a first line followed by a blank one is the short
+/// help without its trailing period, the whole text being the long help.
+pub fn gen_doc_exprs(
+    app_var: &syn::Ident,
+    name: Option<&proc_macro2::TokenStream>,
+    attrs: &Attrs,
+) -> proc_macro2::TokenStream {
+    let doc_exprs = match attrs.doc_exprs() {
+        Some(doc_exprs) => doc_exprs,
+        None => return quote!(),
+    };
+    let parts = &doc_exprs.parts;
+    let finish = match &doc_exprs.suffix {
+        Some(suffix) => quote!( Self::__clap_interned(format!("{} {}", help, #suffix)) ),
+        None => quote!( Self::__clap_interned(help.to_string()) ),
+    };
+    let (split, whole) = match name {
+        Some(name) => (
+            quote!( #app_var.mut_arg(#name, |arg| arg.help(short).long_help(long)) ),
+            quote!( #app_var.mut_arg(#name, |arg| arg.help(long)) ),
+        ),
+        None => (
+            quote!( #app_var.about(short).long_about(long) ),
+            quote!( #app_var.about(long) ),
+        ),
+    };
+
+    quote! {
+        let #app_var = {
+            let parts: &[&str] = &[ #( #parts ),* ];
+            let doc = parts
+                .join(" ")
+                .split('\n')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let doc = doc.trim();
+            let finish = |help: &str| -> &'static str { #finish };
+            let long = finish(doc);
+            match doc.find("\n\n") {
+                Some(end) if !doc[..end].contains('\n') => {
+                    let short = finish(doc[..end].trim_end_matches('.'));
+                    #split
+                }
+                _ => #whole,
+            }
+        };
+    }
+}
+
+/// Generate the helpers of the documentation export functions, rendering
+/// the long help of each subcommand, none without `#[clap(docs)]`.
+pub fn gen_docs_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_docs() {
+        return quote!();
+    }
+
+    let bin_name = parent_attribute.cased_name();
+    let app = into_app::gen_app_expr(name, parent_attribute);
+
+    quote! {
+        pub fn __clap_long_help(path: &[&str]) -> String {
+            #name::__clap_render_long_help(#name::__clap_subcommand_app(#app, path))
+        }
+
+        /// The subcommand of `app` at `path`, named after the whole command
+        /// line running it.
+        pub fn __clap_subcommand_app(
+            mut app: ::clap::App<'static>,
+            path: &[&str],
+        ) -> ::clap::App<'static> {
+            let mut bin_name = String::from(#bin_name);
+            for name in path {
+                let sub = app.get_subcommands().find(|sub| sub.get_name() == *name).cloned();
+                app = match sub {
+                    Some(sub) => sub,
+                    None => panic!("`{}` has no subcommand `{}`", bin_name, name),
+                };
+                bin_name.push(' ');
+                bin_name.push_str(name);
+            }
+            app.bin_name(bin_name)
+        }
+
+        /// Render the long help of `app`.
+        pub fn __clap_render_long_help(mut app: ::clap::App<'static>) -> String {
+            let mut output = Vec::new();
+            app.write_long_help(&mut output)
+                .expect("writing help into a Vec cannot fail");
+            String::from_utf8_lossy(&output).into_owned()
+        }
+
+    }
+}
+
+/// Generate the documentation export functions, which are part of the public
+/// API of the type unlike the helpers of `gen_docs_fns()`, none without
+/// `#[clap(docs)]`.
+pub fn gen_public_docs_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_docs() {
+        return quote!();
+    }
+
+    let bin_name = parent_attribute.cased_name();
+    let app = into_app::gen_app_expr(name, parent_attribute);
+    let completion_scripts = if parent_attribute.has_dynamic_completion() {
+        quote!( #name::__clap_completion_scripts() )
+    } else {
+        quote!( Vec::new() )
+    };
+
+    quote! {
+        /// Render the help of the whole command tree, including every
+        /// subcommand, as Markdown.
+        pub fn markdown_help() -> String {
+            let mut paths = vec![Vec::new()];
+            paths.extend(#name::__clap_subcommand_paths());
+
+            let mut output = String::new();
+            for path in paths {
+                let level = ::std::cmp::min(path.len() + 1, 6);
+                let mut command = String::from(#bin_name);
+                for subcommand in &path {
+                    command.push(' ');
+                    command.push_str(subcommand);
+                }
+
+                let help = #name::__clap_long_help(&path);
+                output.push_str(&"#".repeat(level));
+                output.push_str(&format!(" `{}`\n\n```text\n{}\n```\n\n", command, help.trim_end()));
+            }
+            output
+        }
+
+        /// Render the help of the whole command tree, including every
+        /// subcommand, as reStructuredText.
+        pub fn rst_help() -> String {
+            let mut paths = vec![Vec::new()];
+            paths.extend(#name::__clap_subcommand_paths());
+
+            let mut output = String::new();
+            for path in paths {
+                let underline = ['=', '-', '~', '^', '"'];
+                let underline = underline[::std::cmp::min(path.len(), underline.len() - 1)];
+                let mut command = String::from(#bin_name);
+                for subcommand in &path {
+                    command.push(' ');
+                    command.push_str(subcommand);
+                }
+                let title = format!("``{}``", command);
+
+                let underline = underline.to_string().repeat(title.len());
+                output.push_str(&format!("{}\n{}\n\n.. code-block:: text\n\n", title, underline));
+                for line in #name::__clap_long_help(&path).trim_end().lines() {
+                    if line.is_empty() {
+                        output.push('\n');
+                    } else {
+                        output.push_str(&format!("    {}\n", line));
+                    }
+                }
+                output.push('\n');
+            }
+            output
+        }
+
+        /// Render the help of the whole command tree, including every
+        /// subcommand, as a roff man page.
+        pub fn roff_help() -> String {
+            let mut paths = vec![Vec::new()];
+            paths.extend(#name::__clap_subcommand_paths());
+
+            let mut output = format!(".TH {} 1\n", #bin_name.to_uppercase());
+            for path in paths {
+                let mut command = String::from(#bin_name);
+                for subcommand in &path {
+                    command.push(' ');
+                    command.push_str(subcommand);
+                }
+
+                let macro_name = if path.is_empty() { ".SH" } else { ".SS" };
+                output.push_str(&format!("{} \"{}\"\n.nf\n", macro_name, command));
+                for line in #name::__clap_long_help(&path).trim_end().lines() {
+                    let line = line.replace('\\', "\\e");
+                    if line.starts_with('.') || line.starts_with('\'') {
+                        output.push_str("\\&");
+                    }
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+                output.push_str(".fi\n");
+            }
+            output
+        }
+
+        /// Render the long help of the whole command tree, including every
+        /// subcommand, the same way on every machine: 100 columns wide,
+        /// without colors nor trailing whitespace.
+        ///
+        /// Meant for snapshot tests of the command line interface.
+        pub fn help_snapshot() -> String {
+            let mut paths = vec![Vec::new()];
+            paths.extend(#name::__clap_subcommand_paths());
+
+            let mut output = String::new();
+            for path in paths {
+                let app = #name::__clap_subcommand_app(#app, &path)
+                    .term_width(100)
+                    .max_term_width(100)
+                    .setting(::clap::AppSettings::ColorNever);
+                let mut command = String::from(#bin_name);
+                for subcommand in &path {
+                    command.push(' ');
+                    command.push_str(subcommand);
+                }
+
+                if !output.is_empty() {
+                    output.push('\n');
+                }
+                output.push_str(&format!("$ {} --help\n", command));
+                for line in #name::__clap_render_long_help(app).trim_end().lines() {
+                    output.push_str(line.trim_end());
+                    output.push('\n');
+                }
+            }
+            output
+        }
+
+        /// Write every document generated from the command into `dir`,
+        /// created if needed, returning the paths of the written files.
+        ///
+        /// Meant for packaging scripts: `<bin>.md`, `<bin>.rst` and `<bin>.1`
+        /// are the help as Markdown, reStructuredText and man page, and
+        /// `<bin>.yaml` the carapace completion spec. With
+        /// `#[clap(dynamic_completion)]`, `<bin>.bash`, `_<bin>` and
+        /// `<bin>.fish` are the completion scripts of bash, zsh and fish,
+        /// asking `<bin> complete --` for the candidates.
+        pub fn generate_artifacts<P: AsRef<::std::path::Path>>(
+            dir: P,
+        ) -> ::std::io::Result<Vec<::std::path::PathBuf>> {
+            let dir = dir.as_ref();
+            ::std::fs::create_dir_all(dir)?;
+
+            let mut artifacts = vec![
+                (format!("{}.md", #bin_name), #name::markdown_help()),
+                (format!("{}.rst", #bin_name), #name::rst_help()),
+                (format!("{}.1", #bin_name), #name::roff_help()),
+                (format!("{}.yaml", #bin_name), #name::carapace_spec()),
+            ];
+            artifacts.extend(#completion_scripts);
+            let mut paths = Vec::with_capacity(artifacts.len());
+            for (file_name, contents) in artifacts {
+                let path = dir.join(file_name);
+                ::std::fs::write(&path, contents)?;
+                paths.push(path);
+            }
+            Ok(paths)
+        }
+    }
+}
@@ -15,11 +15,12 @@ use std::env;
 
 use proc_macro2;
 use syn;
-use syn::punctuated;
 use syn::spanned::Spanned as _;
-use syn::token;
 
-use super::{spanned::Sp, sub_type, Attrs, Kind, Name, ParserKind, Ty, DEFAULT_CASING};
+use super::{
+    clap, config, fixed_values, spanned::Sp, sub_type, Attrs, Kind, Name, ParserKind, Ty, DEFAULT_CASING,
+    DEFAULT_ENV_CASING,
+};
 
 pub fn derive_from_argmatches(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     use syn::Data::*;
@@ -39,9 +40,11 @@ pub fn derive_from_argmatches(input: &syn::DeriveInput) -> proc_macro2::TokenStr
                 &input.attrs,
                 Name::Assigned(syn::LitStr::new(&name, proc_macro2::Span::call_site())),
                 Sp::call_site(DEFAULT_CASING),
+                Sp::call_site(DEFAULT_ENV_CASING),
             );
 
-            gen_from_argmatches_impl_for_struct(struct_name, &fields.named, &attrs)
+            let fields = Attrs::from_fields(&fields.named, &attrs);
+            gen_from_argmatches_impl_for_struct(struct_name, &fields, &attrs)
         }
         // Enum(ref e) => clap_for_enum_impl(struct_name, &e.variants, &input.attrs),
         _ => panic!("clap_derive only supports non-tuple structs"), // and enums"),
@@ -52,14 +55,29 @@ pub fn derive_from_argmatches(input: &syn::DeriveInput) -> proc_macro2::TokenStr
 
 pub fn gen_from_argmatches_impl_for_struct(
     name: &syn::Ident,
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    fields: &[(&syn::Field, Attrs)],
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
     let from_argmatches_fn = gen_from_argmatches_fn_for_struct(name, fields, parent_attribute);
+    let prefixed_fns = gen_prefixed_fns();
+    let error_fns = gen_error_fns();
+    let no_prefix = no_prefix();
 
     quote! {
         impl ::clap::FromArgMatches for #name {
+            fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
+                Self::__clap_from_argmatches_prefixed(matches, #no_prefix)
+            }
+        }
+
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        impl #name {
             #from_argmatches_fn
+
+            #prefixed_fns
+
+            #error_fns
         }
 
         impl From<::clap::ArgMatches> for #name {
@@ -68,31 +86,269 @@ pub fn gen_from_argmatches_impl_for_struct(
                 <Self as ::clap::FromArgMatches>::from_argmatches(&m)
             }
         }
+    }
+}
+
+/// The type of the runtime prefix of flattened arguments, a `(prefix,
+/// ENV_PREFIX, casing, localizer)` tuple, see `gen_prefixed_fns()`.
+pub fn prefix_type() -> proc_macro2::TokenStream {
+    quote! {
+        (
+            &'static str,
+            &'static str,
+            Option<(&'static str, bool)>,
+            Option<fn(&str) -> Option<String>>,
+        )
+    }
+}
+
+/// The runtime prefix of arguments flattened without any.
+pub fn no_prefix() -> proc_macro2::TokenStream {
+    quote!( ("", "", None, None) )
+}
+
+/// Generate the code giving the `prefix` variable the casing propagated
+/// with `propagate_rename_all` by `attrs`, if any.
+pub fn gen_propagated_casing(prefix: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    match attrs.propagated_casing() {
+        Some(casing) => quote!( let #prefix = Self::__clap_cased_prefix(#prefix, #casing, true); ),
+        None => quote!(),
+    }
+}
+
+/// Generate `__clap_prefixed()` and its siblings, putting the prefix of a
+/// flattened field in front of an id, long flag or environment variable.
+///
+/// The runtime prefix holds the prefix of the ids and long flags, the one of
+/// the environment variables, derived at expansion time, and the key of the
+/// casing given with `rename_all` at a flatten site, which the derived long
+/// flags follow. The casing is marked when it comes from
+/// `propagate_rename_all`, as they only follow it when their struct has no
+/// `rename_all`. It also carries the `localizer` of the enclosing types, if
+/// any, for the help messages of flattened structs and subcommands.
+///
+/// A struct doesn't know the prefixes it is flattened with when it is
+/// expanded, so prefixed names are joined at runtime, each one being
+/// allocated once for the whole program by `__clap_interned()`.
+pub fn gen_prefixed_fns() -> proc_macro2::TokenStream {
+    let prefix_type = prefix_type();
+
+    quote! {
+        pub fn __clap_prefixed(prefix: #prefix_type, name: &'static str) -> &'static str {
+            Self::__clap_joined(prefix.0, "-", name)
+        }
+
+        pub fn __clap_prefixed_env(prefix: #prefix_type, env: &'static str) -> &'static str {
+            Self::__clap_joined(prefix.1, "_", env)
+        }
+
+        pub fn __clap_joined(
+            prefix: &str,
+            separator: &str,
+            name: &'static str,
+        ) -> &'static str {
+            if prefix.is_empty() {
+                return name;
+            }
+            Self::__clap_interned([prefix, separator, name].concat())
+        }
+
+        /// The names and value lists interned by `__clap_interned()` and
+        /// `__clap_interned_values()`, shared by every thread so that each
+        /// one is leaked only once.
+        pub fn __clap_interner() -> ::std::sync::MutexGuard<
+            'static,
+            (
+                ::std::collections::HashSet<&'static str>,
+                ::std::collections::HashSet<&'static [&'static str]>,
+            ),
+        > {
+            type Interner = ::std::sync::Mutex<(
+                ::std::collections::HashSet<&'static str>,
+                ::std::collections::HashSet<&'static [&'static str]>,
+            )>;
+
+            static INIT: ::std::sync::Once = ::std::sync::Once::new();
+            static mut INTERNER: *const Interner = 0 as *const Interner;
+
+            let interner = unsafe {
+                INIT.call_once(|| {
+                    let interner = Interner::new(Default::default());
+                    INTERNER = Box::into_raw(Box::new(interner));
+                });
+                &*INTERNER
+            };
+            interner.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// `name` as a `&'static str`, allocated once however many times
+        /// it's given.
+        pub fn __clap_interned(name: String) -> &'static str {
+            let mut interner = Self::__clap_interner();
+            match interner.0.get(name.as_str()) {
+                Some(name) => *name,
+                None => {
+                    let name: &'static str = Box::leak(name.into_boxed_str());
+                    interner.0.insert(name);
+                    name
+                }
+            }
+        }
+
+        /// `values` as a `&'static [&'static str]`, allocated once however
+        /// many times they're given.
+        pub fn __clap_interned_values(values: Vec<&'static str>) -> &'static [&'static str] {
+            let mut interner = Self::__clap_interner();
+            match interner.1.get(values.as_slice()) {
+                Some(values) => *values,
+                None => {
+                    let values: &'static [&'static str] = Box::leak(values.into_boxed_slice());
+                    interner.1.insert(values);
+                    values
+                }
+            }
+        }
+
+        /// The first of `envs` which is set, the first one when none is.
+        pub fn __clap_first_env(envs: &[&'static str]) -> &'static str {
+            envs.iter()
+                .find(|env| ::std::env::var_os(env).is_some())
+                .cloned()
+                .unwrap_or(envs[0])
+        }
+
+        /// Warn once that `legacy` is deprecated in favor of `env`.
+        pub fn __clap_warn_legacy_env(env: &'static str, legacy: &'static str) {
+            thread_local! {
+                static WARNED: ::std::cell::RefCell<::std::collections::HashSet<&'static str>> =
+                    ::std::cell::RefCell::new(::std::collections::HashSet::new());
+            }
+
+            if WARNED.with(|warned| warned.borrow_mut().insert(legacy)) {
+                eprintln!("warning: `{}` is deprecated, use `{}` instead", legacy, env);
+            }
+        }
+
+        pub fn __clap_nested_prefix(
+            prefix: #prefix_type,
+            own: &'static str,
+            own_env: &'static str,
+        ) -> #prefix_type {
+            (
+                Self::__clap_joined(prefix.0, "-", own),
+                Self::__clap_joined(prefix.1, "_", own_env),
+                prefix.2,
+                prefix.3,
+            )
+        }
+
+        /// The prefix of subcommands, which only keep the casing.
+        pub fn __clap_casing_prefix(prefix: #prefix_type) -> #prefix_type {
+            ("", "", prefix.2, prefix.3)
+        }
 
-        // @TODO impl TryFrom once stable
+        pub fn __clap_cased_prefix(
+            prefix: #prefix_type,
+            casing: &'static str,
+            inherited: bool,
+        ) -> #prefix_type {
+            (prefix.0, prefix.1, Some((casing, inherited)), prefix.3)
+        }
     }
 }
 
 pub fn gen_from_argmatches_fn_for_struct(
     struct_name: &syn::Ident,
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
+    fields: &[(&syn::Field, Attrs)],
     parent_attribute: &Attrs,
 ) -> proc_macro2::TokenStream {
-    let field_block = gen_constructor(fields, parent_attribute);
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = prefix_type();
+    let field_block = gen_constructor(fields, Some(&prefix));
+    let propagated_casing = gen_propagated_casing(&prefix, parent_attribute);
+
+    let infallible_fn = gen_infallible_from_argmatches_fn();
+
+    quote! {
+        /// Same as `TryFrom<&ArgMatches>`, for arguments flattened with
+        /// `prefix`.
+        #[allow(unreachable_code)]
+        pub fn __clap_try_from_argmatches_prefixed(
+            matches: &::clap::ArgMatches,
+            #prefix: #prefix_type,
+        ) -> ::std::result::Result<Self, ::clap::Error> {
+            #propagated_casing
+            Ok(#struct_name #field_block)
+        }
+
+        #infallible_fn
+    }
+}
+
+/// Generate `__clap_from_argmatches_prefixed()`, panicking where
+/// `__clap_try_from_argmatches_prefixed()` fails, for `from_argmatches`.
+pub fn gen_infallible_from_argmatches_fn() -> proc_macro2::TokenStream {
+    let prefix_type = prefix_type();
 
     quote! {
-        fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
-            #struct_name #field_block
+        /// Same as `from_argmatches`, for arguments flattened with `prefix`.
+        pub fn __clap_from_argmatches_prefixed(
+            matches: &::clap::ArgMatches,
+            prefix: #prefix_type,
+        ) -> Self {
+            match Self::__clap_try_from_argmatches_prefixed(matches, prefix) {
+                Ok(value) => value,
+                Err(e) => panic!("{}", e.message),
+            }
         }
     }
 }
 
+/// Generate the errors of matches which don't fit the type built from them,
+/// as when they come from an `App` other than its own.
+pub fn gen_error_fns() -> proc_macro2::TokenStream {
+    quote! {
+        pub fn __clap_missing_argument(id: &str) -> ::clap::Error {
+            ::clap::Error::with_description(
+                &format!("The argument '{}' is required but wasn't found in the matches", id),
+                ::clap::ErrorKind::MissingRequiredArgument,
+            )
+        }
+
+        pub fn __clap_missing_subcommand() -> ::clap::Error {
+            ::clap::Error::with_description(
+                "A subcommand is required but none was given",
+                ::clap::ErrorKind::MissingSubcommand,
+            )
+        }
+
+        pub fn __clap_invalid_value(id: &str, error: String) -> ::clap::Error {
+            ::clap::Error::with_description(
+                &format!("Invalid value for '{}': {}", id, error),
+                ::clap::ErrorKind::ValueValidation,
+            )
+        }
+
+        pub fn __clap_wrong_number_of_values(id: &str, count: usize) -> ::clap::Error {
+            ::clap::Error::with_description(
+                &format!("The argument '{}' requires {} values", id, count),
+                ::clap::ErrorKind::WrongNumberOfValues,
+            )
+        }
+    }
+}
+
+/// Generate the block building the `fields` from `matches`, looking the
+/// arguments up with the value of the `prefix` variable, if any, in front of
+/// their ids.
+///
+/// The block returns the `clap::Error` of a value which is missing or fails
+/// to parse with `?`.
 pub fn gen_constructor(
-    fields: &punctuated::Punctuated<syn::Field, token::Comma>,
-    parent_attribute: &Attrs,
+    fields: &[(&syn::Field, Attrs)],
+    prefix: Option<&syn::Ident>,
 ) -> proc_macro2::TokenStream {
-    let fields = fields.iter().map(|field| {
-        let attrs = Attrs::from_field(field, parent_attribute.casing());
+    let fields = fields.iter().map(|(field, attrs)| {
         let field_name = field.ident.as_ref().unwrap();
         let kind = attrs.kind();
         match &*attrs.kind() {
@@ -103,16 +359,40 @@ pub fn gen_constructor(
                 };
                 let unwrapper = match **ty {
                     Ty::Option => quote!(),
-                    _ => quote_spanned!( ty.span()=> .unwrap() ),
+                    _ => quote_spanned!( ty.span()=> .ok_or_else(Self::__clap_missing_subcommand)? ),
+                };
+                let prefix = match prefix {
+                    Some(prefix) => quote!( Self::__clap_casing_prefix(#prefix) ),
+                    None => no_prefix(),
                 };
                 quote_spanned! { kind.span()=>
-                    #field_name: <#subcmd_type>::from_subcommand(matches.subcommand())#unwrapper
+                    #field_name: <#subcmd_type>::__clap_try_from_subcommand_prefixed(
+                        matches.subcommand(),
+                        #prefix,
+                    )? #unwrapper
                 }
             }
 
-            Kind::FlattenStruct => quote_spanned! { kind.span()=>
-                #field_name: ::clap::FromArgMatches::from_argmatches(matches)
-            },
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let prefix = config::gen_flatten_prefix(prefix, &attrs).unwrap_or_else(no_prefix);
+                quote_spanned! { kind.span()=>
+                    #field_name: <#ty>::__clap_try_from_argmatches_prefixed(matches, #prefix)?
+                }
+            }
+
+            Kind::Skip(None) if attrs.occurrences_of().is_some() => {
+                let (ident, counted) = attrs.occurrences_of().unwrap();
+                let id = clap::gen_field_arg_id(fields, counted, ident, prefix);
+                let ty = &field.ty;
+                quote_spanned! { kind.span()=>
+                    #field_name: matches.occurrences_of(#id) as #ty
+                }
+            }
+
+            Kind::Skip(None) if attrs.is_arg_matches() => {
+                quote_spanned!(kind.span()=> #field_name: matches.clone())
+            }
 
             Kind::Skip(val) => match val {
                 None => quote_spanned!(kind.span()=> #field_name: Default::default()),
@@ -122,6 +402,21 @@ pub fn gen_constructor(
             Kind::Arg(ty) => {
                 use self::ParserKind::*;
 
+                let name = attrs.cased_name();
+                let name = match prefix {
+                    Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #name) ),
+                    None => quote!(#name),
+                };
+                let error = if attrs.is_secret() {
+                    let secret_error = clap::SECRET_ERROR;
+                    quote!( String::from(#secret_error) )
+                } else {
+                    clap::gen_parse_error(attrs, clap::convert_type(field, attrs, **ty))
+                };
+
+                // the values may come from an `App` other than the one of
+                // the type, so they're parsed again instead of trusting the
+                // validators
                 let parser = attrs.parser();
                 let func = &parser.func;
                 let span = parser.kind.span();
@@ -129,22 +424,26 @@ pub fn gen_constructor(
                     FromStr => (
                         quote_spanned!(span=> value_of),
                         quote_spanned!(span=> values_of),
-                        func.clone(),
+                        quote_spanned!(func.span()=> |s| Ok::<_, ::clap::Error>(#func(s))),
                     ),
                     TryFromStr => (
                         quote_spanned!(span=> value_of),
                         quote_spanned!(span=> values_of),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
+                        quote_spanned! { func.span()=>
+                            |s| #func(s).map_err(|e| Self::__clap_invalid_value(#name, #error))
+                        },
                     ),
                     FromOsStr => (
                         quote_spanned!(span=> value_of_os),
                         quote_spanned!(span=> values_of_os),
-                        func.clone(),
+                        quote_spanned!(func.span()=> |s| Ok::<_, ::clap::Error>(#func(s))),
                     ),
                     TryFromOsStr => (
                         quote_spanned!(span=> value_of_os),
                         quote_spanned!(span=> values_of_os),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
+                        quote_spanned! { func.span()=>
+                            |s| #func(s).map_err(|e| Self::__clap_invalid_value(#name, #error))
+                        },
                     ),
                     FromOccurrences => (
                         quote_spanned!(span=> occurrences_of),
@@ -156,29 +455,59 @@ pub fn gen_constructor(
 
                 let flag = *attrs.parser().kind == ParserKind::FromFlag;
                 let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
-                let name = attrs.cased_name();
+                let fixed = if attrs.has_fixed_values() {
+                    let shape = match **ty {
+                        Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
+                        _ => &field.ty,
+                    };
+                    Some(gen_fixed_values(shape, &parse, &name))
+                } else {
+                    None
+                };
                 let field_value = match **ty {
+                    Ty::Bool if attrs.is_explicit_bool() => quote_spanned! { ty.span()=>
+                        matches.is_present(#name)
+                            && matches.value_of(#name).map_or(true, |v| v == "true")
+                    },
+
                     Ty::Bool => quote_spanned! { ty.span()=>
                         matches.is_present(#name)
                     },
 
-                    Ty::Option => quote_spanned! { ty.span()=>
-                        matches.#value_of(#name)
-                            .map(#parse)
+                    Ty::Option => match &fixed {
+                        Some(fixed) => quote_spanned! { ty.span()=>
+                            matches.#values_of(#name)
+                                .map(#fixed)
+                                .transpose()?
+                        },
+                        None => quote_spanned! { ty.span()=>
+                            matches.#value_of(#name)
+                                .map(#parse)
+                                .transpose()?
+                        },
                     },
 
-                    Ty::OptionOption => quote_spanned! { ty.span()=>
-                        if matches.is_present(#name) {
-                            Some(matches.#value_of(#name).map(#parse))
-                        } else {
-                            None
-                        }
+                    Ty::OptionOption => match attrs.missing_value_marker() {
+                        Some(marker) => quote_spanned! { ty.span()=>
+                            matches.#value_of(#name)
+                                .map(|s| if s == #marker { None } else { Some(s) })
+                                .map(|s| s.map(#parse).transpose())
+                                .transpose()?
+                        },
+                        None => quote_spanned! { ty.span()=>
+                            if matches.is_present(#name) {
+                                Some(matches.#value_of(#name).map(#parse).transpose()?)
+                            } else {
+                                None
+                            }
+                        },
                     },
 
                     Ty::OptionVec => quote_spanned! { ty.span()=>
                         if matches.is_present(#name) {
                             Some(matches.#values_of(#name)
-                                 .map(|v| v.map(#parse).collect())
+                                 .map(|v| v.map(#parse).collect::<::std::result::Result<_, _>>())
+                                 .transpose()?
                                  .unwrap_or_else(Vec::new))
                         } else {
                             None
@@ -187,10 +516,29 @@ pub fn gen_constructor(
 
                     Ty::Vec => quote_spanned! { ty.span()=>
                         matches.#values_of(#name)
-                            .map(|v| v.map(#parse).collect())
+                            .map(|v| v.map(#parse).collect::<::std::result::Result<_, _>>())
+                            .transpose()?
                             .unwrap_or_else(Vec::new)
                     },
 
+                    Ty::Other if attrs.is_verbosity() => {
+                        let quiet = attrs.quiet_id();
+                        let quiet = match prefix {
+                            Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #quiet) ),
+                            None => quote!(#quiet),
+                        };
+                        quote_spanned! { ty.span()=>
+                            {
+                                let level = matches.occurrences_of(#name) as i64
+                                    - matches.occurrences_of(#quiet) as i64;
+                                let level = level
+                                    .max(::std::i8::MIN as i64)
+                                    .min(::std::i8::MAX as i64) as i8;
+                                ::std::convert::From::from(level)
+                            }
+                        }
+                    }
+
                     Ty::Other if occurrences => quote_spanned! { ty.span()=>
                         #parse(matches.#value_of(#name))
                     },
@@ -199,10 +547,35 @@ pub fn gen_constructor(
                         #parse(matches.is_present(#name))
                     },
 
+                    Ty::Other if fixed.is_some() => {
+                        let fixed = fixed.as_ref().unwrap();
+                        if attrs.is_explicitly_optional() {
+                            quote_spanned! { ty.span()=>
+                                matches.#values_of(#name)
+                                    .map(#fixed)
+                                    .transpose()?
+                                    .unwrap_or_default()
+                            }
+                        } else {
+                            quote_spanned! { ty.span()=>
+                                matches.#values_of(#name)
+                                    .map(#fixed)
+                                    .unwrap_or_else(|| Err(Self::__clap_missing_argument(#name)))?
+                            }
+                        }
+                    }
+
+                    Ty::Other if attrs.is_explicitly_optional() => quote_spanned! { ty.span()=>
+                        matches.#value_of(#name)
+                            .map(#parse)
+                            .transpose()?
+                            .unwrap_or_default()
+                    },
+
                     Ty::Other => quote_spanned! { ty.span()=>
                         matches.#value_of(#name)
                             .map(#parse)
-                            .unwrap()
+                            .unwrap_or_else(|| Err(Self::__clap_missing_argument(#name)))?
                     },
                 };
 
@@ -216,12 +589,46 @@ pub fn gen_constructor(
     }}
 }
 
+/// The closure building the tuple or array `shape` out of the values of the
+/// argument `name`, parsing each with `parse`, failing when there are too
+/// few of them.
+fn gen_fixed_values(
+    shape: &syn::Type,
+    parse: &proc_macro2::TokenStream,
+    name: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (_, count) = fixed_values(shape).unwrap();
+    let mut shape = shape;
+    while let syn::Type::Paren(paren) = shape {
+        shape = &paren.elem;
+    }
+    let values = (0..count).map(|_| {
+        quote! {
+            values
+                .next()
+                .unwrap_or_else(|| Err(Self::__clap_wrong_number_of_values(#name, #count)))?
+        }
+    });
+    let built = match shape {
+        syn::Type::Array(_) => quote!( [ #( #values ),* ] ),
+        _ => quote!( ( #( #values ),* ) ),
+    };
+
+    quote! {
+        |values| -> ::std::result::Result<_, ::clap::Error> {
+            let mut values = values.map(#parse);
+            Ok(#built)
+        }
+    }
+}
+
 pub fn gen_from_argmatches_impl_for_enum(name: &syn::Ident) -> proc_macro2::TokenStream {
+    let no_prefix = no_prefix();
+
     quote! {
         impl ::clap::FromArgMatches for #name {
             fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
-                <#name>::from_subcommand(matches.subcommand())
-                    .unwrap()
+                <#name>::__clap_from_argmatches_prefixed(matches, #no_prefix)
             }
         }
 
@@ -231,7 +638,33 @@ pub fn gen_from_argmatches_impl_for_enum(name: &syn::Ident) -> proc_macro2::Toke
                 <Self as ::clap::FromArgMatches>::from_argmatches(&m)
             }
         }
+    }
+}
 
-        // @TODO: impl TryFrom once stable
+/// Generate the `TryFrom<&ArgMatches>` implementation of a type deriving
+/// `Clap`, failing with the checks made after parsing, and where
+/// `from_argmatches` would panic: a value missing or failing to parse, or,
+/// for subcommands, none being given.
+///
+/// The matches don't hold the command line, so the `bin_name` and `raw_argv`
+/// fields are left empty, as with `from_argmatches`.
+pub fn gen_try_from_impl(name: &syn::Ident) -> proc_macro2::TokenStream {
+    let no_prefix = no_prefix();
+
+    quote! {
+        /// The `bin_name` and `raw_argv` fields are left empty, the command
+        /// line not being part of the matches: parse with `try_parse_from`
+        /// to have them filled.
+        impl<'a> ::std::convert::TryFrom<&'a ::clap::ArgMatches> for #name {
+            type Error = ::clap::Error;
+
+            fn try_from(
+                matches: &'a ::clap::ArgMatches,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                <#name>::__clap_check_occurrences(matches, #no_prefix)
+                    .and_then(|_| <#name>::__clap_try_from_argmatches_prefixed(matches, #no_prefix))
+                    .map_err(|e| <#name>::__clap_redact(e, &[]))
+            }
+        }
     }
 }
@@ -0,0 +1,446 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of random command lines for property tests and fuzzing, for
+//! the types asking for it with `#[clap(arbitrary)]`, which needs the
+//! `arbitrary` feature. Flattened and subcommand types must ask for it too.
+//!
+//! Values of arguments are taken from their `possible_values` when given,
+//! and otherwise from the `Arbitrary` and `ToString` implementations of
+//! their type, which must then be a string, a number, a `bool` or a `char`
+//! parsed with `from_str` or `try_from_str`. Optional arguments of other
+//! types are left out of the command lines. Positional values can only
+//! start with `-` when no subcommand follows them, as they are then put
+//! after `--`.
+
+use proc_macro2;
+use proc_macro_error::abort;
+use syn::{self, spanned::Spanned};
+
+use super::{config, from_argmatches, plugin, sub_type, Attrs, Kind, ParserKind, Ty, VariantAttrs};
+
+/// Generate the arbitrary command line functions of a struct.
+pub fn gen_arbitrary_fns_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_arbitrary() {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let parts = gen_arbitrary_parts(fields, parent_attribute, &prefix);
+    let subcommand = gen_arbitrary_subcommand(fields, &prefix);
+
+    quote! {
+        pub fn __clap_arbitrary_parts(
+            u: &mut ::arbitrary::Unstructured,
+            options: &mut Vec<String>,
+            positionals: &mut Vec<String>,
+            hyphen_values: bool,
+            #prefix: #prefix_type,
+        ) -> ::arbitrary::Result<()> {
+            #propagated_casing
+            #parts
+            Ok(())
+        }
+
+        pub fn __clap_arbitrary_subcommand(
+            u: &mut ::arbitrary::Unstructured,
+            args: &mut Vec<String>,
+            #prefix: #prefix_type,
+        ) -> ::arbitrary::Result<()> {
+            #propagated_casing
+            #subcommand
+            Ok(())
+        }
+
+    }
+}
+
+/// Generate the arbitrary command line functions of an enum, every variant
+/// being a subcommand.
+pub fn gen_arbitrary_fns_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    if !parent_attribute.has_arbitrary() {
+        return quote!();
+    }
+
+    let subcommand_variants: Vec<_> = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .collect();
+    let last = subcommand_variants.len().saturating_sub(1);
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+
+    let arms = subcommand_variants.iter().enumerate().map(|(i, (variant, attrs, fields))| {
+        let name = attrs.cased_name();
+        let args = match (&variant.fields, fields) {
+            (Named(_), Some(fields)) => {
+                let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                let parts = gen_arbitrary_parts(fields, attrs, &prefix);
+                let subcommand = gen_arbitrary_subcommand(fields, &prefix);
+                quote! {
+                    let #prefix = Self::__clap_casing_prefix(#prefix);
+                    #propagated_casing
+                    {
+                        let args = &mut subcommand;
+                        #subcommand
+                    }
+                    {
+                        let options = &mut options;
+                        let positionals = &mut positionals;
+                        let hyphen_values = subcommand.is_empty();
+                        #parts
+                    }
+                }
+            }
+            (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                let ty = &unnamed[0].ty;
+                quote! {
+                    let #prefix = Self::__clap_casing_prefix(#prefix);
+                    <#ty>::__clap_arbitrary_subcommand(u, &mut subcommand, #prefix)?;
+                    let hyphen_values = subcommand.is_empty();
+                    <#ty>::__clap_arbitrary_parts(
+                        u,
+                        &mut options,
+                        &mut positionals,
+                        hyphen_values,
+                        #prefix,
+                    )?;
+                }
+            }
+            _ => quote!(),
+        };
+
+        quote! {
+            #i => {
+                let mut options: Vec<String> = Vec::new();
+                let mut positionals: Vec<String> = Vec::new();
+                let mut subcommand: Vec<String> = Vec::new();
+                #args
+                args.push(String::from(#name));
+                args.extend(options);
+                if subcommand.is_empty() && !positionals.is_empty() {
+                    args.push(String::from("--"));
+                }
+                args.extend(positionals);
+                args.extend(subcommand);
+            }
+        }
+    });
+
+    quote! {
+        pub fn __clap_arbitrary_parts(
+            u: &mut ::arbitrary::Unstructured,
+            options: &mut Vec<String>,
+            positionals: &mut Vec<String>,
+            hyphen_values: bool,
+            #prefix: #prefix_type,
+        ) -> ::arbitrary::Result<()> {
+            Ok(())
+        }
+
+        pub fn __clap_arbitrary_subcommand(
+            u: &mut ::arbitrary::Unstructured,
+            args: &mut Vec<String>,
+            #prefix: #prefix_type,
+        ) -> ::arbitrary::Result<()> {
+            #propagated_casing
+            match u.int_in_range(0..=#last)? {
+                #( #arms )*
+                _ => (),
+            }
+            Ok(())
+        }
+
+    }
+}
+
+/// Generate the public `arbitrary_args()` function.
+pub fn gen_arbitrary_args_fn(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_arbitrary() {
+        return quote!();
+    }
+
+    let bin_name = parent_attribute.cased_name();
+    let no_prefix = from_argmatches::no_prefix();
+
+    quote! {
+        /// Build a random command line accepted by this parser, binary name
+        /// included, out of the raw data `u`.
+        pub fn arbitrary_args(
+            u: &mut ::arbitrary::Unstructured,
+        ) -> ::arbitrary::Result<Vec<String>> {
+            let mut subcommand = Vec::new();
+            #name::__clap_arbitrary_subcommand(u, &mut subcommand, #no_prefix)?;
+
+            let mut options = Vec::new();
+            let mut positionals = Vec::new();
+            let hyphen_values = subcommand.is_empty();
+            #name::__clap_arbitrary_parts(
+                u,
+                &mut options,
+                &mut positionals,
+                hyphen_values,
+                #no_prefix,
+            )?;
+
+            let mut args = vec![String::from(#bin_name)];
+            args.extend(options);
+            if subcommand.is_empty() && !positionals.is_empty() {
+                args.push(String::from("--"));
+            }
+            args.extend(positionals);
+            args.extend(subcommand);
+            Ok(args)
+        }
+    }
+}
+
+/// Code pushing random arguments for `fields` into `options` and
+/// `positionals`, flagged under the runtime `prefix`.
+fn gen_arbitrary_parts(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let parts = fields.iter().map(|(field, attrs)| {
+        let kind = attrs.kind();
+        let ty = match &*kind {
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                return quote! {
+                    <#ty>::__clap_arbitrary_parts(
+                        u,
+                        options,
+                        positionals,
+                        hyphen_values,
+                        #flatten_prefix,
+                    )?;
+                };
+            }
+            Kind::Arg(ty) => **ty,
+            Kind::Subcommand(_) | Kind::Skip(_) => return quote!(),
+        };
+
+        let value_type = match ty {
+            Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
+            Ty::OptionOption | Ty::OptionVec => {
+                sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
+            }
+            _ => &field.ty,
+        };
+        let takes_value = match ty {
+            Ty::Bool => false,
+            Ty::Other => match *attrs.parser().kind {
+                ParserKind::FromFlag | ParserKind::FromOccurrences => false,
+                _ => true,
+            },
+            _ => true,
+        };
+        let value = match attrs.possible_values() {
+            Some(values) => quote! {{
+                let values: &[&str] = #values;
+                u.choose(values)?.to_string()
+            }},
+            None if !takes_value || is_drawable(attrs, value_type) => quote! {{
+                let value: #value_type = u.arbitrary()?;
+                value.to_string()
+            }},
+            None if attrs.is_required() => abort!(
+                field.ty.span(),
+                "`arbitrary` can't draw the values of this argument";
+                help = "give it `possible_values`, or make it optional to leave it out of the command lines";
+                note = "only strings, numbers, `bool` and `char` parsed with `from_str` or `try_from_str` are drawn";
+            ),
+            // left out, and so are the optional positionals after it
+            None if attrs.long_name().is_none() && attrs.short_name().is_none() => {
+                return quote!( positionals_done.set(true); );
+            }
+            None => return quote!(),
+        };
+
+        let flag = match (attrs.long_name(), attrs.short_name()) {
+            (Some(_), _) => {
+                let attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+                let long = attrs.method_args("long");
+                quote!( Some(format!("--{}", #long)) )
+            }
+            (None, Some(short)) => {
+                let short = format!("-{}", short);
+                quote!( if #prefix.0.is_empty() { Some(String::from(#short)) } else { None } )
+            }
+            (None, None) => {
+                // values starting with `-` would be taken for flags, so
+                // they're drawn again, a few times, before giving up
+                let value = quote! {{
+                    let mut value = #value;
+                    let mut tries = 0;
+                    while !hyphen_values && value.starts_with('-') {
+                        if tries == 8 {
+                            return Err(::arbitrary::Error::IncorrectFormat);
+                        }
+                        value = #value;
+                        tries += 1;
+                    }
+                    value
+                }};
+                return match ty {
+                    Ty::Vec | Ty::OptionVec => quote! {
+                        if !positionals_done.get() {
+                            for _ in 0..u.int_in_range(0..=3)? {
+                                positionals.push(#value);
+                            }
+                        }
+                    },
+                    Ty::Option | Ty::OptionOption => quote! {
+                        if !positionals_done.get() && u.arbitrary()? {
+                            positionals.push(#value);
+                        } else {
+                            positionals_done.set(true);
+                        }
+                    },
+                    _ => quote!( positionals.push(#value); ),
+                };
+            }
+        };
+        let with_value = quote!( options.push(format!("{}={}", flag, #value)); );
+
+        let push = match ty {
+            Ty::Bool => quote! {
+                if u.arbitrary()? {
+                    options.push(flag.clone());
+                }
+            },
+            Ty::Other if *attrs.parser().kind == ParserKind::FromFlag => quote! {
+                if u.arbitrary()? {
+                    options.push(flag.clone());
+                }
+            },
+            Ty::Other if *attrs.parser().kind == ParserKind::FromOccurrences => quote! {
+                for _ in 0..u.int_in_range(0..=3)? {
+                    options.push(flag.clone());
+                }
+            },
+            Ty::Option => quote! {
+                if u.arbitrary()? {
+                    #with_value
+                }
+            },
+            Ty::OptionOption => quote! {
+                match u.int_in_range(0..=2)? {
+                    0 => (),
+                    1 => options.push(flag.clone()),
+                    _ => #with_value
+                }
+            },
+            Ty::Vec | Ty::OptionVec => quote! {
+                for _ in 0..u.int_in_range(0..=3)? {
+                    #with_value
+                }
+            },
+            Ty::Other if attrs.is_required() => with_value,
+            Ty::Other => quote! {
+                if u.arbitrary()? {
+                    #with_value
+                }
+            },
+        };
+        // short flags are dropped under a prefix, leaving the argument out
+        quote! {
+            if let Some(flag) = #flag {
+                #push
+            }
+        }
+    });
+
+    quote! {
+        // Once an optional positional is left out, the following ones must
+        // be too or they would take its place.
+        let positionals_done = ::std::cell::Cell::new(false);
+        #( #parts )*
+    }
+}
+
+/// Code pushing a random subcommand of `fields`, if they have one, into
+/// `args`.
+fn gen_arbitrary_subcommand(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let subcommands = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::Subcommand(ty) => Some(match (**ty, sub_type(&field.ty)) {
+                (Ty::Option, Some(sub_type)) => quote! {
+                    if u.arbitrary()? {
+                        <#sub_type>::__clap_arbitrary_subcommand(
+                            u,
+                            args,
+                            Self::__clap_casing_prefix(#prefix),
+                        )?;
+                    }
+                },
+                _ => {
+                    let ty = &field.ty;
+                    quote! {
+                        <#ty>::__clap_arbitrary_subcommand(
+                            u,
+                            args,
+                            Self::__clap_casing_prefix(#prefix),
+                        )?;
+                    }
+                }
+            }),
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                Some(quote!( <#ty>::__clap_arbitrary_subcommand(u, args, #flatten_prefix)?; ))
+            }
+            _ => None,
+        }
+    });
+
+    quote!( #( #subcommands )* )
+}
+
+/// Whether the values of an argument of type `value_type` can be drawn with
+/// `Arbitrary` and given back to its parser with `ToString`.
+fn is_drawable(attrs: &Attrs, value_type: &syn::Type) -> bool {
+    match *attrs.parser().kind {
+        ParserKind::FromStr | ParserKind::TryFromStr => (),
+        _ => return false,
+    }
+    let ident = match value_type {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => match path.segments.last() {
+            Some(segment) if segment.arguments.is_empty() => &segment.ident,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    [
+        "String", "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ]
+    .iter()
+    .any(|name| ident == name)
+}
@@ -16,7 +16,7 @@ use std::env;
 use proc_macro2;
 use syn;
 
-use super::{spanned::Sp, Attrs, GenOutput, Name, DEFAULT_CASING};
+use super::{spanned::Sp, Attrs, GenOutput, Name, DEFAULT_CASING, DEFAULT_ENV_CASING};
 
 pub fn derive_into_app(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
     use syn::Data::*;
@@ -37,12 +37,36 @@ pub fn derive_into_app(input: &syn::DeriveInput) -> proc_macro2::TokenStream {
 pub fn gen_into_app_impl_for_struct(name: &syn::Ident, attrs: &[syn::Attribute]) -> GenOutput {
     let into_app_fn = gen_into_app_fn_for_struct(attrs);
     let into_app_fn_tokens = into_app_fn.tokens;
+    let app_name = into_app_fn.attrs.cased_name();
+    let help_template_fn = gen_help_template_fn(&into_app_fn.attrs);
+    let help_messages_fn = gen_help_messages_fn(&into_app_fn.attrs);
+    let app_fn = gen_app_fn(name, &into_app_fn.attrs);
 
     let tokens = quote! {
         impl ::clap::IntoApp for #name {
             #into_app_fn_tokens
         }
 
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        impl #name {
+            /// The `App` parsing a command line made of `words`.
+            pub fn __clap_lazy_app<'b>(words: &[String]) -> ::clap::App<'b> {
+                #[cfg(debug_assertions)]
+                Self::__clap_debug_assert();
+                let app = ::clap::App::new(#app_name);
+                let app = Self::__clap_apply_help_template(app);
+                let app = Self::__clap_augment_app_with(app, Some(words));
+                Self::__clap_apply_help_messages(app)
+            }
+
+            #help_template_fn
+
+            #help_messages_fn
+        }
+
+        #app_fn
+
         impl<'b> Into<::clap::App<'b>> for #name {
             fn into(self) -> ::clap::App<'b> {
                 use ::clap::IntoApp;
@@ -63,7 +87,9 @@ pub fn gen_into_app_fn_for_struct(struct_attrs: &[syn::Attribute]) -> GenOutput
 
     let tokens = quote! {
         fn into_app<'b>() -> ::clap::App<'b> {
-            Self::augment_app(#app_tokens)
+            #[cfg(debug_assertions)]
+            Self::__clap_debug_assert();
+            Self::__clap_apply_help_messages(Self::augment_app(#app_tokens))
         }
     };
 
@@ -81,10 +107,14 @@ pub fn gen_app_builder(attrs: &[syn::Attribute]) -> GenOutput {
         attrs,
         Name::Assigned(syn::LitStr::new(&name, proc_macro2::Span::call_site())),
         Sp::call_site(DEFAULT_CASING),
+        Sp::call_site(DEFAULT_ENV_CASING),
     );
     let tokens = {
         let name = attrs.cased_name();
-        quote!(::clap::App::new(#name))
+        let help_template = attrs
+            .help_template()
+            .map(|template| quote!( .help_template(#template) ));
+        quote!(::clap::App::new(#name)#help_template)
     };
 
     GenOutput { tokens, attrs }
@@ -93,12 +123,37 @@ pub fn gen_app_builder(attrs: &[syn::Attribute]) -> GenOutput {
 pub fn gen_into_app_impl_for_enum(name: &syn::Ident, attrs: &[syn::Attribute]) -> GenOutput {
     let into_app_fn = gen_into_app_fn_for_enum(attrs);
     let into_app_fn_tokens = into_app_fn.tokens;
+    let app_name = into_app_fn.attrs.cased_name();
+    let help_template_fn = gen_help_template_fn(&into_app_fn.attrs);
+    let help_messages_fn = gen_help_messages_fn(&into_app_fn.attrs);
+    let app_fn = gen_app_fn(name, &into_app_fn.attrs);
 
     let tokens = quote! {
         impl ::clap::IntoApp for #name {
             #into_app_fn_tokens
         }
 
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        impl #name {
+            /// The `App` parsing a command line made of `words`.
+            pub fn __clap_lazy_app<'b>(words: &[String]) -> ::clap::App<'b> {
+                #[cfg(debug_assertions)]
+                Self::__clap_debug_assert();
+                let app = ::clap::App::new(#app_name)
+                    .setting(::clap::AppSettings::SubcommandRequiredElseHelp);
+                let app = Self::__clap_apply_help_template(app);
+                let app = Self::__clap_augment_app_with(app, Some(words));
+                Self::__clap_apply_help_messages(app)
+            }
+
+            #help_template_fn
+
+            #help_messages_fn
+        }
+
+        #app_fn
+
         impl<'b> Into<::clap::App<'b>> for #name {
             fn into(self) -> ::clap::App<'b> {
                 use ::clap::IntoApp;
@@ -119,9 +174,11 @@ pub fn gen_into_app_fn_for_enum(enum_attrs: &[syn::Attribute]) -> GenOutput {
 
     let tokens = quote! {
         fn into_app<'b>() -> ::clap::App<'b> {
+            #[cfg(debug_assertions)]
+            Self::__clap_debug_assert();
             let app = #app_tokens
                 .setting(::clap::AppSettings::SubcommandRequiredElseHelp);
-            Self::augment_app(app)
+            Self::__clap_apply_help_messages(Self::augment_app(app))
         }
     };
 
@@ -130,3 +187,145 @@ pub fn gen_into_app_fn_for_enum(enum_attrs: &[syn::Attribute]) -> GenOutput {
         attrs: gen.attrs,
     }
 }
+
+/// Generate `__clap_apply_help_template()`, giving an `App` the
+/// `help_template` of the command, if any.
+///
+/// This is how a variant wrapping the command gets its template, which is
+/// not applied when the command is flattened.
+fn gen_help_template_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    let help_template = attrs
+        .help_template()
+        .map(|template| quote!( .help_template(#template) ));
+
+    quote! {
+        pub fn __clap_apply_help_template<'b>(app: ::clap::App<'b>) -> ::clap::App<'b> {
+            app#help_template
+        }
+    }
+}
+
+/// Generate the `with_clap_app()` accessor of the types asking for it with
+/// `#[clap(cached_app)]`, which needs the `cached_app` feature, building the
+/// `App` once per thread and lending it out, or a new one to the calls made
+/// while it is lent.
+///
+/// The `App` is the one of `into_app()`, the environment and configuration
+/// files being read when parsing.
+fn gen_app_fn(name: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    if !attrs.has_cached_app() {
+        return quote!();
+    }
+
+    quote! {
+        impl #name {
+            /// Call `f` with the `App` of the command, built the first time
+            /// it is asked for on the current thread and kept until the
+            /// thread exits.
+            ///
+            /// When `f` asks for the `App` again, the inner call gets one
+            /// built for it alone, the cached one being lent out.
+            #[allow(unreachable_pub, dead_code)]
+            pub fn with_clap_app<R, F>(f: F) -> R
+            where
+                F: FnOnce(&mut ::clap::App<'static>) -> R {
+                thread_local! {
+                    static APP: ::std::cell::RefCell<::clap::App<'static>> =
+                        ::std::cell::RefCell::new(<#name as ::clap::IntoApp>::into_app());
+                }
+                APP.with(|app| match app.try_borrow_mut() {
+                    Ok(mut app) => f(&mut app),
+                    Err(_) => f(&mut <#name as ::clap::IntoApp>::into_app()),
+                })
+            }
+        }
+    }
+}
+
+/// The `App` of `name`, a clone of the cached one with `cached_app`.
+pub fn gen_app_expr(name: &syn::Ident, attrs: &Attrs) -> proc_macro2::TokenStream {
+    if attrs.has_cached_app() {
+        quote!( #name::with_clap_app(|app| app.clone()) )
+    } else {
+        quote!( <#name as ::clap::IntoApp>::into_app() )
+    }
+}
+
+/// Generate `__clap_apply_help_messages()`, giving the `--help` and
+/// `--version` flags of an `App` and of all its subcommands the
+/// `help_message` and `version_message` of the command, if any.
+///
+/// Only the apps having a `--version` flag, their own or propagated from
+/// their parent without `VersionlessSubcommands`, get the `version_message`.
+/// The flags are left without `-h` or `-V` when one of the arguments of the
+/// app already uses it.
+fn gen_help_messages_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    let help = attrs.help_message().map(|message| {
+        quote! {
+            let short_taken = app
+                .get_arguments()
+                .any(|arg| arg.get_name() != "help" && arg.get_short() == Some('h'));
+            let app = app.mut_arg("help", |arg| {
+                let arg = arg.long("help").help(#message);
+                if short_taken {
+                    arg
+                } else {
+                    arg.short('h')
+                }
+            });
+        }
+    });
+    let version = attrs.version_message().map(|message| {
+        quote! {
+            let app = if versioned {
+                let short_taken = app
+                    .get_arguments()
+                    .any(|arg| arg.get_name() != "version" && arg.get_short() == Some('V'));
+                app.mut_arg("version", |arg| {
+                    let arg = arg.long("version").help(#message);
+                    if short_taken {
+                        arg
+                    } else {
+                        arg.short('V')
+                    }
+                })
+            } else {
+                app
+            };
+        }
+    });
+    if help.is_none() && version.is_none() {
+        return quote! {
+            pub fn __clap_apply_help_messages<'b>(app: ::clap::App<'b>) -> ::clap::App<'b> {
+                app
+            }
+        };
+    }
+
+    quote! {
+        pub fn __clap_apply_help_messages<'b>(app: ::clap::App<'b>) -> ::clap::App<'b> {
+            Self::__clap_apply_help_messages_to(app, false)
+        }
+
+        /// Same as `__clap_apply_help_messages`, `app` having a `--version`
+        /// flag from its parent when `inherited_version`.
+        pub fn __clap_apply_help_messages_to<'b>(
+            app: ::clap::App<'b>,
+            inherited_version: bool,
+        ) -> ::clap::App<'b> {
+            let versioned = inherited_version
+                || app.get_version().is_some()
+                || app.get_long_version().is_some();
+            let propagated = versioned && !app.is_set(::clap::AppSettings::VersionlessSubcommands);
+            #help
+            #version
+            let names: Vec<String> =
+                app.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+            names.iter().fold(app, |app, name| {
+                app.mut_subcommand(name.as_str(), |sub| {
+                    Self::__clap_apply_help_messages_to(sub, propagated)
+                })
+            })
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Runtime translation of help messages through `help_key`s.
+//!
+//! Every derived type gets a `__clap_localize(prefix, key)` function calling
+//! the `localizer` given on top of it, if any, or else the one of the types
+//! it is flattened in or is a subcommand of, carried by the runtime prefix.
+//! Messages are only replaced when the localizer knows the key, so doc
+//! comments act as the default language.
+
+use proc_macro2;
+
+use super::{from_argmatches, Attrs};
+
+/// Generate the `__clap_localize()` function, along with
+/// `__clap_localizing_prefix()`, handing the `localizer` of the type down to
+/// the ones it augments its app with through the runtime prefix.
+pub fn gen_localize_fn(attrs: &Attrs) -> proc_macro2::TokenStream {
+    let prefix_type = from_argmatches::prefix_type();
+    let localizer = match attrs.localizer() {
+        Some(localizer) => quote! {{
+            let localizer: fn(&str) -> Option<String> = #localizer;
+            Some(localizer)
+        }},
+        None => quote!(prefix.3),
+    };
+
+    quote! {
+        pub fn __clap_localizing_prefix(prefix: #prefix_type) -> #prefix_type {
+            (prefix.0, prefix.1, prefix.2, #localizer)
+        }
+
+        pub fn __clap_localize(prefix: #prefix_type, key: &str) -> Option<&'static str> {
+            let lookup = Self::__clap_localizing_prefix(prefix).3?;
+            lookup(key).map(Self::__clap_interned)
+        }
+    }
+}
+
+/// The runtime prefix handed to `__clap_localize()`, that of the `prefix`
+/// variable, if any.
+pub fn localize_prefix(prefix: Option<&syn::Ident>) -> proc_macro2::TokenStream {
+    match prefix {
+        Some(prefix) => quote!(#prefix),
+        None => from_argmatches::no_prefix(),
+    }
+}
+
+/// Generate the code translating the help of the argument described by
+/// `attrs`, with the id given by `name`, if it has a `help_key`.
+///
+/// `<key>.long` is looked up for the long help, defaulting to the
+/// translated short help.
+pub fn gen_localized_help(
+    app_var: &syn::Ident,
+    name: &proc_macro2::TokenStream,
+    attrs: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let key = match attrs.help_key() {
+        Some(key) => key,
+        None => return quote!(),
+    };
+    let long_key = format!("{}.long", key.value());
+    let prefix = localize_prefix(prefix);
+
+    quote! {
+        let #app_var = match Self::__clap_localize(#prefix, #key) {
+            Some(help) => {
+                let long_help = Self::__clap_localize(#prefix, #long_key).unwrap_or(help);
+                #app_var.mut_arg(#name, |arg| arg.help(help).long_help(long_help))
+            }
+            None => #app_var,
+        };
+    }
+}
+
+/// Generate the code translating the about message of the app described by
+/// `attrs`, if it has a `help_key`.
+///
+/// `<key>.long` is looked up for the long about, defaulting to the
+/// translated about.
+pub fn gen_localized_about(
+    app_var: &syn::Ident,
+    attrs: &Attrs,
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let key = match attrs.help_key() {
+        Some(key) => key,
+        None => return quote!(),
+    };
+    let long_key = format!("{}.long", key.value());
+    let prefix = localize_prefix(prefix);
+
+    quote! {
+        let #app_var = match Self::__clap_localize(#prefix, #key) {
+            Some(about) => {
+                let long_about = Self::__clap_localize(#prefix, #long_key).unwrap_or(about);
+                #app_var.about(about).long_about(long_about)
+            }
+            None => #app_var,
+        };
+    }
+}
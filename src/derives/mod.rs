@@ -18,13 +18,25 @@ pub mod attrs;
 pub mod parse;
 pub mod spanned;
 pub mod ty;
+mod argv;
+mod checks;
 mod clap;
+mod color;
+mod completion;
+mod config;
+mod docs;
 mod from_argmatches;
+mod fuzz;
 mod into_app;
+mod localize;
+mod occurrences;
+mod plugin;
+mod prompt;
+mod untagged;
 
 pub use self::arg_enum::derive_arg_enum;
-pub use self::attrs::{Attrs, Kind, Name, Parser, ParserKind, CasingStyle, GenOutput, DEFAULT_CASING};
-pub use self::ty::{sub_type, Ty};
+pub use self::attrs::{Attrs, Kind, Name, Parser, ParserKind, CasingStyle, GenOutput, VariantAttrs, DEFAULT_CASING, DEFAULT_ENV_CASING};
+pub use self::ty::{fixed_values, is_integer_ty, nonzero_integer_ty, sub_type, Ty};
 pub use self::clap::derive_clap;
 pub use self::from_argmatches::derive_from_argmatches;
 pub use self::into_app::derive_into_app;
@@ -0,0 +1,227 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Limits on how many times an argument is given, set with
+//! `max_occurrences`, and errors naming the environment variable a bad value
+//! was read from.
+//!
+//! clap has no such limit, and its validators can't tell where a value comes
+//! from, so the matches are checked after parsing, before the fields are
+//! built from them. The validators leave the values equal to the one of the
+//! environment variable to this check: they are read from it when their
+//! argument doesn't occur on the command line. A warning tells when they come
+//! from a legacy variable of a field marked with `warn_legacy_env`.
+
+use proc_macro2;
+use syn::{self, spanned::Spanned};
+
+use super::{clap, config, from_argmatches, plugin, sub_type, Attrs, Kind, Ty, VariantAttrs};
+
+/// Generate the `__clap_check_occurrences()` function of a struct, checking
+/// its arguments, flattened ones and subcommands included.
+pub fn gen_check_occurrences_fn_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let checks = gen_checks(fields, Some(&prefix));
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+
+    quote! {
+        /// Fail if an argument of `matches` was given more times than its
+        /// `max_occurrences`, or if a value left to it by the validators is
+        /// bad, `prefix` being in front of the flattened ids.
+        pub fn __clap_check_occurrences(
+            matches: &::clap::ArgMatches,
+            #prefix: #prefix_type,
+        ) -> ::std::result::Result<(), ::clap::Error> {
+            #propagated_casing
+            #checks
+            Ok(())
+        }
+    }
+}
+
+/// Generate the `__clap_check_occurrences()` function of an enum, checking
+/// the arguments of the subcommand found in `matches`.
+pub fn gen_check_occurrences_fn_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    use syn::Fields::*;
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let arms = variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .filter_map(|(variant, attrs, fields)| {
+            let sub_name = attrs.gen_runtime_cased_name(&prefix);
+            let checks = match (&variant.fields, fields) {
+                (Named(_), Some(fields)) => {
+                    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                    let checks = gen_checks(fields, Some(&prefix));
+                    quote!( #propagated_casing #checks )
+                }
+                (Unnamed(fields), _) if fields.unnamed.len() == 1 => {
+                    let ty = &fields.unnamed[0].ty;
+                    quote! {
+                        <#ty>::__clap_check_occurrences(
+                            matches,
+                            Self::__clap_casing_prefix(#prefix),
+                        )?;
+                    }
+                }
+                _ => return None,
+            };
+            Some(quote! {
+                (sub_name, Some(matches)) if sub_name == #sub_name => {
+                    #checks
+                }
+            })
+        });
+
+    let plugin_arm = plugin::gen_plugin_check_arm(plugin::find_plugin_variant(variants));
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+
+    quote! {
+        /// Fail if an argument of the subcommand in `matches` was given more
+        /// times than its `max_occurrences` or has a bad value, or if the
+        /// subcommand is unknown and no plugin executable matches it.
+        pub fn __clap_check_occurrences(
+            matches: &::clap::ArgMatches,
+            #prefix: #prefix_type,
+        ) -> ::std::result::Result<(), ::clap::Error> {
+            #propagated_casing
+            match matches.subcommand() {
+                #( #arms )*
+                #plugin_arm
+                _ => (),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Generate the checks of `fields`, looking the arguments up with the value
+/// of the `prefix` variable, if any, in front of their ids.
+fn gen_checks(
+    fields: &[(&syn::Field, Attrs)],
+    prefix: Option<&syn::Ident>,
+) -> proc_macro2::TokenStream {
+    let checks = fields.iter().filter_map(|(field, attrs)| {
+        match &*attrs.kind() {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                let prefix = match prefix {
+                    Some(prefix) => quote!( Self::__clap_casing_prefix(#prefix) ),
+                    None => from_argmatches::no_prefix(),
+                };
+                Some(quote!( <#subcmd_type>::__clap_check_occurrences(matches, #prefix)?; ))
+            }
+            Kind::FlattenStruct => {
+                let ty = &field.ty;
+                let prefix = config::gen_flatten_prefix(prefix, &attrs)
+                    .unwrap_or_else(from_argmatches::no_prefix);
+                Some(quote!( <#ty>::__clap_check_occurrences(matches, #prefix)?; ))
+            }
+            Kind::Arg(ty) => {
+                let max = attrs.max_occurrences();
+                let env = attrs.gen_env_var(prefix);
+                let check = env.as_ref().and_then(|_| clap::gen_value_check(field, &attrs, **ty));
+                if max.is_none() && check.is_none() && !attrs.warns_legacy_env() {
+                    return None;
+                }
+
+                let prefixed = |name: proc_macro2::TokenStream| match prefix {
+                    Some(prefix) => quote!( Self::__clap_prefixed(#prefix, #name) ),
+                    None => name,
+                };
+                let name = attrs.cased_name();
+                let name = prefixed(quote!(#name));
+                let shown = match (attrs.long_name(), attrs.short_name()) {
+                    (Some(long), _) => {
+                        let long = prefixed(quote!(#long));
+                        quote!( format!("--{}", #long) )
+                    }
+                    (None, Some(short)) => quote!( format!("-{}", #short) ),
+                    (None, None) => quote!( format!("<{}>", #name) ),
+                };
+                let max = max.map(|max| {
+                    quote_spanned! { max.span()=>
+                        let max: u64 = #max;
+                        if matches.occurrences_of(#name) > max {
+                            return Err(::clap::Error::with_description(
+                                &format!(
+                                    "The argument '{}' was provided more than {} times",
+                                    #shown, max
+                                ),
+                                ::clap::ErrorKind::UnexpectedMultipleUsage,
+                            ));
+                        }
+                    }
+                });
+                // the validator leaves the values equal to the variable to
+                // this check, as they may come from the command line too
+                let check = check.map(|check| {
+                    quote! {
+                        let env = ::std::string::String::from(#env);
+                        let env_value = ::std::env::var_os(&env);
+                        let from_env = matches.occurrences_of(#name) == 0;
+                        let check = #check;
+                        for s in matches.values_of_os(#name).into_iter().flatten() {
+                            // the other values were checked by the validator
+                            if env_value.as_ref().map_or(true, |v| v.as_os_str() != s) {
+                                continue;
+                            }
+                            if let Err(e) = check(s) {
+                                let e = if from_env {
+                                    format!("{} (read from the `{}` environment variable)", e, env)
+                                } else {
+                                    e
+                                };
+                                return Err(::clap::Error::with_description(
+                                    &format!("Invalid value for '{}': {}", #shown, e),
+                                    ::clap::ErrorKind::ValueValidation,
+                                ));
+                            }
+                        }
+                    }
+                });
+                let warning = if attrs.warns_legacy_env() {
+                    let current = attrs.env_var().map(|env| match prefix {
+                        Some(prefix) => quote!( Self::__clap_prefixed_env(#prefix, #env) ),
+                        None => env,
+                    });
+                    Some(quote! {
+                        let current: &'static str = #current;
+                        let read: &'static str = #env;
+                        if read != current
+                            && matches.is_present(#name)
+                            && matches.occurrences_of(#name) == 0
+                        {
+                            Self::__clap_warn_legacy_env(current, read);
+                        }
+                    })
+                } else {
+                    None
+                };
+                Some(quote!( #max #check #warning ))
+            }
+            Kind::Skip(_) => None,
+        }
+    });
+
+    quote!( #( { #checks } )* )
+}
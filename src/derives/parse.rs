@@ -1,6 +1,6 @@
 use std::iter::FromIterator;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use proc_macro_error::{abort, ResultExt};
 use syn::{
     self, parenthesized,
@@ -33,14 +33,69 @@ pub enum ClapAttr {
     Flatten(Ident),
     Subcommand(Ident),
     NoVersion(Ident),
+    NoAutoVersion(Ident),
+    Partial(Ident),
+    Secret(Ident),
+    WarnLegacyEnv(Ident),
+    ExplicitBool(Ident),
+    Canonicalize(Ident),
+    ExpandTilde(Ident),
+    Trim(Ident),
+    Lowercase(Ident),
+    Plugin(Ident),
+    Registered(Ident),
+    DynamicCompletion(Ident),
+    Docs(Ident),
+    ParseFromStr(Ident),
+    Argfile(Ident),
+    Arbitrary(Ident),
+    Prompt(Ident),
+    Zeroize(Ident),
+    CachedApp(Ident),
+    Lazy(Ident),
+    Env(Ident),
+    CaseInsensitiveLongs(Ident),
+    HidePossibleValues(Ident),
+    ShowDefaults(Ident),
+    ShowEnv(Ident),
+    SourceOrder(Ident),
+    ArgEnum(Ident),
+    Verbosity(Ident),
+    ParseOutput(Ident),
+    Untagged(Ident),
+    PropagateRenameAll(Ident),
+    ArgMatches(Ident),
+    BinName(Ident),
+    RawArgv(Ident),
+    HideShortHelp(Ident),
+    HideLongHelp(Ident),
+    ImplFromStr(Ident),
+    ImplDisplay(Ident),
+    CaseSensitive(Ident),
 
     // ident [= "string literal"]
     About(Ident, Option<LitStr>),
     Author(Ident, Option<LitStr>),
+    Dotenv(Ident, Option<LitStr>),
+    ArgIds(Ident, Option<LitStr>),
+    Color(Ident, Option<LitStr>),
 
     // ident = "string literal"
-    Version(Ident, LitStr),
+    Version(Ident, Option<LitStr>),
     RenameAll(Ident, LitStr),
+    RenameAllEnv(Ident, LitStr),
+    RenameAllValue(Ident, LitStr),
+    LegacyRenameAll(Ident, LitStr),
+    ConfigFile(Ident, LitStr),
+    EnvPrefix(Ident, LitStr),
+    LongPrefix(Ident, LitStr),
+    Prefix(Ident, LitStr),
+    HelpKey(Ident, LitStr),
+    HelpTemplate(Ident, LitStr),
+    HelpMessage(Ident, LitStr),
+    VersionMessage(Ident, LitStr),
+    Example(Ident, LitStr),
+    OccurrencesOf(Ident, LitStr),
     NameLitStr(Ident, LitStr),
 
     // parse(parser_kind [= parser_func])
@@ -51,6 +106,37 @@ pub enum ClapAttr {
 
     // ident = arbitrary_expr
     NameExpr(Ident, Expr),
+    Localizer(Ident, Expr),
+    MutArg(Ident, Expr),
+    MutQuiet(Ident, Expr),
+    MaxOccurrences(Ident, Expr),
+    ExitCode(Ident, Expr),
+    PossibleValuesFn(Ident, Expr),
+    CompleteWith(Ident, Expr),
+
+    // action = Identifier
+    Action(Ident, Ident),
+
+    // env = ["string literal", ...]
+    EnvList(Ident, Vec<LitStr>),
+
+    // requires_all = ["string literal", ...]
+    RequiresAll(Ident, Vec<LitStr>),
+    // value_names = ["string literal", ...]
+    ValueNames(Ident, Vec<LitStr>),
+    // required_unless_any = ["string literal", ...]
+    // required_unless_all = ["string literal", ...]
+    RequiredUnless(Ident, Vec<LitStr>),
+    // default_value_ifs = [("string literal", "string literal" | None, "string literal"), ...]
+    DefaultValueIfs(Ident, Vec<(LitStr, Option<LitStr>, LitStr)>),
+    // requires_if("string literal", "string literal")
+    // required_if("string literal", "string literal")
+    RelationIf(Ident, LitStr, LitStr),
+    // group(name = "string literal", args = ["string literal", ...], ...)
+    Group(Ident, GroupSpec),
+
+    // levels = [arbitrary_expr, ...]
+    Levels(Ident, Vec<Expr>),
 
     // ident(arbitrary_expr,*)
     MethodCall(Ident, Vec<Expr>),
@@ -85,9 +171,41 @@ impl Parse for ClapAttr {
                 match &*name_str.to_string() {
                     "rename_all" => Ok(RenameAll(name, lit)),
 
+                    "rename_all_env" => Ok(RenameAllEnv(name, lit)),
+
+                    "rename_all_value" => Ok(RenameAllValue(name, lit)),
+
+                    "legacy_rename_all" => Ok(LegacyRenameAll(name, lit)),
+
+                    "config_file" => Ok(ConfigFile(name, lit)),
+
+                    "dotenv" => Ok(Dotenv(name, Some(lit))),
+
+                    "arg_ids" => Ok(ArgIds(name, Some(lit))),
+
+                    "env_prefix" => Ok(EnvPrefix(name, lit)),
+
+                    "long_prefix" => Ok(LongPrefix(name, lit)),
+
+                    "prefix" => Ok(Prefix(name, lit)),
+
+                    "color" => Ok(Color(name, Some(lit))),
+
+                    "help_key" => Ok(HelpKey(name, lit)),
+
+                    "help_template" => Ok(HelpTemplate(name, lit)),
+
+                    "help_message" => Ok(HelpMessage(name, lit)),
+
+                    "version_message" => Ok(VersionMessage(name, lit)),
+
+                    "example" => Ok(Example(name, lit)),
+
+                    "occurrences_of" => Ok(OccurrencesOf(name, lit)),
+
                     "version" => {
                         check_empty_lit("version");
-                        Ok(Version(name, lit))
+                        Ok(Version(name, Some(lit)))
                     }
 
                     "author" => {
@@ -116,6 +234,97 @@ impl Parse for ClapAttr {
                     Ok(expr) => {
                         if name_str == "skip" {
                             Ok(Skip(name, Some(expr)))
+                        } else if name_str == "localizer" {
+                            Ok(Localizer(name, expr))
+                        } else if name_str == "mut_arg" {
+                            Ok(MutArg(name, expr))
+                        } else if name_str == "mut_quiet" {
+                            Ok(MutQuiet(name, expr))
+                        } else if name_str == "max_occurrences" {
+                            Ok(MaxOccurrences(name, expr))
+                        } else if name_str == "error_exit_code" || name_str == "usage_exit_code" {
+                            Ok(ExitCode(name, expr))
+                        } else if name_str == "possible_values_fn" {
+                            Ok(PossibleValuesFn(name, expr))
+                        } else if name_str == "complete_with" {
+                            Ok(CompleteWith(name, expr))
+                        } else if name_str == "action" {
+                            let action = match &expr {
+                                Expr::Path(path) => path.path.get_ident().cloned(),
+                                _ => None,
+                            };
+                            match action {
+                                Some(action) => Ok(Action(name, action)),
+                                None => abort!(
+                                    expr.span(),
+                                    "`action` expects one of `Set`, `SetTrue`, `Count` or `Append`"
+                                ),
+                            }
+                        } else if name_str == "levels" {
+                            let levels = match expr {
+                                Expr::Array(array) => array,
+                                _ => abort!(
+                                    expr.span(),
+                                    "`levels` expects a list of values, \
+                                     like `levels = [Level::Warn, Level::Info]`"
+                                ),
+                            };
+                            if levels.elems.is_empty() {
+                                abort!(levels.span(), "`levels` needs at least one value");
+                            }
+                            Ok(Levels(name, levels.elems.into_iter().collect()))
+                        } else if let ("requires_all", Expr::Array(array)) = (&*name_str, &expr) {
+                            let names = array.elems.iter().map(|elem| match elem {
+                                Expr::Lit(ExprLit {
+                                    lit: Lit::Str(lit), ..
+                                }) => lit.clone(),
+                                _ => abort!(
+                                    elem.span(),
+                                    "`requires_all` expects field names, \
+                                     like `requires_all = [\"user\", \"password\"]`"
+                                ),
+                            });
+                            Ok(RequiresAll(name, names.collect()))
+                        } else if let Some(names) = value_names_lits(&name_str, &expr) {
+                            if names.is_empty() {
+                                abort!(expr.span(), "`value_names` needs at least one name");
+                            }
+                            Ok(ValueNames(name, names))
+                        } else if let ("required_unless_any", Expr::Array(array))
+                        | ("required_unless_all", Expr::Array(array)) = (&*name_str, &expr)
+                        {
+                            let names = array.elems.iter().map(|elem| match elem {
+                                Expr::Lit(ExprLit {
+                                    lit: Lit::Str(lit), ..
+                                }) => lit.clone(),
+                                _ => abort!(
+                                    elem.span(),
+                                    "`{}` expects field names, \
+                                     like `{} = [\"stdin\", \"file\"]`", name_str, name_str
+                                ),
+                            });
+                            let names: Vec<LitStr> = names.collect();
+                            if names.is_empty() {
+                                abort!(array.span(), "`{}` needs at least one field", name_str);
+                            }
+                            Ok(RequiredUnless(name, names))
+                        } else if name_str == "default_value_ifs" {
+                            Ok(DefaultValueIfs(name, parse_default_value_ifs(&expr)))
+                        } else if let ("env", Expr::Array(array)) = (&*name_str, &expr) {
+                            let names = array.elems.iter().map(|elem| match elem {
+                                Expr::Lit(ExprLit {
+                                    lit: Lit::Str(lit), ..
+                                }) => lit.clone(),
+                                _ => abort!(
+                                    elem.span(),
+                                    "`env` expects string literals, like `env = [\"NEW\", \"OLD\"]`"
+                                ),
+                            });
+                            let names: Vec<LitStr> = names.collect();
+                            if names.is_empty() {
+                                abort!(array.span(), "`env` needs at least one variable name");
+                            }
+                            Ok(EnvList(name, names))
                         } else {
                             Ok(NameExpr(name, expr))
                         }
@@ -165,6 +374,36 @@ impl Parse for ClapAttr {
                     }
                 },
 
+                "requires_if" | "required_if" => {
+                    let args: Punctuated<LitStr, Token![,]> =
+                        nested.parse_terminated(<LitStr as Parse>::parse)?;
+                    if args.len() != 2 {
+                        if name_str == "requires_if" {
+                            abort!(
+                                name.span(),
+                                "`requires_if` expects a value and a field name, \
+                                 like `requires_if(\"tls\", \"cert\")`"
+                            )
+                        } else {
+                            abort!(
+                                name.span(),
+                                "`required_if` expects a field name and a value, \
+                                 like `required_if(\"mode\", \"tls\")`"
+                            )
+                        }
+                    }
+                    let mut args = args.into_iter();
+                    let first = args.next().unwrap();
+                    let second = args.next().unwrap();
+                    Ok(RelationIf(name, first, second))
+                }
+
+                "group" => {
+                    let settings: Punctuated<Expr, Token![,]> =
+                        nested.parse_terminated(Expr::parse)?;
+                    Ok(Group(name.clone(), GroupSpec::from_settings(&name, settings)))
+                }
+
                 _ => {
                     let method_args: Punctuated<_, Token![,]> =
                         nested.parse_terminated(Expr::parse)?;
@@ -179,19 +418,55 @@ impl Parse for ClapAttr {
                 "flatten" => Ok(Flatten(name)),
                 "subcommand" => Ok(Subcommand(name)),
                 "no_version" => Ok(NoVersion(name)),
+                "no_auto_version" => Ok(NoAutoVersion(name)),
+                "partial" => Ok(Partial(name)),
+                "secret" => Ok(Secret(name)),
+                "warn_legacy_env" => Ok(WarnLegacyEnv(name)),
+                "explicit_bool" => Ok(ExplicitBool(name)),
+                "canonicalize" => Ok(Canonicalize(name)),
+                "expand_tilde" => Ok(ExpandTilde(name)),
+                "trim" => Ok(Trim(name)),
+                "lowercase" => Ok(Lowercase(name)),
+                "plugin" => Ok(Plugin(name)),
+                "registered" => Ok(Registered(name)),
+                "dynamic_completion" => Ok(DynamicCompletion(name)),
+                "docs" => Ok(Docs(name)),
+                "parse_from_str" => Ok(ParseFromStr(name)),
+                "argfile" => Ok(Argfile(name)),
+                "arbitrary" => Ok(Arbitrary(name)),
+                "prompt" => Ok(Prompt(name)),
+                "zeroize" => Ok(Zeroize(name)),
+                "cached_app" => Ok(CachedApp(name)),
+                "lazy" => Ok(Lazy(name)),
+                "env" => Ok(Env(name)),
+                "case_insensitive_longs" => Ok(CaseInsensitiveLongs(name)),
+                "hide_possible_values" => Ok(HidePossibleValues(name)),
+                "show_defaults" => Ok(ShowDefaults(name)),
+                "show_env" => Ok(ShowEnv(name)),
+                "source_order" => Ok(SourceOrder(name)),
+                "arg_enum" => Ok(ArgEnum(name)),
+                "verbosity" => Ok(Verbosity(name)),
+                "parse_output" => Ok(ParseOutput(name)),
+                "untagged" => Ok(Untagged(name)),
+                "propagate_rename_all" => Ok(PropagateRenameAll(name)),
+                "arg_matches" => Ok(ArgMatches(name)),
+                "bin_name" => Ok(BinName(name)),
+                "raw_argv" => Ok(RawArgv(name)),
+                "hide_short_help" => Ok(HideShortHelp(name)),
+                "hide_long_help" => Ok(HideLongHelp(name)),
+                "impl_from_str" => Ok(ImplFromStr(name)),
+                "impl_display" => Ok(ImplDisplay(name)),
+                "case_sensitive" => Ok(CaseSensitive(name)),
 
                 "about" => (Ok(About(name, None))),
                 "author" => (Ok(Author(name, None))),
+                "version" => Ok(Version(name, None)),
+                "dotenv" => Ok(Dotenv(name, None)),
+                "arg_ids" => Ok(ArgIds(name, None)),
+                "color" => Ok(Color(name, None)),
 
                 "skip" => Ok(Skip(name, None)),
 
-                "version" => abort!(
-                    name.span(),
-                    "#[clap(version)] is invalid attribute, \
-                     clap_derive inherits version from Cargo.toml by default, \
-                     no attribute needed"
-                ),
-
                 _ => abort!(name.span(), "unexpected attribute: {}", name_str),
             }
         }
@@ -223,6 +498,100 @@ impl Parse for ParserSpec {
     }
 }
 
+/// A group declared with `group(...)` on top of a struct.
+#[derive(Clone)]
+pub struct GroupSpec {
+    pub name: LitStr,
+    pub args: Vec<LitStr>,
+    pub multiple: Option<LitBool>,
+    pub required: Option<LitBool>,
+    pub requires: Vec<LitStr>,
+    pub conflicts_with: Vec<LitStr>,
+}
+
+impl GroupSpec {
+    fn from_settings(ident: &Ident, settings: Punctuated<Expr, Token![,]>) -> Self {
+        let mut name = None;
+        let mut spec = GroupSpec {
+            name: LitStr::new("", ident.span()),
+            args: Vec::new(),
+            multiple: None,
+            required: None,
+            requires: Vec::new(),
+            conflicts_with: Vec::new(),
+        };
+
+        for setting in settings {
+            let (key, value) = match &setting {
+                Expr::Assign(assign) => match &*assign.left {
+                    Expr::Path(path) if path.path.get_ident().is_some() => {
+                        (path.path.get_ident().unwrap().to_string(), &*assign.right)
+                    }
+                    _ => abort!(assign.left.span(), "expected a setting of the group"),
+                },
+                _ => abort!(
+                    setting.span(),
+                    "`group` expects settings, like `group(name = \"input\", args = [\"file\"])`";
+                    help = "use `group = ArgGroup::with_name(...)` to pass a `clap::ArgGroup`"
+                ),
+            };
+
+            match &*key {
+                "name" => name = Some(group_str(&key, value)),
+                "args" => spec.args.extend(group_strs(&key, value)),
+                "multiple" => spec.multiple = Some(group_bool(&key, value)),
+                "required" => spec.required = Some(group_bool(&key, value)),
+                "requires" => spec.requires.extend(group_strs(&key, value)),
+                "conflicts_with" => spec.conflicts_with.extend(group_strs(&key, value)),
+                _ => abort!(
+                    setting.span(),
+                    "unexpected setting of the group: {}", key;
+                    help = "the settings are `name`, `args`, `multiple`, `required`, \
+                        `requires` and `conflicts_with`"
+                ),
+            }
+        }
+
+        spec.name = match name {
+            Some(name) => name,
+            None => abort!(ident.span(), "`group` needs a `name`"),
+        };
+        if spec.args.is_empty() {
+            abort!(
+                ident.span(),
+                "the group `{}` needs at least one argument", spec.name.value();
+                help = "list the fields holding its arguments, like `args = [\"file\", \"url\"]`"
+            );
+        }
+        spec
+    }
+}
+
+fn group_str(key: &str, value: &Expr) -> LitStr {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => lit.clone(),
+        _ => abort!(value.span(), "`{}` expects a string literal", key),
+    }
+}
+
+fn group_strs(key: &str, value: &Expr) -> Vec<LitStr> {
+    match value {
+        Expr::Array(array) => array.elems.iter().map(|elem| group_str(key, elem)).collect(),
+        _ => vec![group_str(key, value)],
+    }
+}
+
+fn group_bool(key: &str, value: &Expr) -> LitBool {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(lit), ..
+        }) => lit.clone(),
+        _ => abort!(value.span(), "`{}` expects `true` or `false`", key),
+    }
+}
+
 fn raw_method_suggestion(ts: ParseBuffer) -> String {
     let do_parse = move || -> Result<(Ident, TokenStream), syn::Error> {
         let name = ts.parse()?;
@@ -266,3 +635,72 @@ pub fn parse_clap_attributes(all_attrs: &[Attribute]) -> Vec<ClapAttr> {
         })
         .collect()
 }
+
+/// The `(field, value, default)` conditions of `default_value_ifs`, the value
+/// being `None` for the field being given with any value.
+fn parse_default_value_ifs(expr: &Expr) -> Vec<(LitStr, Option<LitStr>, LitStr)> {
+    fn expected(span: Span) -> ! {
+        abort!(
+            span,
+            "`default_value_ifs` expects `(field, value, default)` triples, \
+             like `default_value_ifs = [(\"mode\", \"fast\", \"4\")]`";
+            help = "use `None` as the value for the field being given with any value"
+        )
+    }
+    fn lit_str(expr: &Expr) -> Option<LitStr> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => Some(lit.clone()),
+            _ => None,
+        }
+    }
+
+    let array = match expr {
+        Expr::Array(array) if !array.elems.is_empty() => array,
+        _ => expected(expr.span()),
+    };
+    array
+        .elems
+        .iter()
+        .map(|elem| {
+            let tuple = match elem {
+                Expr::Tuple(tuple) if tuple.elems.len() == 3 => tuple,
+                _ => expected(elem.span()),
+            };
+            let lit = |expr: &Expr| lit_str(expr).unwrap_or_else(|| expected(expr.span()));
+            let value = match &tuple.elems[1] {
+                Expr::Path(path) if path.path.is_ident("None") => None,
+                value => Some(lit(value)),
+            };
+            (lit(&tuple.elems[0]), value, lit(&tuple.elems[2]))
+        })
+        .collect()
+}
+
+/// The names of `value_names = ["...", ...]`, the reference of the array
+/// being optional, or `None` when the value is left to `Arg::value_names` as
+/// is.
+fn value_names_lits(name: &str, expr: &Expr) -> Option<Vec<LitStr>> {
+    if name != "value_names" {
+        return None;
+    }
+    let array = match expr {
+        Expr::Array(array) => array,
+        Expr::Reference(reference) => match &*reference.expr {
+            Expr::Array(array) => array,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    array
+        .elems
+        .iter()
+        .map(|elem| match elem {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => Some(lit.clone()),
+            _ => None,
+        })
+        .collect()
+}
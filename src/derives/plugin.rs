@@ -0,0 +1,232 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Subcommands unknown to the derive: dispatch to `<bin>-<subcommand>`
+//! executables the way cargo and git do, and registration of subcommand
+//! types by other crates at link time.
+
+use proc_macro2;
+use proc_macro_error::abort;
+use syn::{self, spanned::Spanned};
+
+use super::{Attrs, VariantAttrs};
+
+/// The variant marked with `#[clap(plugin)]`, if any.
+pub fn find_plugin_variant<'a>(variants: &[VariantAttrs<'a>]) -> Option<&'a syn::Variant> {
+    let mut plugins = variants
+        .iter()
+        .filter(|(_, attrs, _)| attrs.plugin().is_some())
+        .map(|(variant, _, _)| *variant);
+    let plugin = plugins.next()?;
+
+    if let Some(second) = plugins.next() {
+        abort!(
+            second.span(),
+            "multiple plugin variants are not allowed, that's the second"
+        );
+    }
+
+    match plugin.fields {
+        syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 2 => {
+            Some(plugin)
+        }
+        _ => abort!(
+            plugin.span(),
+            "plugin variants must hold the executable and its arguments";
+            help = "use a variant like `External(PathBuf, Vec<OsString>)`"
+        ),
+    }
+}
+
+/// Whether the variant with the attributes `attrs` is a subcommand known at
+/// expansion time, not marked with `plugin` nor `registered`.
+pub fn is_builtin(attrs: &Attrs) -> bool {
+    attrs.plugin().is_none() && attrs.registered().is_none()
+}
+
+/// Generate the `__clap_find_plugin()` function, looking the executable
+/// `<bin>-<name>` up on `PATH`.
+pub fn gen_find_plugin_fn(parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    let prefix = format!("{}-", parent_attribute.cased_name().value());
+
+    quote! {
+        pub fn __clap_find_plugin(name: &str) -> Option<::std::path::PathBuf> {
+            let file = format!("{}{}{}", #prefix, name, ::std::env::consts::EXE_SUFFIX);
+            ::std::env::var_os("PATH").and_then(|paths| {
+                ::std::env::split_paths(&paths)
+                    .map(|dir| dir.join(&file))
+                    .find(|path| Self::__clap_is_executable(path))
+            })
+        }
+
+        #[cfg(unix)]
+        fn __clap_is_executable(path: &::std::path::Path) -> bool {
+            use ::std::os::unix::fs::PermissionsExt;
+            path.metadata()
+                .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        }
+
+        #[cfg(not(unix))]
+        fn __clap_is_executable(path: &::std::path::Path) -> bool {
+            path.is_file()
+        }
+    }
+}
+
+/// Generate the `from_subcommand` match arm building the plugin variant out
+/// of an unknown subcommand.
+///
+/// A subcommand matching no executable builds nothing, the checks run after
+/// parsing having failed with an unrecognized subcommand error already.
+pub fn gen_plugin_arm(name: &syn::Ident, variant: Option<&syn::Variant>) -> proc_macro2::TokenStream {
+    let variant_name = match variant {
+        Some(variant) => &variant.ident,
+        None => return quote!(),
+    };
+
+    quote! {
+        (external, Some(matches)) if !external.is_empty() => {
+            #name::__clap_find_plugin(external).map(|path| {
+                let args = matches
+                    .values_of_os("")
+                    .map(|values| values.map(::std::ffi::OsString::from).collect())
+                    .unwrap_or_else(Vec::new);
+                #name::#variant_name(path, args)
+            })
+        },
+    }
+}
+
+/// Generate the check arm failing on an unknown subcommand when no plugin
+/// executable matches it either.
+pub fn gen_plugin_check_arm(variant: Option<&syn::Variant>) -> proc_macro2::TokenStream {
+    if variant.is_none() {
+        return quote!();
+    }
+
+    quote! {
+        (external, Some(_))
+            if !external.is_empty()
+                && Self::__clap_from_subcommand_prefixed(matches.subcommand(), prefix).is_none() =>
+        {
+            return Err(::clap::Error::with_description(
+                &format!("no such subcommand: `{}`", external),
+                ::clap::ErrorKind::UnrecognizedSubcommand,
+            ));
+        }
+    }
+}
+
+/// The variant marked with `#[clap(registered)]`, if any.
+pub fn find_registered_variant<'a>(variants: &[VariantAttrs<'a>]) -> Option<&'a syn::Variant> {
+    let mut registered = variants
+        .iter()
+        .filter(|(_, attrs, _)| attrs.registered().is_some())
+        .map(|(variant, _, _)| *variant);
+    let variant = registered.next()?;
+
+    if let Some(second) = registered.next() {
+        abort!(
+            second.span(),
+            "multiple registered variants are not allowed, that's the second"
+        );
+    }
+
+    match variant.fields {
+        syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
+            Some(variant)
+        }
+        _ => abort!(
+            variant.span(),
+            "registered variants must hold exactly one value";
+            help = "use a variant like `Registered(Box<dyn Command>)`"
+        ),
+    }
+}
+
+fn registration_name(name: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("{}Registration", name), name.span())
+}
+
+/// Generate the `<Name>Registration` type other crates submit to
+/// `inventory` to add subcommands to `name`.
+pub fn gen_registration(
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    variant: Option<&syn::Variant>,
+) -> proc_macro2::TokenStream {
+    let ty = match variant.map(|variant| &variant.fields) {
+        Some(syn::Fields::Unnamed(syn::FieldsUnnamed { unnamed, .. })) => &unnamed[0].ty,
+        _ => return quote!(),
+    };
+    let registration = registration_name(name);
+    let doc = format!(
+        "A subcommand of `{}` registered by another crate with `inventory::submit!`.",
+        name
+    );
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #registration {
+            /// Name of the subcommand.
+            pub name: &'static str,
+            /// Adds the arguments of the subcommand, usually the
+            /// `augment_app` function of a derived type.
+            pub augment_app: fn(::clap::App<'static>) -> ::clap::App<'static>,
+            /// Builds the value held by the registered variant.
+            pub from_argmatches: fn(&::clap::ArgMatches) -> #ty,
+        }
+
+        ::inventory::collect!(#registration);
+    }
+}
+
+/// Generate the code adding the registered subcommands to `app_var`.
+pub fn gen_registered_subcommands(
+    name: &syn::Ident,
+    app_var: &syn::Ident,
+    variant: Option<&syn::Variant>,
+) -> proc_macro2::TokenStream {
+    if variant.is_none() {
+        return quote!();
+    }
+    let registration = registration_name(name);
+
+    quote! {
+        let #app_var = ::inventory::iter::<#registration>
+            .into_iter()
+            .fold(#app_var, |app, registration| {
+                app.subcommand((registration.augment_app)(::clap::App::new(registration.name)))
+            });
+    }
+}
+
+/// Generate the `from_subcommand` match arm building the registered variant.
+pub fn gen_registered_arm(name: &syn::Ident, variant: Option<&syn::Variant>) -> proc_macro2::TokenStream {
+    let variant_name = match variant {
+        Some(variant) => &variant.ident,
+        None => return quote!(),
+    };
+    let registration = registration_name(name);
+
+    quote! {
+        (sub_name, Some(matches))
+            if ::inventory::iter::<#registration>
+                .into_iter()
+                .any(|registration| registration.name == sub_name) =>
+        {
+            ::inventory::iter::<#registration>
+                .into_iter()
+                .find(|registration| registration.name == sub_name)
+                .map(|registration| #name::#variant_name((registration.from_argmatches)(matches)))
+        },
+    }
+}
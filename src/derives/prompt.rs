@@ -0,0 +1,762 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Interactive prompting for missing required arguments, and the wizard
+//! asking for every argument.
+
+use proc_macro2;
+use syn;
+
+use super::{
+    clap, completion, config, from_argmatches, plugin, sub_type, Attrs, Kind, Ty, VariantAttrs,
+};
+
+/// Generate the `__clap_prompt_args()` function of a struct, listing the
+/// `(name, prompt, secret)` triples of its required arguments, and the
+/// `__clap_wizard_args()` one, listing all its visible arguments, flattened
+/// ones included, along with their `_at()` counterparts listing those of the
+/// subcommand at a path. The arguments are named under the runtime `prefix`.
+///
+/// None without `#[clap(prompt)]`, which the subcommands and flattened
+/// structs of a type asking for it need as well.
+pub fn gen_prompt_args_for_struct(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_prompt() {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let prompt_args = gen_prompt_args(fields, parent_attribute, &prefix);
+    let prompt_args_at = gen_fields_args_at(fields, "__clap_prompt_args_at", &prefix);
+    let wizard_args = gen_wizard_args(fields, parent_attribute, &prefix);
+    let wizard_args_at = gen_fields_args_at(fields, "__clap_wizard_args_at", &prefix);
+    let wizard_arg = wizard_arg_type();
+
+    quote! {
+        pub fn __clap_prompt_args(#prefix: #prefix_type) -> Vec<(&'static str, &'static str, bool)> {
+            #propagated_casing
+            let prompt_args: Vec<Vec<(&'static str, &'static str, bool)>> =
+                vec![ #( #prompt_args ),* ];
+            prompt_args.into_iter().flatten().collect()
+        }
+
+        /// The arguments asked for by `parse_or_prompt` for the subcommand
+        /// at `path`.
+        pub fn __clap_prompt_args_at(
+            path: &[&str],
+            #prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str, bool)> {
+            if path.is_empty() {
+                return Self::__clap_prompt_args(#prefix);
+            }
+            #propagated_casing
+            let prompt_args: Vec<Vec<(&'static str, &'static str, bool)>> =
+                vec![ #( #prompt_args_at ),* ];
+            prompt_args.into_iter().flatten().collect()
+        }
+
+        /// The arguments asked for by `wizard`.
+        pub fn __clap_wizard_args(#prefix: #prefix_type) -> Vec<#wizard_arg> {
+            #propagated_casing
+            let wizard_args: Vec<Vec<#wizard_arg>> = vec![ #( #wizard_args ),* ];
+            wizard_args.into_iter().flatten().collect()
+        }
+
+        /// The arguments asked for by `wizard` for the subcommand at `path`.
+        pub fn __clap_wizard_args_at(path: &[&str], #prefix: #prefix_type) -> Vec<#wizard_arg> {
+            if path.is_empty() {
+                return Self::__clap_wizard_args(#prefix);
+            }
+            #propagated_casing
+            let wizard_args: Vec<Vec<#wizard_arg>> = vec![ #( #wizard_args_at ),* ];
+            wizard_args.into_iter().flatten().collect()
+        }
+    }
+}
+
+/// Expressions evaluating to the `__clap_prompt_args()` entries of the
+/// required arguments in `fields`, flattened ones included, named under the
+/// runtime `prefix`.
+fn gen_prompt_args(
+    fields: &[(&syn::Field, Attrs)],
+    _parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                        .unwrap_or_else(from_argmatches::no_prefix);
+                    Some(quote!( <#ty>::__clap_prompt_args(#flatten_prefix) ))
+                }
+                Kind::Arg(_) => {
+                    if attrs.is_required() {
+                        let name = attrs.cased_name();
+                        let prompt = attrs.help_text().unwrap_or_else(|| name.value());
+                        let secret = attrs.is_secret();
+                        Some(quote! {
+                            vec![(Self::__clap_prefixed(#prefix, #name), #prompt, #secret)]
+                        })
+                    } else {
+                        None
+                    }
+                }
+                Kind::Subcommand(_) | Kind::Skip(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// The type of the arguments asked for by `wizard`: `(switch, prompt,
+/// heading, kind, required, secret, default, possible values)`, the switch
+/// being empty for positional arguments and the kind one of `flag`, `value`
+/// and `values`.
+fn wizard_arg_type() -> proc_macro2::TokenStream {
+    quote! {
+        (
+            &'static str,
+            &'static str,
+            Option<&'static str>,
+            &'static str,
+            bool,
+            bool,
+            Option<&'static str>,
+            &'static [&'static str],
+        )
+    }
+}
+
+/// Expressions evaluating to the `__clap_wizard_args()` entries of the
+/// visible arguments in `fields`, flattened ones included, switched under the
+/// runtime `prefix`.
+fn gen_wizard_args(
+    fields: &[(&syn::Field, Attrs)],
+    parent_attribute: &Attrs,
+    prefix: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                        .unwrap_or_else(from_argmatches::no_prefix);
+                    Some(quote!( <#ty>::__clap_wizard_args(#flatten_prefix) ))
+                }
+                Kind::Arg(ty) if !attrs.has_method("hidden") => {
+                    let switch = match (attrs.long_name(), attrs.short_name()) {
+                        (Some(_), _) => {
+                            let attrs = attrs.clone().with_runtime_prefix(prefix, parent_attribute);
+                            let long = attrs.method_args("long");
+                            quote!( Some(Self::__clap_interned(format!("--{}", #long))) )
+                        }
+                        (None, Some(short)) => {
+                            let short = format!("-{}", short);
+                            quote!( if #prefix.0.is_empty() { Some(#short) } else { None } )
+                        }
+                        (None, None) => quote!(Some("")),
+                    };
+                    let name = attrs.cased_name();
+                    let prompt = attrs.help_text().unwrap_or_else(|| name.value());
+                    // `help_heading` is given either a string or an option
+                    let heading = match attrs.help_heading() {
+                        Some(heading) => quote!( Option::<&'static str>::from(#heading) ),
+                        None => quote!(None),
+                    };
+                    let kind = match **ty {
+                        _ if !completion::takes_value(&attrs, **ty) => "flag",
+                        Ty::Vec | Ty::OptionVec => "values",
+                        _ => "value",
+                    };
+                    let required = attrs.is_required();
+                    let secret = attrs.is_secret();
+                    let default = match attrs.find_lit("default_value") {
+                        Some(default) => quote!(Some(#default)),
+                        None => quote!(None),
+                    };
+                    let values = attrs.possible_values().unwrap_or_else(|| quote!(&[]));
+                    // short switches are dropped under a prefix, leaving
+                    // nothing to ask for
+                    Some(quote! {
+                        match #switch {
+                            Some(switch) => {
+                                let values: &'static [&'static str] = #values;
+                                vec![(switch, #prompt, #heading, #kind, #required, #secret, #default, values)]
+                            }
+                            None => Vec::new(),
+                        }
+                    })
+                }
+                Kind::Arg(_) | Kind::Subcommand(_) | Kind::Skip(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Expressions evaluating to the `<fn_name>(path)` entries, that is
+/// `__clap_prompt_args_at()` or `__clap_wizard_args_at()`, of the
+/// subcommands held by `fields`, directly or in flattened structs.
+fn gen_fields_args_at(
+    fields: &[(&syn::Field, Attrs)],
+    fn_name: &str,
+    prefix: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    let fn_name = syn::Ident::new(fn_name, proc_macro2::Span::call_site());
+    fields
+        .iter()
+        .filter_map(|(field, attrs)| {
+            match &*attrs.kind() {
+                Kind::Subcommand(ty) => {
+                    let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                        (Ty::Option, Some(sub_type)) => sub_type,
+                        _ => &field.ty,
+                    };
+                    Some(quote!( <#subcmd_type>::#fn_name(path, Self::__clap_casing_prefix(#prefix)) ))
+                }
+                Kind::FlattenStruct => {
+                    let ty = &field.ty;
+                    let flatten_prefix = config::gen_flatten_prefix(Some(prefix), &attrs)
+                        .unwrap_or_else(from_argmatches::no_prefix);
+                    Some(quote!( <#ty>::#fn_name(path, #flatten_prefix) ))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Generate the `__clap_prompt_args()` and `__clap_wizard_args()` functions
+/// of an enum, which has no arguments of its own, and their `_at()`
+/// counterparts, finding the arguments of the subcommand at a path among its
+/// variants, none without `#[clap(prompt)]`.
+pub fn gen_prompt_args_for_enum(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_prompt() {
+        return quote!();
+    }
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, parent_attribute);
+    let prompt_arg = quote!( (&'static str, &'static str, bool) );
+    let wizard_arg = wizard_arg_type();
+    let prompt_arms = gen_args_at_arms(
+        variants,
+        &prompt_arg,
+        "__clap_prompt_args_at",
+        gen_prompt_args,
+    );
+    let wizard_arms = gen_args_at_arms(
+        variants,
+        &wizard_arg,
+        "__clap_wizard_args_at",
+        gen_wizard_args,
+    );
+
+    quote! {
+        pub fn __clap_prompt_args(_prefix: #prefix_type) -> Vec<#prompt_arg> {
+            Vec::new()
+        }
+
+        pub fn __clap_prompt_args_at(path: &[&str], #prefix: #prefix_type) -> Vec<#prompt_arg> {
+            #propagated_casing
+            match path.first() {
+                #( #prompt_arms )*
+                _ => Vec::new(),
+            }
+        }
+
+        pub fn __clap_wizard_args(_prefix: #prefix_type) -> Vec<#wizard_arg> {
+            Vec::new()
+        }
+
+        pub fn __clap_wizard_args_at(path: &[&str], #prefix: #prefix_type) -> Vec<#wizard_arg> {
+            #propagated_casing
+            match path.first() {
+                #( #wizard_arms )*
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+/// The match arms of `<fn_name>(path, prefix)` for the variants of an enum,
+/// a variant with named fields listing the `args` of these at the end of the
+/// path, entries of type `arg`.
+fn gen_args_at_arms(
+    variants: &[VariantAttrs],
+    arg: &proc_macro2::TokenStream,
+    fn_name: &str,
+    args: fn(&[(&syn::Field, Attrs)], &Attrs, &syn::Ident) -> Vec<proc_macro2::TokenStream>,
+) -> Vec<proc_macro2::TokenStream> {
+    use syn::Fields::*;
+
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let fn_ident = syn::Ident::new(fn_name, proc_macro2::Span::call_site());
+    variants
+        .iter()
+        .filter(|(_, attrs, _)| plugin::is_builtin(attrs))
+        .map(|(variant, attrs, fields)| {
+            let name = attrs.cased_name();
+            let found = match (&variant.fields, fields) {
+                (Named(_), Some(fields)) => {
+                    let propagated_casing = from_argmatches::gen_propagated_casing(&prefix, attrs);
+                    let own = args(fields, attrs, &prefix);
+                    let nested = gen_fields_args_at(fields, fn_name, &prefix);
+                    quote! {{
+                        let #prefix = Self::__clap_casing_prefix(#prefix);
+                        #propagated_casing
+                        if path.len() == 1 {
+                            let args: Vec<Vec<#arg>> = vec![ #( #own ),* ];
+                            args.into_iter().flatten().collect()
+                        } else {
+                            let path = &path[1..];
+                            let args: Vec<Vec<#arg>> = vec![ #( #nested ),* ];
+                            args.into_iter().flatten().collect()
+                        }
+                    }}
+                }
+                (Unnamed(syn::FieldsUnnamed { unnamed, .. }), _) if unnamed.len() == 1 => {
+                    let ty = &unnamed[0].ty;
+                    quote!( <#ty>::#fn_ident(&path[1..], Self::__clap_casing_prefix(#prefix)) )
+                }
+                _ => quote!(Vec::new()),
+            };
+            quote!( Some(&#name) => #found, )
+        })
+        .collect()
+}
+
+/// Generate the prompt and wizard functions of an `untagged` enum, which has
+/// neither arguments of its own nor subcommands, none without
+/// `#[clap(prompt)]`.
+pub fn gen_prompt_args_for_untagged(parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_prompt() {
+        return quote!();
+    }
+
+    let prefix_type = from_argmatches::prefix_type();
+    let wizard_arg = wizard_arg_type();
+
+    quote! {
+        pub fn __clap_prompt_args(_prefix: #prefix_type) -> Vec<(&'static str, &'static str, bool)> {
+            Vec::new()
+        }
+
+        pub fn __clap_prompt_args_at(
+            _path: &[&str],
+            _prefix: #prefix_type,
+        ) -> Vec<(&'static str, &'static str, bool)> {
+            Vec::new()
+        }
+
+        pub fn __clap_wizard_args(_prefix: #prefix_type) -> Vec<#wizard_arg> {
+            Vec::new()
+        }
+
+        pub fn __clap_wizard_args_at(_path: &[&str], _prefix: #prefix_type) -> Vec<#wizard_arg> {
+            Vec::new()
+        }
+    }
+}
+
+/// Generate the `parse_or_prompt` family of functions, for the types asking
+/// for them with `#[clap(prompt)]`, which needs the `prompt` feature.
+///
+/// Missing required arguments, those of the subcommands given included, are
+/// asked for on the terminal and used as defaults of the real parse, so every
+/// other rule of the `App` still applies to them. The `wizard` ones ask for
+/// every argument instead, see `gen_wizard_fns()`.
+pub fn gen_public_prompt_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_prompt() {
+        return quote!();
+    }
+
+    let exit = clap::gen_exit(name, parent_attribute);
+
+    quote! {
+        /// Parse the command line, asking on the terminal for the required
+        /// arguments it lacks.
+        #[allow(unreachable_pub)]
+        pub fn parse_or_prompt() -> #name {
+            #name::try_parse_or_prompt_from(::std::env::args_os())
+                .unwrap_or_else(|e| #exit)
+        }
+        /// Parse `itr`, asking on the terminal for the required arguments it
+        /// lacks.
+        ///
+        /// When stdin is not a terminal, this behaves like `try_parse_from`.
+        #[allow(unreachable_pub)]
+        pub fn try_parse_or_prompt_from<I, T>(itr: I) -> ::std::result::Result<#name, ::clap::Error>
+        where
+            I: ::std::iter::IntoIterator<Item = T>,
+            T: Into<::std::ffi::OsString> + Clone {
+            use ::std::io::Write;
+
+            #name::__clap_parse_or_prompt_with(
+                itr.into_iter().map(Into::into).collect(),
+                ::atty::is(::atty::Stream::Stdin),
+                |prompt, secret| {
+                    if secret {
+                        ::rpassword::read_password_from_tty(Some(prompt))
+                    } else {
+                        eprint!("{}", prompt);
+                        ::std::io::stderr().flush().ok();
+                        let mut line = String::new();
+                        ::std::io::stdin().read_line(&mut line).map(|_| {
+                            let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+                            line.truncate(len);
+                            line
+                        })
+                    }
+                },
+            )
+        }
+
+        /// Ask on the terminal for every argument in turn, and parse the
+        /// answers, exiting on error.
+        #[allow(unreachable_pub)]
+        pub fn wizard() -> #name {
+            #name::try_wizard().unwrap_or_else(|e| #exit)
+        }
+        /// Ask on the terminal for every argument in turn, and parse the
+        /// answers.
+        #[allow(unreachable_pub)]
+        pub fn try_wizard() -> ::std::result::Result<#name, ::clap::Error> {
+            use ::std::io::Write;
+
+            let bin = ::std::env::args_os()
+                .next()
+                .map(|bin| bin.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            #name::__clap_wizard_with(bin, |prompt, secret| {
+                if secret {
+                    ::rpassword::read_password_from_tty(Some(prompt))
+                } else {
+                    eprint!("{}", prompt);
+                    ::std::io::stderr().flush().ok();
+                    let mut line = String::new();
+                    ::std::io::stdin().read_line(&mut line).map(|_| {
+                        let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+                        line.truncate(len);
+                        line
+                    })
+                }
+            })
+        }
+    }
+}
+
+/// Generate the hidden helpers of the functions of `gen_public_prompt_fns()`.
+pub fn gen_prompt_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    if !parent_attribute.has_prompt() {
+        return quote!();
+    }
+
+    // The answers outlive the `App` borrowing them, and with `zeroize` are
+    // wiped when dropped, early returns included.
+    let answer = if parent_attribute.has_zeroize() {
+        quote!( ::zeroize::Zeroizing::new(value) )
+    } else {
+        quote!(value)
+    };
+
+    let wizard_fns = gen_wizard_fns(name, parent_attribute);
+    let zeroize_args = clap::gen_zeroize_args(name, parent_attribute, quote!(&mut args));
+    let replace_args = clap::gen_replace_args(
+        name,
+        parent_attribute,
+        quote!(args),
+        quote!(#name::__clap_fold_long_case(expanded)),
+    );
+    let no_prefix = from_argmatches::no_prefix();
+
+    quote! {
+        /// Parse `args`, getting the required arguments it lacks from `ask`,
+        /// given their prompt and whether they're secret, when `interactive`.
+        pub fn __clap_parse_or_prompt_with<F>(
+            mut args: Vec<::std::ffi::OsString>,
+            interactive: bool,
+            ask: F,
+        ) -> ::std::result::Result<#name, ::clap::Error>
+        where
+            F: FnMut(&str, bool) -> ::std::io::Result<String> {
+            let parsed = #name::__clap_parse_or_prompt_args(&mut args, interactive, ask)
+                .map_err(|e| #name::__clap_redact(e, &args));
+            #zeroize_args
+            parsed
+        }
+
+        /// Same as `__clap_parse_or_prompt_with`, the errors being left
+        /// unredacted but for the secret answers, and `args` being replaced
+        /// by the command line as parsed, with its argument files expanded.
+        pub fn __clap_parse_or_prompt_args<F>(
+            args: &mut Vec<::std::ffi::OsString>,
+            interactive: bool,
+            mut ask: F,
+        ) -> ::std::result::Result<#name, ::clap::Error>
+        where
+            F: FnMut(&str, bool) -> ::std::io::Result<String> {
+            use ::clap::FromArgMatches;
+
+            let expanded = #name::__clap_expand_argfiles(args.clone())?;
+            #replace_args
+            let args: &Vec<::std::ffi::OsString> = args;
+
+            let parsed = #name::__clap_app_from(args)?
+                .try_get_matches_from(args)
+                .and_then(|matches| {
+                    #name::__clap_check_occurrences(&matches, #no_prefix)?;
+                    Ok(#name::from_argmatches(&matches))
+                });
+            match parsed {
+                Err(ref e)
+                    if e.kind == ::clap::ErrorKind::MissingRequiredArgument && interactive => {}
+                result => return result,
+            }
+
+            // Required arguments are relaxed at every level, the levels the
+            // command line goes through being asked for in turn.
+            fn at_path<'b, S: AsRef<str>>(
+                app: ::clap::App<'b>,
+                path: &[S],
+                f: &mut dyn FnMut(::clap::App<'b>) -> ::clap::App<'b>,
+            ) -> ::clap::App<'b> {
+                match path.split_first() {
+                    None => f(app),
+                    Some((first, rest)) => {
+                        app.mut_subcommand(first.as_ref(), |sub| at_path(sub, rest, &mut *f))
+                    }
+                }
+            }
+
+            let relaxed = ::std::iter::once(Vec::new())
+                .chain(#name::__clap_subcommand_paths())
+                .fold(#name::__clap_app_from(args)?, |app, path| {
+                    let prompt_args = #name::__clap_prompt_args_at(&path, #no_prefix);
+                    at_path(app, &path[..], &mut |app| {
+                        prompt_args.iter().fold(app, |app, &(id, _, _)| {
+                            app.mut_arg(id, |arg| arg.required(false))
+                        })
+                    })
+                });
+            let matches = relaxed.try_get_matches_from(args)?;
+
+            let mut answers = Vec::new();
+            let mut path: Vec<String> = Vec::new();
+            let mut level = Some(&matches);
+            while let Some(matches) = level {
+                let at: Vec<&str> = path.iter().map(String::as_str).collect();
+                for &(id, prompt, secret) in &#name::__clap_prompt_args_at(&at, #no_prefix) {
+                    if matches.is_present(id) {
+                        continue;
+                    }
+
+                    let value = ask(&format!("{}: ", prompt), secret).map_err(|e| {
+                        ::clap::Error::with_description(&e.to_string(), ::clap::ErrorKind::Io)
+                    })?;
+                    answers.push((path.clone(), id, secret, #answer));
+                }
+                level = match matches.subcommand_name() {
+                    Some(sub) => {
+                        path.push(sub.to_string());
+                        matches.subcommand_matches(sub)
+                    }
+                    None => None,
+                };
+            }
+
+            // the `App` only borrows the answers, its lifetime being
+            // shortened to theirs
+            let app: ::clap::App<'_> = #name::__clap_app_from(args)?;
+            let app = answers.iter().fold(app, |app, (path, id, _, value)| {
+                let value: &str = value;
+                at_path(app, &path[..], &mut |app| {
+                    app.mut_arg(*id, |arg| arg.default_value(value))
+                })
+            });
+            let matches = app.try_get_matches_from(args).map_err(|e| {
+                let secrets: Vec<&str> = answers
+                    .iter()
+                    .filter(|answer| answer.2)
+                    .map(|answer| answer.3.as_str())
+                    .collect();
+                #name::__clap_redact_values(e, &secrets)
+            })?;
+            #name::__clap_check_occurrences(&matches, #no_prefix)?;
+            Ok(#name::from_argmatches(&matches))
+        }
+
+        #wizard_fns
+    }
+}
+
+/// Generate `__clap_wizard_with()`, behind `wizard`, asking for every
+/// argument in turn, the answers making up the command line which is parsed.
+///
+/// Arguments are asked for heading by heading, the way the help lists them,
+/// then the subcommand, if any, whose arguments are asked for in turn. An
+/// empty answer keeps the default, if any, and possible values are checked
+/// before going on. With `zeroize`, the answers are wiped once parsed.
+fn gen_wizard_fns(name: &syn::Ident, parent_attribute: &Attrs) -> proc_macro2::TokenStream {
+    let no_prefix = from_argmatches::no_prefix();
+    let keep = if parent_attribute.has_zeroize() {
+        quote!( ::zeroize::Zeroizing::new(arg) )
+    } else {
+        quote!(arg)
+    };
+
+    quote! {
+        /// Get every argument from `ask`, given their prompt and whether
+        /// they're secret, and parse the answers after `bin`.
+        pub fn __clap_wizard_with<F>(
+            bin: String,
+            mut ask: F,
+        ) -> ::std::result::Result<#name, ::clap::Error>
+        where
+            F: FnMut(&str, bool) -> ::std::io::Result<String> {
+            let keep = |arg: String| #keep;
+            let mut ask = |prompt: &str, secret: bool| {
+                ask(prompt, secret).map(keep).map_err(|e| {
+                    ::clap::Error::with_description(&e.to_string(), ::clap::ErrorKind::Io)
+                })
+            };
+            let heading_of = |heading: Option<&'static str>, switch: &str, kind: &str| {
+                match (heading, switch.is_empty(), kind) {
+                    (Some(heading), _, _) => heading,
+                    (None, true, _) => "ARGS",
+                    (None, false, "flag") => "FLAGS",
+                    (None, false, _) => "OPTIONS",
+                }
+            };
+            let subcommand_paths = #name::__clap_subcommand_paths();
+
+            let mut args = vec![keep(bin)];
+            let mut path: Vec<&'static str> = Vec::new();
+            loop {
+                let wizard_args = #name::__clap_wizard_args_at(&path, #no_prefix);
+                let mut headings = vec!["ARGS", "FLAGS", "OPTIONS"];
+                for arg in &wizard_args {
+                    let heading = heading_of(arg.2, arg.0, arg.3);
+                    if !headings.contains(&heading) {
+                        headings.push(heading);
+                    }
+                }
+
+                let mut positionals = Vec::new();
+                for heading in headings {
+                    let in_heading: Vec<_> = wizard_args
+                        .iter()
+                        .filter(|arg| heading_of(arg.2, arg.0, arg.3) == heading)
+                        .collect();
+                    if in_heading.is_empty() {
+                        continue;
+                    }
+
+                    eprintln!("{}:", heading);
+                    for &&(switch, prompt, _, kind, required, secret, default, values) in &in_heading {
+                        if kind == "flag" {
+                            let answer = ask(&format!("{}? [y/N] ", prompt), false)?;
+                            let answer = answer.trim().to_lowercase();
+                            if answer == "y" || answer == "yes" {
+                                args.push(keep(switch.to_string()));
+                            }
+                            continue;
+                        }
+
+                        let mut hint = String::new();
+                        if !values.is_empty() {
+                            hint.push_str(&format!(" ({})", values.join("/")));
+                        }
+                        if let Some(default) = default {
+                            hint.push_str(&format!(" [{}]", default));
+                        }
+                        let mut answers = Vec::new();
+                        loop {
+                            let answer = ask(&format!("{}{}: ", prompt, hint), secret)?;
+                            if answer.is_empty() {
+                                if required && default.is_none() && answers.is_empty() {
+                                    eprintln!("a value is required");
+                                    continue;
+                                }
+                                break;
+                            }
+                            if !values.is_empty() && !values.contains(&answer.as_str()) {
+                                eprintln!("expected one of: {}", values.join(", "));
+                                continue;
+                            }
+                            answers.push(answer);
+                            if kind != "values" {
+                                break;
+                            }
+                        }
+
+                        for answer in answers {
+                            if switch.is_empty() {
+                                positionals.push(answer);
+                            } else {
+                                args.push(keep(format!("{}={}", switch, answer.as_str())));
+                            }
+                        }
+                    }
+                }
+
+                let choices: Vec<&'static str> = subcommand_paths
+                    .iter()
+                    .filter(|sub| sub.len() == path.len() + 1 && sub.starts_with(&path))
+                    .map(|sub| sub[path.len()])
+                    .collect();
+                let choice = if choices.is_empty() {
+                    None
+                } else {
+                    loop {
+                        let answer =
+                            ask(&format!("Subcommand ({}): ", choices.join("/")), false)?;
+                        if answer.is_empty() {
+                            break None;
+                        }
+                        match choices.iter().find(|choice| **choice == answer.as_str()) {
+                            Some(choice) => break Some(*choice),
+                            None => eprintln!("expected one of: {}", choices.join(", ")),
+                        }
+                    }
+                };
+
+                match choice {
+                    // positionals may start with `-` once no subcommand follows
+                    Some(choice) => {
+                        args.extend(positionals);
+                        args.push(keep(choice.to_string()));
+                        path.push(choice);
+                    }
+                    None => {
+                        if !positionals.is_empty() {
+                            args.push(keep("--".to_string()));
+                            args.extend(positionals);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            #name::try_parse_from(args.iter().map(|arg| -> &str { arg }))
+        }
+    }
+}
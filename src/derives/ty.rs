@@ -2,9 +2,10 @@
 
 use super::spanned::Sp;
 
+use quote::ToTokens;
 use syn::{
-    spanned::Spanned, GenericArgument, Path, PathArguments, PathArguments::AngleBracketed,
-    PathSegment, Type, TypePath,
+    spanned::Spanned, Expr, ExprLit, GenericArgument, Lit, Path, PathArguments,
+    PathArguments::AngleBracketed, PathSegment, Type, TypePath,
 };
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -44,6 +45,123 @@ pub fn sub_type(ty: &syn::Type) -> Option<&syn::Type> {
     subty_if(ty, |_| true)
 }
 
+/// The path of `ty`, wrapped in `Option`s and `Vec`s or not, when it is
+/// spelled out from one of the `log` or `tracing` crates.
+fn log_path(mut ty: &syn::Type) -> Option<&syn::Path> {
+    while let Some(subty) = subty_if_name(ty, "Option").or_else(|| subty_if_name(ty, "Vec")) {
+        ty = subty;
+    }
+
+    let path = match ty {
+        Type::Path(TypePath { qself: None, path }) if path.segments.len() > 1 => path,
+        _ => return None,
+    };
+    let krate = &path.segments[0].ident;
+    if ["log", "tracing", "tracing_core", "tracing_subscriber"]
+        .iter()
+        .any(|name| krate == name)
+    {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// The possible values of `ty` when it is a level or level filter of `log` or
+/// `tracing`, named by its full path so that types of the same name aren't
+/// taken for one.
+pub fn log_levels(ty: &syn::Type) -> Option<&'static [&'static str]> {
+    let segment = log_path(ty)?.segments.last()?;
+    if let PathArguments::None = segment.arguments {
+        if segment.ident == "LevelFilter" {
+            return Some(&["off", "error", "warn", "info", "debug", "trace"]);
+        } else if segment.ident == "Level" {
+            return Some(&["error", "warn", "info", "debug", "trace"]);
+        }
+    }
+    None
+}
+
+/// Whether `ty` is the `EnvFilter` of `tracing_subscriber`, a filter string
+/// like `RUST_LOG` holds.
+pub fn is_log_filter(ty: &syn::Type) -> bool {
+    match log_path(ty).and_then(|path| path.segments.last()) {
+        Some(segment) => match segment.arguments {
+            PathArguments::None => segment.ident == "EnvFilter",
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Whether `ty` is one of the primitive integer types, 128-bit and
+/// pointer-sized ones included.
+pub fn is_integer_ty(ty: &syn::Type) -> bool {
+    [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ]
+    .iter()
+    .any(|name| is_simple_ty(ty, name))
+}
+
+/// The primitive integer type wrapped by `ty` when it is one of the
+/// `NonZero*` types of `std::num`, with its path or not.
+pub fn nonzero_integer_ty(ty: &syn::Type) -> Option<&'static str> {
+    let segment = match ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    if let PathArguments::None = segment.arguments {
+        [
+            ("NonZeroI8", "i8"),
+            ("NonZeroI16", "i16"),
+            ("NonZeroI32", "i32"),
+            ("NonZeroI64", "i64"),
+            ("NonZeroI128", "i128"),
+            ("NonZeroIsize", "isize"),
+            ("NonZeroU8", "u8"),
+            ("NonZeroU16", "u16"),
+            ("NonZeroU32", "u32"),
+            ("NonZeroU64", "u64"),
+            ("NonZeroU128", "u128"),
+            ("NonZeroUsize", "usize"),
+        ]
+        .iter()
+        .find(|(nonzero, _)| segment.ident == nonzero)
+        .map(|(_, integer)| *integer)
+    } else {
+        None
+    }
+}
+
+/// The type of the values and their count when `ty` holds a fixed number of
+/// values of one type: a tuple like `(u32, u32)` or an array like `[u32; 2]`.
+pub fn fixed_values(ty: &syn::Type) -> Option<(&syn::Type, usize)> {
+    match ty {
+        Type::Paren(paren) => fixed_values(&paren.elem),
+        Type::Tuple(tuple) if tuple.elems.len() > 1 => {
+            let first = &tuple.elems[0];
+            let first_tokens = first.to_token_stream().to_string();
+            if tuple
+                .elems
+                .iter()
+                .all(|elem| elem.to_token_stream().to_string() == first_tokens)
+            {
+                Some((first, tuple.elems.len()))
+            } else {
+                None
+            }
+        }
+        Type::Array(array) => match &array.len {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }) => Some((&array.elem, len.base10_parse().ok()?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn only_last_segment(ty: &syn::Type) -> Option<&PathSegment> {
     match ty {
         Type::Path(TypePath {
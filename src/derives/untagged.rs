@@ -0,0 +1,293 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `untagged` enums, whose variants are alternative sets of arguments
+//! flattened into the parent instead of subcommands.
+//!
+//! Each variant with fields gets a group of its arguments, conflicting with
+//! the groups of the other variants. The arguments required by a variant are
+//! only required once one of its arguments is given, and, unless a unit
+//! variant stands for none of them being given, one of the variants is.
+
+use proc_macro2;
+use proc_macro_error::abort;
+use syn::{self, punctuated, spanned::Spanned, token};
+
+use super::{clap, config, from_argmatches, Attrs, Kind, VariantAttrs};
+
+/// The variants of the enum, with their attributes and their fields, if any.
+pub fn variants_with_attrs<'a>(
+    variants: &'a punctuated::Punctuated<syn::Variant, token::Comma>,
+    parent_attribute: &Attrs,
+) -> Vec<VariantAttrs<'a>> {
+    if variants.is_empty() {
+        abort!(
+            parent_attribute.untagged().unwrap().span(),
+            "an `untagged` enum needs at least one variant"
+        );
+    }
+
+    let variants = Attrs::from_variants(variants, parent_attribute);
+    for (variant, _, fields) in &variants {
+        match (&variant.fields, fields) {
+            (syn::Fields::Unnamed(_), _) => abort!(
+                variant.span(),
+                "the variants of an `untagged` enum need named fields";
+                help = "use a unit variant for none of the arguments being given"
+            ),
+            (_, Some(fields)) => check_fields(fields),
+            _ => (),
+        }
+    }
+    variants
+}
+
+/// Abort on the fields which can't be told apart between variants.
+fn check_fields(fields: &[(&syn::Field, Attrs)]) {
+    for (field, attrs) in fields {
+        match &*attrs.kind() {
+            Kind::Subcommand(_) => abort!(
+                field.span(),
+                "the variants of an `untagged` enum can't have subcommands"
+            ),
+            Kind::Arg(_) if attrs.is_positional() => abort!(
+                field.span(),
+                "the arguments of an `untagged` enum need `long` or `short`";
+                help = "positional arguments can't tell which variant was given"
+            ),
+            _ => (),
+        }
+    }
+}
+
+/// The name of the group of the arguments of a variant.
+fn group_name(parent_attribute: &Attrs, variant_attrs: &Attrs) -> syn::LitStr {
+    let name = format!(
+        "{}-{}",
+        parent_attribute.cased_name().value(),
+        variant_attrs.cased_name().value()
+    );
+    syn::LitStr::new(&name, proc_macro2::Span::call_site())
+}
+
+/// Generate the `augment_app()` functions of an `untagged` enum, adding the
+/// arguments of all its variants.
+pub fn gen_augment_app_fn(
+    variants: &[VariantAttrs],
+    parent_attribute: &Attrs,
+) -> proc_macro2::TokenStream {
+    let app_var = syn::Ident::new("app", proc_macro2::Span::call_site());
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let (prefix_type, no_prefix) = (from_argmatches::prefix_type(), from_argmatches::no_prefix());
+    let has_unit = variants.iter().any(|(_, _, fields)| fields.is_none());
+
+    let with_fields: Vec<_> = variants
+        .iter()
+        .filter_map(|(_, attrs, fields)| fields.as_ref().map(|fields| (attrs, fields)))
+        .collect();
+    let groups: Vec<_> = with_fields
+        .iter()
+        .map(|(attrs, _)| group_name(parent_attribute, attrs))
+        .collect();
+
+    let augmentations = with_fields.iter().map(|(attrs, fields)| {
+        let augmentation = clap::gen_app_augmentation(fields, &app_var, attrs, Some(&prefix));
+        quote!( let #app_var = #augmentation; )
+    });
+
+    let variant_groups = with_fields.iter().enumerate().map(|(i, (attrs, fields))| {
+        let group = &groups[i];
+        let others = groups
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, other)| other);
+        let ids = config::gen_arg_ids(fields, &prefix);
+        let required: Vec<_> = fields
+            .iter()
+            .filter_map(|(_, field_attrs)| {
+                if field_attrs.is_required() {
+                    Some(field_attrs.cased_name())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        quote! {
+            let #app_var = #app_var.group(
+                ::clap::ArgGroup::with_name(Self::__clap_prefixed(#prefix, #group))
+                    .multiple(true)
+                    .args(&#ids)
+                    .conflicts_with_all(&[ #( Self::__clap_prefixed(#prefix, #others) ),* ])
+                    .requires_all(&[ #( Self::__clap_prefixed(#prefix, #required) ),* ])
+            );
+            #(
+                let #app_var = #app_var.mut_arg(
+                    Self::__clap_prefixed(#prefix, #required),
+                    |arg| arg.required(false),
+                );
+            )*
+        }
+    });
+
+    let one_required = if has_unit || groups.is_empty() {
+        quote!()
+    } else {
+        let name = parent_attribute.cased_name();
+        quote! {
+            let #app_var = #app_var.group(
+                ::clap::ArgGroup::with_name(Self::__clap_prefixed(#prefix, #name))
+                    .multiple(true)
+                    .required(true)
+                    .args(&Self::__clap_arg_ids(#prefix))
+            );
+        }
+    };
+
+    quote! {
+        pub fn augment_app<'b>(
+            #app_var: ::clap::App<'b>
+        ) -> ::clap::App<'b> {
+            Self::__clap_augment_app_with(#app_var, None)
+        }
+        /// Same as `augment_app`, `words` being unused.
+        pub fn __clap_augment_app_with<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+        ) -> ::clap::App<'b> {
+            Self::__clap_augment_app_prefixed(#app_var, words, #no_prefix)
+        }
+        /// Same as `__clap_augment_app_with`, the ids, long flags and
+        /// environment variables of the arguments being prefixed with
+        /// `prefix` unless it is empty.
+        pub fn __clap_augment_app_prefixed<'b>(
+            #app_var: ::clap::App<'b>,
+            words: Option<&[String]>,
+            #prefix: #prefix_type,
+        ) -> ::clap::App<'b> {
+            let #prefix = Self::__clap_localizing_prefix(#prefix);
+            #( #augmentations )*
+            #( #variant_groups )*
+            #one_required
+            #app_var
+        }
+    }
+}
+
+/// Generate the `__clap_arg_ids()` function of an `untagged` enum, listing
+/// the ids of the arguments of all its variants.
+pub fn gen_arg_ids_fn(variants: &[VariantAttrs]) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+    let prefix_type = from_argmatches::prefix_type();
+    let ids = variants
+        .iter()
+        .filter_map(|(_, _, fields)| {
+            fields
+                .as_ref()
+                .map(|fields| config::gen_arg_ids(fields, &prefix))
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        pub fn __clap_arg_ids(#prefix: #prefix_type) -> Vec<&'static str> {
+            let ids: Vec<Vec<&'static str>> = vec![ #( #ids ),* ];
+            ids.into_iter().flatten().collect()
+        }
+    }
+}
+
+/// Generate the `FromArgMatches` implementation of an `untagged` enum,
+/// building the first variant one of whose arguments was given.
+///
+/// When none was, that is the unit variant, if any, or else the first one.
+pub fn gen_from_argmatches_impl(
+    name: &syn::Ident,
+    variants: &[VariantAttrs],
+) -> proc_macro2::TokenStream {
+    let prefix = syn::Ident::new("prefix", proc_macro2::Span::call_site());
+
+    let build = |variant: &syn::Variant, attrs: &Attrs, fields: Option<&[(&syn::Field, Attrs)]>| {
+        let variant_name = &variant.ident;
+        match fields {
+            Some(fields) => {
+                let constructor = from_argmatches::gen_constructor(fields, Some(&prefix));
+                quote!( #name::#variant_name #constructor )
+            }
+            None => quote!( #name::#variant_name ),
+        }
+    };
+
+    let given = variants.iter().filter_map(|(variant, attrs, fields)| {
+        let fields = fields.as_ref()?;
+        let ids = config::gen_arg_ids(fields, &prefix);
+        let value = build(variant, attrs, Some(fields));
+        Some(quote! {
+            let ids: Vec<&'static str> = #ids;
+            if ids.into_iter().any(|id| matches.occurrences_of(id) > 0) {
+                return Ok(#value);
+            }
+        })
+    });
+    let fallback = {
+        let (variant, attrs, fields) = variants
+            .iter()
+            .find(|(_, _, fields)| fields.is_none())
+            .unwrap_or(&variants[0]);
+        build(variant, attrs, fields.as_ref().map(Vec::as_slice))
+    };
+    let prefixed_fns = from_argmatches::gen_prefixed_fns();
+    let error_fns = from_argmatches::gen_error_fns();
+    let infallible_fn = from_argmatches::gen_infallible_from_argmatches_fn();
+    let (prefix_type, no_prefix) = (from_argmatches::prefix_type(), from_argmatches::no_prefix());
+
+    quote! {
+        impl ::clap::FromArgMatches for #name {
+            fn from_argmatches(matches: &::clap::ArgMatches) -> Self {
+                Self::__clap_from_argmatches_prefixed(matches, #no_prefix)
+            }
+        }
+
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        impl #name {
+            /// Same as `TryFrom<&ArgMatches>`, for arguments flattened with
+            /// `prefix`.
+            #[allow(unreachable_code)]
+            pub fn __clap_try_from_argmatches_prefixed(
+                matches: &::clap::ArgMatches,
+                #prefix: #prefix_type,
+            ) -> ::std::result::Result<Self, ::clap::Error> {
+                #( #given )*
+                Ok(#fallback)
+            }
+
+            #infallible_fn
+
+            /// An `untagged` enum is never a subcommand.
+            pub fn from_subcommand<'b>(
+                _sub: (&'b str, Option<&'b ::clap::ArgMatches>)
+            ) -> Option<Self> {
+                None
+            }
+
+            #prefixed_fns
+
+            #error_fns
+        }
+
+        impl From<::clap::ArgMatches> for #name {
+            fn from(m: ::clap::ArgMatches) -> Self {
+                use ::clap::FromArgMatches;
+                <Self as ::clap::FromArgMatches>::from_argmatches(&m)
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use clap::Clap;
+
+#[test]
+fn set_true() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, long, action = SetTrue)]
+        verbose: bool,
+    }
+
+    assert_eq!(Opt { verbose: false }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { verbose: true }, Opt::parse_from(&["test", "-v"]));
+    assert!(Opt::try_parse_from(&["test", "-v", "-v"]).is_err());
+}
+
+#[test]
+fn set_true_on_other_types() {
+    #[derive(PartialEq, Debug)]
+    struct Enabled(bool);
+
+    impl From<bool> for Enabled {
+        fn from(b: bool) -> Self {
+            Enabled(b)
+        }
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, action = SetTrue)]
+        cache: Enabled,
+    }
+
+    assert_eq!(
+        Opt {
+            cache: Enabled(false)
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            cache: Enabled(true)
+        },
+        Opt::parse_from(&["test", "--cache"])
+    );
+}
+
+#[test]
+fn set() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, action = Set)]
+        color: bool,
+
+        #[clap(long, action = Set)]
+        jobs: Option<u32>,
+    }
+
+    assert_eq!(
+        Opt {
+            color: true,
+            jobs: None
+        },
+        Opt::parse_from(&["test", "--color", "true"])
+    );
+    assert_eq!(
+        Opt {
+            color: false,
+            jobs: Some(4)
+        },
+        Opt::parse_from(&["test", "--color", "false", "--jobs", "4"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--color"]).is_err());
+}
+
+#[test]
+fn count() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, action = Count)]
+        verbose: u8,
+    }
+
+    assert_eq!(Opt { verbose: 0 }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { verbose: 3 }, Opt::parse_from(&["test", "-vvv"]));
+}
+
+#[test]
+fn append() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, long, action = Append)]
+        include: Vec<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            include: vec!["a".into(), "b".into()]
+        },
+        Opt::parse_from(&["test", "-i", "a", "--include", "b"])
+    );
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "arbitrary")]
+
+use std::path::PathBuf;
+
+use arbitrary::Unstructured;
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", arbitrary)]
+struct Opt {
+    #[clap(short, long, parse(from_occurrences))]
+    verbose: u8,
+
+    #[clap(long, possible_values = &["json", "text"])]
+    format: Option<String>,
+
+    #[clap(short = "j", default_value = "1")]
+    jobs: u32,
+
+    input: String,
+
+    outputs: Vec<String>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(arbitrary)]
+enum Sub {
+    Fetch {
+        #[clap(long)]
+        all: bool,
+        remote: Option<String>,
+    },
+    Push,
+}
+
+fn data(seed: u32) -> Vec<u8> {
+    (0..256u32)
+        .map(|i| (i.wrapping_mul(seed.wrapping_add(7)) >> 3) as u8)
+        .collect()
+}
+
+#[test]
+fn arbitrary_args_always_parse() {
+    for seed in 0..200 {
+        let data = data(seed);
+        let args = Opt::arbitrary_args(&mut Unstructured::new(&data)).unwrap();
+
+        if let Err(e) = Opt::try_parse_from(&args) {
+            panic!("{:?} failed to parse: {}", args, e);
+        }
+    }
+}
+
+#[test]
+fn arbitrary_subcommands_always_parse() {
+    for seed in 0..200 {
+        let data = data(seed);
+        let args = Sub::arbitrary_args(&mut Unstructured::new(&data)).unwrap();
+
+        if let Err(e) = Sub::try_parse_from(&args) {
+            panic!("{:?} failed to parse: {}", args, e);
+        }
+    }
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", arbitrary)]
+struct WithSub {
+    offset: i64,
+
+    #[clap(subcommand)]
+    cmd: Sub,
+}
+
+#[test]
+fn positionals_before_subcommands_always_parse() {
+    for seed in 0..200 {
+        let data = data(seed);
+        let args = match WithSub::arbitrary_args(&mut Unstructured::new(&data)) {
+            Ok(args) => args,
+            Err(arbitrary::Error::IncorrectFormat) => continue,
+            Err(e) => panic!("{}", e),
+        };
+
+        assert!(!args[1].starts_with('-'), "{:?}", args);
+        if let Err(e) = WithSub::try_parse_from(&args) {
+            panic!("{:?} failed to parse: {}", args, e);
+        }
+    }
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", arbitrary)]
+struct WithPaths {
+    #[clap(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    name: String,
+
+    #[clap(parse(from_os_str))]
+    files: Vec<PathBuf>,
+}
+
+#[test]
+fn optional_arguments_of_other_types_are_left_out() {
+    for seed in 0..200 {
+        let data = data(seed);
+        let args = WithPaths::arbitrary_args(&mut Unstructured::new(&data)).unwrap();
+
+        let opt = match WithPaths::try_parse_from(&args) {
+            Ok(opt) => opt,
+            Err(e) => panic!("{:?} failed to parse: {}", args, e),
+        };
+        assert_eq!(opt.config, None);
+        assert!(opt.files.is_empty());
+    }
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "mirror", arbitrary)]
+struct Mirror {
+    #[clap(flatten, prefix = "remote", rename_all = "snake")]
+    remote: Endpoint,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(arbitrary)]
+struct Endpoint {
+    #[clap(long)]
+    push_url: String,
+
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[test]
+fn flattened_arguments_are_prefixed() {
+    for seed in 0..200 {
+        let data = data(seed);
+        let args = Mirror::arbitrary_args(&mut Unstructured::new(&data)).unwrap();
+
+        assert!(args.iter().any(|arg| arg.starts_with("--remote-push_url=")));
+        if let Err(e) = Mirror::try_parse_from(&args) {
+            panic!("{:?} failed to parse: {}", args, e);
+        }
+    }
+}
@@ -1,54 +1,110 @@
-// // Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
-// // Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
-// // Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
-// //
-// // Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
-// // http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
-// // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
-// // option. This file may not be copied, modified, or distributed
-// // except according to those terms.
-// #[macro_use]
-// extern crate clap;
-// #[macro_use]
-// extern crate clap_derive;
-
-// use clap::{App, Arg};
-
-// #[derive(ArgEnum, Debug, PartialEq)]
-// enum ArgChoice {
-//     Foo,
-//     Bar,
-//     Baz,
-// }
-
-// #[test]
-// fn when_lowercase() {
-//     let matches = App::new(env!("CARGO_PKG_NAME"))
-//         .arg(
-//             Arg::with_name("arg")
-//                 .required(true)
-//                 .takes_value(true)
-//                 .possible_values(&ArgChoice::variants()),
-//         )
-//         .try_get_matches_from(vec!["", "foo"])
-//         .unwrap();
-//     let t = value_t!(matches.value_of("arg"), ArgChoice);
-//     assert!(t.is_ok());
-//     assert_eq!(t.unwrap(), ArgChoice::Foo);
-// }
-
-// #[test]
-// fn when_capitalized() {
-//     let matches = App::new(env!("CARGO_PKG_NAME"))
-//         .arg(
-//             Arg::with_name("arg")
-//                 .required(true)
-//                 .takes_value(true)
-//                 .possible_values(&ArgChoice::variants()),
-//         )
-//         .try_get_matches_from(vec!["", "Foo"])
-//         .unwrap();
-//     let t = value_t!(matches.value_of("arg"), ArgChoice);
-//     assert!(t.is_ok());
-//     assert_eq!(t.unwrap(), ArgChoice::Foo);
-// }
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>,
+// Kevin Knapp (@kbknapp) <kbknapp@gmail.com>, and
+// Andrew Hobden (@hoverbear) <andrew@hoverbear.org>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use clap::{ArgEnum, Clap};
+
+#[derive(ArgEnum, Debug, PartialEq)]
+enum ArgChoice {
+    Foo,
+    BarBaz,
+    #[clap(name = "qux")]
+    Quux,
+}
+
+#[test]
+fn variant_values() {
+    assert_eq!(ArgChoice::VARIANTS, &["foo", "bar-baz", "qux"]);
+    assert_eq!(ArgChoice::variants(), ["foo", "bar-baz", "qux"]);
+    assert_eq!(ArgChoice::BarBaz.value(), "bar-baz");
+    assert_eq!(ArgChoice::from_value("qux"), Ok(ArgChoice::Quux));
+    assert_eq!(
+        ArgChoice::from_value("quux"),
+        Err("valid values: foo, bar-baz, qux".to_string())
+    );
+}
+
+#[test]
+fn when_capitalized() {
+    assert_eq!(ArgChoice::from_value("Foo"), Ok(ArgChoice::Foo));
+    assert_eq!(ArgChoice::from_value("BAR-BAZ"), Ok(ArgChoice::BarBaz));
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, arg_enum)]
+        choice: ArgChoice,
+    }
+
+    assert_eq!(
+        Opt {
+            choice: ArgChoice::Quux
+        },
+        Opt::parse_from(&["test", "--choice", "Qux"])
+    );
+}
+
+#[test]
+fn arg_enum_field() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, arg_enum)]
+        choice: ArgChoice,
+        #[clap(long, arg_enum)]
+        others: Vec<ArgChoice>,
+    }
+
+    assert_eq!(
+        Opt {
+            choice: ArgChoice::BarBaz,
+            others: vec![ArgChoice::Foo, ArgChoice::Quux]
+        },
+        Opt::parse_from(&["test", "--choice", "bar-baz", "--others", "foo", "qux"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--choice", "quux"]).is_err());
+}
+
+#[derive(ArgEnum, Debug, PartialEq)]
+#[clap(impl_from_str, impl_display)]
+enum Format {
+    Json,
+    PlainText,
+}
+
+#[test]
+fn from_str_and_display() {
+    assert_eq!("plain-text".parse(), Ok(Format::PlainText));
+    assert!("yaml".parse::<Format>().is_err());
+    assert_eq!(Format::Json.to_string(), "json");
+}
+
+#[derive(ArgEnum, Debug, PartialEq)]
+#[clap(rename_all = "snake")]
+enum Level {
+    VeryLow,
+    High,
+}
+
+#[test]
+fn rename_all_is_independent_of_the_struct() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(rename_all = "screaming_snake")]
+    struct Opt {
+        #[clap(long, arg_enum)]
+        min_level: Level,
+    }
+
+    assert_eq!(Level::VARIANTS, &["very_low", "high"]);
+    assert_eq!(
+        Opt {
+            min_level: Level::VeryLow
+        },
+        Opt::parse_from(&["test", "--MIN_LEVEL", "very_low"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--MIN_LEVEL", "very-low"]).is_err());
+}
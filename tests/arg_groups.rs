@@ -0,0 +1,49 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(group(name = "input", args = ["file", "url"], multiple = true, required = true))]
+#[clap(group(name = "output", args = ["out", "print"], conflicts_with = "input-free"))]
+#[clap(group(name = "input-free", args = ["dry_run"], requires = "file"))]
+struct Opt {
+    #[clap(long)]
+    file: Option<String>,
+
+    #[clap(long)]
+    url: Option<String>,
+
+    #[clap(long)]
+    out: Option<String>,
+
+    #[clap(long)]
+    print: bool,
+
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[test]
+fn multiple_group_members() {
+    assert_eq!(
+        Opt {
+            file: Some("a".into()),
+            url: Some("b".into()),
+            out: None,
+            print: false,
+            dry_run: false,
+        },
+        Opt::try_parse_from(&["test", "--file", "a", "--url", "b"]).unwrap()
+    );
+}
+
+#[test]
+fn required_group() {
+    assert!(Opt::try_parse_from(&["test"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--url", "b"]).is_ok());
+}
+
+#[test]
+fn group_requires_and_conflicts() {
+    assert!(Opt::try_parse_from(&["test", "--url", "b", "--dry-run"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--file", "a", "--dry-run"]).is_ok());
+    assert!(Opt::try_parse_from(&["test", "--file", "a", "--print", "--dry-run"]).is_err());
+}
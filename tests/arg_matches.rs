@@ -0,0 +1,43 @@
+use clap::{ArgMatches, Clap};
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(short, long)]
+    verbose: bool,
+
+    #[clap(long)]
+    file: Vec<String>,
+
+    #[clap(arg_matches)]
+    matches: ArgMatches,
+}
+
+#[test]
+fn field_holds_the_matches() {
+    let opt = Opt::parse_from(&["test", "--file", "a", "-v", "--file", "b"]);
+    assert!(opt.verbose);
+    assert_eq!(opt.file, vec!["a", "b"]);
+    assert_eq!(opt.matches.occurrences_of("file"), 2);
+    assert_eq!(opt.matches.index_of("verbose"), Some(3));
+}
+
+#[derive(Clap, Debug)]
+struct Outer {
+    #[clap(long)]
+    name: Option<String>,
+
+    #[clap(flatten)]
+    inner: Inner,
+}
+
+#[derive(Clap, Debug)]
+struct Inner {
+    #[clap(arg_matches)]
+    matches: ArgMatches,
+}
+
+#[test]
+fn flattened_field_holds_all_the_matches() {
+    let opt = Outer::parse_from(&["test", "--name", "x"]);
+    assert_eq!(opt.inner.matches.value_of("name"), Some("x"));
+}
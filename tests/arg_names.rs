@@ -0,0 +1,77 @@
+use clap::{Clap, IntoApp};
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(rename_all = "kebab", arg_ids)]
+struct Opt {
+    #[clap(long)]
+    output_file: String,
+
+    #[clap(long, name = "lvl")]
+    level: u8,
+
+    #[clap(long)]
+    r#type: Option<String>,
+
+    #[clap(flatten)]
+    common: Common,
+
+    #[clap(flatten, prefix = "remote")]
+    remote: Common,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(arg_ids = "common_ids")]
+struct Common {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(arg_ids)]
+enum Cmd {
+    Add {
+        #[clap(long)]
+        dry_run: bool,
+    },
+    Move {
+        #[clap(long = "to")]
+        target: String,
+    },
+}
+
+#[test]
+fn constants_hold_the_cased_names() {
+    assert_eq!(opt_args::ARG_OUTPUT_FILE, "output-file");
+    assert_eq!(opt_args::ARG_LEVEL, "lvl");
+    assert_eq!(opt_args::ARG_TYPE, "type");
+    assert_eq!(common_ids::ARG_VERBOSE, "verbose");
+}
+
+#[test]
+fn flattened_ids_get_their_prefix() {
+    assert_eq!(opt_args::common(common_ids::ARG_VERBOSE), "verbose");
+    assert_eq!(opt_args::remote(common_ids::ARG_VERBOSE), "remote-verbose");
+}
+
+#[test]
+fn variants_have_their_own_module() {
+    assert_eq!(cmd_args::add::ARG_DRY_RUN, "dry-run");
+    assert_eq!(cmd_args::r#move::ARG_TARGET, "target");
+}
+
+#[test]
+fn constants_look_up_matches() {
+    let matches = Opt::into_app().get_matches_from(&[
+        "test",
+        "--output-file",
+        "out.txt",
+        "--lvl",
+        "3",
+        "--verbose",
+        "--remote-verbose",
+    ]);
+    assert_eq!(matches.value_of(opt_args::ARG_OUTPUT_FILE), Some("out.txt"));
+    assert_eq!(matches.value_of(opt_args::ARG_LEVEL), Some("3"));
+    assert!(matches.is_present(opt_args::common(common_ids::ARG_VERBOSE)));
+    assert!(matches.is_present(opt_args::remote(common_ids::ARG_VERBOSE)));
+}
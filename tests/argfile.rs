@@ -0,0 +1,67 @@
+use clap::Clap;
+use std::path::PathBuf;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(argfile)]
+struct Opt {
+    #[clap(short)]
+    verbose: bool,
+
+    #[clap(short = "L", long)]
+    lib: Vec<String>,
+
+    files: Vec<String>,
+}
+
+fn argfile(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("clap_derive_argfile_{}", name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn argfile_is_expanded() {
+    let path = argfile("expanded", "-L\nfoo\r\n\n--lib=bar\nmain.o\n");
+    let arg = format!("@{}", path.display());
+    assert_eq!(
+        Opt {
+            verbose: true,
+            lib: vec!["foo".into(), "bar".into()],
+            files: vec!["main.o".into(), "util.o".into()],
+        },
+        Opt::try_parse_from(&["test", "-v", &arg, "util.o"]).unwrap()
+    );
+}
+
+#[test]
+fn argfile_not_expanded_after_double_dash() {
+    assert_eq!(
+        Opt {
+            verbose: false,
+            lib: vec![],
+            files: vec!["@not-a-file".into()],
+        },
+        Opt::try_parse_from(&["test", "--", "@not-a-file"]).unwrap()
+    );
+}
+
+#[test]
+fn missing_argfile() {
+    let err = Opt::try_parse_from(&["test", "@/nonexistent/clap_derive_argfile"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::Io);
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct NoArgfile {
+    files: Vec<String>,
+}
+
+#[test]
+fn argfile_is_opt_in() {
+    assert_eq!(
+        NoArgfile {
+            files: vec!["@args.txt".into()],
+        },
+        NoArgfile::try_parse_from(&["test", "@args.txt"]).unwrap()
+    );
+}
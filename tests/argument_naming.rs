@@ -179,6 +179,34 @@ fn test_standalone_short_works_with_verbatim_casing() {
     assert_eq!(Opt { _foo: true }, Opt::parse_from(&["test", "-_"]));
 }
 
+#[test]
+fn test_standalone_long_works_with_lower_casing() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(rename_all = "lower", long)]
+        foo_option: bool,
+    }
+
+    assert_eq!(
+        Opt { foo_option: true },
+        Opt::parse_from(&["test", "--foooption"])
+    );
+}
+
+#[test]
+fn test_standalone_long_works_with_upper_casing() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(rename_all = "uppercase", long)]
+        foo_option: bool,
+    }
+
+    assert_eq!(
+        Opt { foo_option: true },
+        Opt::parse_from(&["test", "--FOOOPTION"])
+    );
+}
+
 #[test]
 fn test_rename_all_is_propagated_from_struct_to_fields() {
     #[derive(Clap, Debug, PartialEq)]
@@ -214,6 +242,76 @@ fn test_rename_all_is_not_propagated_from_struct_into_flattened() {
     );
 }
 
+#[test]
+fn test_rename_all_at_flatten_site_applies_to_flattened() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(flatten, rename_all = "screaming_snake")]
+        foo: Foo,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all = "snake")]
+    struct Foo {
+        #[clap(long)]
+        dry_run: bool,
+        #[clap(rename_all = "kebab", long)]
+        keep_going: bool,
+        #[clap(long = "no-color")]
+        no_color: bool,
+        #[clap(flatten)]
+        bar: Bar,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    struct Bar {
+        #[clap(long)]
+        max_jobs: Option<u32>,
+    }
+
+    assert_eq!(
+        Opt {
+            foo: Foo {
+                dry_run: true,
+                keep_going: true,
+                no_color: true,
+                bar: Bar { max_jobs: Some(2) },
+            }
+        },
+        Opt::parse_from(&[
+            "test",
+            "--DRY_RUN",
+            "--keep-going",
+            "--no-color",
+            "--MAX_JOBS",
+            "2"
+        ])
+    );
+    assert!(Opt::try_parse_from(&["test", "--dry_run"]).is_err());
+}
+
+#[test]
+fn test_rename_all_at_flatten_site_keeps_prefix() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(flatten, prefix = "net", rename_all = "snake")]
+        foo: Foo,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    struct Foo {
+        #[clap(long)]
+        max_jobs: Option<u32>,
+    }
+
+    assert_eq!(
+        Opt {
+            foo: Foo { max_jobs: Some(2) }
+        },
+        Opt::parse_from(&["test", "--net-max_jobs", "2"])
+    );
+}
+
 #[test]
 fn test_rename_all_is_not_propagated_from_struct_into_subcommand() {
     #[derive(Clap, Debug, PartialEq)]
@@ -239,6 +337,96 @@ fn test_rename_all_is_not_propagated_from_struct_into_subcommand() {
     );
 }
 
+#[test]
+fn test_propagate_rename_all_reaches_subcommands_and_flattened() {
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all = "snake", propagate_rename_all)]
+    struct Opt {
+        #[clap(long)]
+        dry_run: bool,
+        #[clap(flatten)]
+        common: Common,
+        #[clap(subcommand)]
+        cmd: Command,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    struct Common {
+        #[clap(long)]
+        max_jobs: Option<u32>,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    enum Command {
+        AddRemote {
+            #[clap(long)]
+            remote_name: String,
+        },
+        ShowLog(ShowLog),
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    struct ShowLog {
+        #[clap(long)]
+        max_count: u32,
+    }
+
+    assert_eq!(
+        Opt {
+            dry_run: true,
+            common: Common { max_jobs: Some(2) },
+            cmd: Command::AddRemote {
+                remote_name: "origin".into()
+            },
+        },
+        Opt::parse_from(&[
+            "test",
+            "--dry_run",
+            "--max_jobs",
+            "2",
+            "add_remote",
+            "--remote_name",
+            "origin"
+        ])
+    );
+    assert_eq!(
+        Opt {
+            dry_run: false,
+            common: Common { max_jobs: None },
+            cmd: Command::ShowLog(ShowLog { max_count: 3 }),
+        },
+        Opt::parse_from(&["test", "show_log", "--max_count", "3"])
+    );
+}
+
+#[test]
+fn test_propagate_rename_all_can_be_overridden() {
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all = "snake", propagate_rename_all)]
+    struct Opt {
+        #[clap(subcommand)]
+        cmd: Command,
+    }
+
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all = "kebab")]
+    enum Command {
+        AddRemote {
+            #[clap(long)]
+            remote_name: String,
+        },
+    }
+
+    assert_eq!(
+        Opt {
+            cmd: Command::AddRemote {
+                remote_name: "origin".into()
+            },
+        },
+        Opt::parse_from(&["test", "add-remote", "--remote-name", "origin"])
+    );
+}
+
 #[test]
 fn test_rename_all_is_propagated_from_enum_to_variants_and_their_fields() {
     #[derive(Clap, Debug, PartialEq)]
@@ -288,3 +476,125 @@ fn test_rename_all_is_propagation_can_be_overridden() {
         Opt::parse_from(&["test", "SECOND_VARIANT", "--foo-option"])
     );
 }
+
+#[test]
+fn test_env_uses_screaming_snake_casing_by_default() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(long, env)]
+        naming_default_env: String,
+    }
+
+    std::env::set_var("NAMING_DEFAULT_ENV", "foo");
+    assert_eq!(
+        Opt {
+            naming_default_env: "foo".into()
+        },
+        Opt::parse_from(&["test"])
+    );
+}
+
+#[test]
+fn test_rename_all_env_is_propagated_from_struct_to_fields() {
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all_env = "kebab")]
+    struct Opt {
+        #[clap(long, env)]
+        naming_kebab_env: String,
+    }
+
+    std::env::set_var("naming-kebab-env", "foo");
+    assert_eq!(
+        Opt {
+            naming_kebab_env: "foo".into()
+        },
+        Opt::parse_from(&["test"])
+    );
+}
+
+#[test]
+fn test_rename_all_env_does_not_change_argument_names() {
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(rename_all_env = "snake")]
+    struct Opt {
+        #[clap(long, env)]
+        naming_snake_env: String,
+    }
+
+    assert_eq!(
+        Opt {
+            naming_snake_env: "foo".into()
+        },
+        Opt::parse_from(&["test", "--naming-snake-env", "foo"])
+    );
+}
+
+#[test]
+fn test_id_is_the_key_of_raw_relations() {
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(long = "src", id = "source_path")]
+        source: Option<String>,
+        #[clap(long, requires = "source_path")]
+        dest: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            source: Some("a".into()),
+            dest: Some("b".into())
+        },
+        Opt::parse_from(&["test", "--src", "a", "--dest", "b"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--dest", "b"]).is_err());
+}
+
+#[test]
+fn test_id_does_not_change_displayed_names() {
+    use clap::IntoApp;
+
+    #[derive(Clap, Debug, PartialEq)]
+    struct Opt {
+        #[clap(id = "input_path")]
+        input: String,
+        #[clap(long, id = "output_path")]
+        output_file: String,
+    }
+
+    let mut help = Vec::new();
+    Opt::into_app().write_help(&mut help).unwrap();
+    let help = String::from_utf8(help).unwrap();
+    assert!(help.contains("<input>"));
+    assert!(help.contains("--output-file <output-file>"));
+    assert!(!help.contains("output_path"));
+    assert!(!help.contains("input_path"));
+}
+
+#[test]
+fn test_legacy_rename_all_adds_hidden_aliases() {
+    use clap::IntoApp;
+
+    #[derive(Clap, Debug, PartialEq)]
+    #[clap(legacy_rename_all = "snake")]
+    struct Opt {
+        #[clap(long)]
+        dry_run: bool,
+        #[clap(long = "out")]
+        output_file: Option<String>,
+        #[clap(long)]
+        force: bool,
+    }
+
+    let parsed = Opt {
+        dry_run: true,
+        output_file: None,
+        force: true,
+    };
+    assert_eq!(parsed, Opt::parse_from(&["test", "--dry-run", "--force"]));
+    assert_eq!(parsed, Opt::parse_from(&["test", "--dry_run", "--force"]));
+    assert!(Opt::try_parse_from(&["test", "--output_file", "x"]).is_err());
+
+    let mut help = Vec::new();
+    Opt::into_app().write_help(&mut help).unwrap();
+    assert!(!String::from_utf8(help).unwrap().contains("dry_run"));
+}
@@ -50,3 +50,34 @@ fn explicit_version_not_str() {
     let output = get_long_help::<Opt>();
     assert!(output.contains("custom version"));
 }
+
+#[test]
+fn explicit_cargo_version() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "foo", no_auto_version, version)]
+    struct Opt {}
+
+    let output = get_long_help::<Opt>();
+    assert!(output.starts_with(&format!("foo {}", env!("CARGO_PKG_VERSION"))));
+}
+
+#[test]
+fn no_auto_version() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "foo", no_auto_version)]
+    struct Opt {}
+
+    let output = get_long_help::<Opt>();
+    assert!(output.starts_with("foo \n\nUSAGE:"));
+}
+
+#[test]
+fn no_auto_version_with_explicit_author() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(name = "foo", no_auto_version, author = "Jane Doe")]
+    struct Opt {}
+
+    let output = get_long_help::<Opt>();
+    assert!(output.starts_with("foo \n"));
+    assert!(output.contains("Jane Doe"));
+}
@@ -28,3 +28,34 @@ fn basic() {
         Opt::parse_from(&["test", "-a24", "--arg", "42"])
     );
 }
+
+#[test]
+fn parse_from_any_os_string_iterator() {
+    use std::ffi::{OsStr, OsString};
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short)]
+        arg: Option<String>,
+    }
+
+    let expected = Opt {
+        arg: Some("x".into()),
+    };
+    assert_eq!(expected, Opt::parse_from(vec!["test", "-a", "x"]));
+    assert_eq!(
+        expected,
+        Opt::parse_from(vec![
+            String::from("test"),
+            String::from("-a"),
+            String::from("x")
+        ])
+    );
+    let os_args: Vec<OsString> = vec!["test".into(), "-a".into(), "x".into()];
+    assert_eq!(expected, Opt::parse_from(os_args.clone()));
+    assert_eq!(expected, Opt::parse_from(&os_args));
+    assert_eq!(
+        expected,
+        Opt::try_parse_from(["test", "-a", "x"].iter().map(OsStr::new)).unwrap()
+    );
+}
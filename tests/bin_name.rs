@@ -0,0 +1,48 @@
+use clap::Clap;
+use std::ffi::OsString;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(bin_name)]
+    program: String,
+
+    #[clap(short)]
+    verbose: bool,
+
+    #[clap(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    Run {
+        #[clap(bin_name)]
+        program: OsString,
+    },
+}
+
+#[test]
+fn bin_name_is_the_first_argument() {
+    assert_eq!(
+        Opt {
+            program: "/usr/bin/tool".into(),
+            verbose: true,
+            cmd: None,
+        },
+        Opt::parse_from(&["/usr/bin/tool", "-v"])
+    );
+}
+
+#[test]
+fn bin_name_reaches_subcommands() {
+    assert_eq!(
+        Opt {
+            program: "tool".into(),
+            verbose: false,
+            cmd: Some(Command::Run {
+                program: "tool".into()
+            }),
+        },
+        Opt::parse_from(&["tool", "run"])
+    );
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "cached_app")]
+
+use clap::{Arg, Clap, FromArgMatches};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", cached_app, docs)]
+struct Opt {
+    /// Number of jobs
+    #[clap(short, long, default_value = "1")]
+    jobs: u32,
+}
+
+impl Opt {
+    // methods of the type are left alone by the derive
+    fn app(&self) -> u32 {
+        self.jobs
+    }
+}
+
+#[test]
+fn cached_app_parses_repeatedly() {
+    for jobs in 1..4 {
+        let matches =
+            Opt::with_clap_app(|app| app.clone().get_matches_from(&["tool", "-j", &jobs.to_string()]));
+        assert_eq!(jobs, Opt::from_argmatches(&matches).app());
+    }
+}
+
+#[test]
+fn cached_app_renders_help() {
+    let mut help = Vec::new();
+    Opt::with_clap_app(|app| app.write_help(&mut help)).unwrap();
+    assert!(String::from_utf8(help).unwrap().contains("Number of jobs"));
+    assert!(Opt::help_snapshot().contains("Number of jobs"));
+}
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+fn counted(arg: Arg) -> Arg {
+    BUILDS.fetch_add(1, Ordering::SeqCst);
+    arg
+}
+
+#[test]
+fn parsing_uses_the_cached_app() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(cached_app)]
+    struct Counted {
+        #[clap(long, mut_arg = counted)]
+        name: Option<String>,
+    }
+
+    for name in &["a", "b", "c"] {
+        assert_eq!(
+            Counted {
+                name: Some(name.to_string())
+            },
+            Counted::parse_from(&["test", "--name", name])
+        );
+    }
+    assert!(Counted::try_parse_from(&["test", "--nope"]).is_err());
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn nested_calls_get_their_own_app() {
+    let (outer, inner) = Opt::with_clap_app(|outer| {
+        let inner = Opt::with_clap_app(|inner| {
+            let mut help = Vec::new();
+            inner.write_help(&mut help).unwrap();
+            String::from_utf8(help).unwrap()
+        });
+        let mut help = Vec::new();
+        outer.write_help(&mut help).unwrap();
+        (String::from_utf8(help).unwrap(), inner)
+    });
+    assert!(inner.contains("Number of jobs"));
+    assert_eq!(outer, inner);
+}
@@ -0,0 +1,54 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Logging {
+    #[clap(long = "logLevel", default_value = "info")]
+    log_level: String,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(case_insensitive_longs)]
+struct Opt {
+    #[clap(long)]
+    output: Option<String>,
+    #[clap(long)]
+    verbose: bool,
+    #[clap(flatten)]
+    logging: Logging,
+    rest: Vec<String>,
+}
+
+#[test]
+fn longs_match_in_any_case() {
+    assert_eq!(
+        Opt {
+            output: Some("out.txt".into()),
+            verbose: true,
+            logging: Logging {
+                log_level: "debug".into()
+            },
+            rest: vec![],
+        },
+        Opt::parse_from(&["test", "--Output", "out.txt", "--VERBOSE", "--LOGLEVEL=debug"])
+    );
+}
+
+#[test]
+fn nothing_is_folded_after_double_dash() {
+    assert_eq!(
+        Opt {
+            output: None,
+            verbose: false,
+            logging: Logging {
+                log_level: "info".into()
+            },
+            rest: vec!["--Verbose".into()],
+        },
+        Opt::parse_from(&["test", "--", "--Verbose"])
+    );
+}
+
+#[test]
+fn unknown_longs_are_still_errors() {
+    assert!(Opt::try_parse_from(&["test", "--Unknown"]).is_err());
+}
@@ -0,0 +1,69 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(color = "never")]
+struct Opt {
+    /// When to use colors
+    #[clap(long, color, default_value = "auto")]
+    color: String,
+
+    #[clap(short)]
+    verbose: bool,
+}
+
+#[test]
+fn color_choice_field() {
+    assert_eq!(
+        Opt {
+            color: "auto".into(),
+            verbose: false,
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            color: "always".into(),
+            verbose: true,
+        },
+        Opt::parse_from(&["test", "--color", "always", "-v"])
+    );
+    assert_eq!(
+        Opt {
+            color: "never".into(),
+            verbose: false,
+        },
+        Opt::parse_from(&["test", "--color=never"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--color", "sometimes"]).is_err());
+}
+
+#[test]
+fn color_arg_is_found_through_flatten() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Outer {
+        #[clap(flatten)]
+        inner: Opt,
+    }
+
+    assert_eq!(Outer::__clap_color_arg(("", "", None, None)), Some("color"));
+}
+
+#[test]
+fn color_arg_is_prefixed_through_flatten() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Outer {
+        #[clap(flatten, prefix = "ui")]
+        inner: Opt,
+    }
+
+    assert_eq!(Outer::__clap_color_arg(("", "", None, None)), Some("ui-color"));
+    assert_eq!(
+        Outer {
+            inner: Opt {
+                color: "always".into(),
+                verbose: false,
+            }
+        },
+        Outer::parse_from(&["test", "--ui-color", "always"])
+    );
+}
@@ -0,0 +1,217 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", about = "A tool", dynamic_completion, docs)]
+struct Opt {
+    /// Be verbose
+    #[clap(short, long, parse(from_occurrences))]
+    verbose: u8,
+
+    /// Output format
+    #[clap(long, possible_values = &["json", "text"])]
+    format: Option<String>,
+
+    #[clap(subcommand)]
+    cmd: Sub,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(dynamic_completion, docs)]
+enum Sub {
+    /// Fetch remote objects
+    Fetch {
+        #[clap(possible_values = &["origin", "upstream"])]
+        remote: String,
+    },
+}
+
+#[test]
+fn carapace_spec() {
+    assert_eq!(
+        Opt::carapace_spec(),
+        concat!(
+            "name: \"tool\"\n",
+            "description: \"A tool\"\n",
+            "flags:\n",
+            "  \"-v, --verbose*\": \"Be verbose\"\n",
+            "  \"--format=\": \"Output format\"\n",
+            "completion:\n",
+            "  flag:\n",
+            "    \"format\": [\"json\", \"text\"]\n",
+            "commands:\n",
+            "  - name: \"fetch\"\n",
+            "    description: \"Fetch remote objects\"\n",
+            "    completion:\n",
+            "      positional:\n",
+            "        - [\"origin\", \"upstream\"]\n",
+        )
+    );
+}
+
+fn complete(words: &[&str]) -> Vec<String> {
+    let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    Opt::__clap_complete(&words)
+}
+
+#[test]
+fn dynamic_completion_of_flags() {
+    assert_eq!(complete(&["--f"]), vec!["--format"]);
+    assert_eq!(complete(&["-"]), vec!["--verbose", "-v", "--format"]);
+}
+
+#[test]
+fn dynamic_completion_of_values() {
+    assert_eq!(complete(&["--format", ""]), vec!["json", "text"]);
+    assert_eq!(complete(&["--format", "t"]), vec!["text"]);
+}
+
+#[test]
+fn dynamic_completion_of_subcommands() {
+    assert_eq!(complete(&["-v", "f"]), vec!["fetch"]);
+    assert_eq!(complete(&["fetch", "up"]), vec!["upstream"]);
+    assert_eq!(complete(&["--format", "json", "fetch", ""]), vec!["origin", "upstream"]);
+}
+
+fn list_remotes() -> Vec<String> {
+    vec!["origin".into(), "upstream".into()]
+}
+
+fn list_branches() -> Vec<&'static str> {
+    vec!["main", "master", "release"]
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "git", dynamic_completion, docs)]
+struct Git {
+    #[clap(long, complete_with = list_remotes)]
+    remote: Option<String>,
+
+    #[clap(complete_with = list_branches)]
+    branch: Option<String>,
+}
+
+fn complete_git(words: &[&str]) -> Vec<String> {
+    let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+    Git::__clap_complete(&words)
+}
+
+#[test]
+fn dynamic_completion_with_functions() {
+    assert_eq!(complete_git(&["--remote", "up"]), vec!["upstream"]);
+    assert_eq!(complete_git(&["ma"]), vec!["main", "master"]);
+}
+
+#[test]
+fn carapace_spec_with_functions() {
+    let spec = Git::carapace_spec();
+    assert!(spec.contains("    \"remote\": [\"origin\", \"upstream\"]\n"));
+    assert!(spec.contains("    - [\"main\", \"master\", \"release\"]\n"));
+}
+
+static LISTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn list_counted() -> Vec<String> {
+    LISTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    vec!["slow".into()]
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "counted", dynamic_completion)]
+struct Counted {
+    #[clap(long, complete_with = list_counted)]
+    profile: Option<String>,
+
+    #[clap(long)]
+    name: Option<String>,
+}
+
+#[test]
+fn complete_with_functions_are_called_only_for_their_argument() {
+    let complete = |words: &[&str]| {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        Counted::__clap_complete(&words)
+    };
+
+    assert_eq!(complete(&["--n"]), vec!["--name"]);
+    assert_eq!(complete(&["--name", ""]), Vec::<String>::new());
+    assert_eq!(LISTED.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    assert_eq!(complete(&["--profile", ""]), vec!["slow"]);
+    assert_eq!(LISTED.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "fetcher", dynamic_completion, docs)]
+struct Fetcher {
+    #[clap(long, possible_values = &["origin", "upstream"])]
+    remote: Option<String>,
+
+    target: Option<String>,
+}
+
+#[test]
+fn dynamic_completion_through_the_complete_subcommand() {
+    // a value named `complete` is not taken for a completion request
+    assert_eq!(
+        Fetcher {
+            remote: None,
+            target: Some("complete".into())
+        },
+        Fetcher::try_parse_from(&["fetcher", "complete"]).unwrap()
+    );
+
+    let err = Fetcher::try_parse_from(&["fetcher", "complete", "--", "--remote", "o"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::HelpDisplayed);
+    assert_eq!(err.message, "origin");
+}
+
+#[test]
+fn completion_scripts_are_written_with_the_artifacts() {
+    let dir = std::env::temp_dir().join("clap_derive_completion_scripts");
+    let paths = Fetcher::generate_artifacts(&dir).unwrap();
+    assert_eq!(&paths[4..], &[dir.join("fetcher.bash"), dir.join("_fetcher"), dir.join("fetcher.fish")]);
+
+    let bash = std::fs::read_to_string(dir.join("fetcher.bash")).unwrap();
+    assert!(bash.contains("$(fetcher complete -- \"${COMP_WORDS[@]:1:COMP_CWORD}\")"));
+    assert!(bash.ends_with("complete -o default -F _fetcher fetcher\n"));
+    let zsh = std::fs::read_to_string(dir.join("_fetcher")).unwrap();
+    assert!(zsh.starts_with("#compdef fetcher\n"));
+    let fish = std::fs::read_to_string(dir.join("fetcher.fish")).unwrap();
+    assert!(fish.starts_with("complete -c fetcher -a '(fetcher complete -- "));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "mirror", about = "A mirror", dynamic_completion, docs)]
+struct Mirror {
+    #[clap(flatten, prefix = "remote", rename_all = "snake")]
+    remote: Remote,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(dynamic_completion, docs)]
+struct Remote {
+    /// Remote to push to
+    #[clap(short, long, possible_values = &["origin", "upstream"])]
+    push_url: Option<String>,
+}
+
+#[test]
+fn flattened_flags_are_prefixed() {
+    assert_eq!(
+        Mirror::carapace_spec(),
+        concat!(
+            "name: \"mirror\"\n",
+            "description: \"A mirror\"\n",
+            "flags:\n",
+            "  \"--remote-push_url=\": \"Remote to push to\"\n",
+            "completion:\n",
+            "  flag:\n",
+            "    \"remote-push_url\": [\"origin\", \"upstream\"]\n",
+        )
+    );
+
+    let words = vec!["--remote-push_url".to_string(), "u".to_string()];
+    assert_eq!(Mirror::__clap_complete(&words), vec!["upstream"]);
+    let words = vec!["--r".to_string()];
+    assert_eq!(Mirror::__clap_complete(&words), vec!["--remote-push_url"]);
+}
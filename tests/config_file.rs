@@ -0,0 +1,190 @@
+#![cfg(feature = "toml")]
+
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(config_file = "--config")]
+struct Opt {
+    #[clap(long, default_value = "1")]
+    jobs: u32,
+
+    #[clap(long)]
+    host: Option<String>,
+
+    #[clap(short, long)]
+    verbose: bool,
+}
+
+fn write_config(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn config_file_values_are_defaults() {
+    let path = write_config("clap_derive_defaults.toml", "jobs = 4\nhost = \"example.com\"\n");
+
+    assert_eq!(
+        Opt {
+            jobs: 4,
+            host: Some("example.com".into()),
+            verbose: false,
+        },
+        Opt::parse_from(&["test", "--config", &path])
+    );
+    assert_eq!(
+        Opt {
+            jobs: 8,
+            host: Some("example.com".into()),
+            verbose: true,
+        },
+        Opt::parse_from(&["test", "-v", &format!("--config={}", path), "--jobs", "8"])
+    );
+}
+
+#[test]
+fn no_config_file() {
+    assert_eq!(
+        Opt {
+            jobs: 1,
+            host: None,
+            verbose: false,
+        },
+        Opt::parse_from(&["test"])
+    );
+}
+
+#[test]
+fn snake_case_keys() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(config_file = "--config")]
+    struct Opt {
+        #[clap(long)]
+        max_retries: u32,
+    }
+
+    let path = write_config("clap_derive_snake_case.toml", "max_retries = 3\n");
+    assert_eq!(
+        Opt { max_retries: 3 },
+        Opt::parse_from(&["test", "--config", &path])
+    );
+}
+
+#[test]
+fn invalid_config_file() {
+    let path = write_config("clap_derive_invalid.toml", "jobs = \n");
+    assert!(Opt::try_parse_from(&["test", "--config", &path]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--config", "/nonexistent/clap.toml"]).is_err());
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(config_file = "--config")]
+struct Tool {
+    #[clap(long)]
+    host: Option<String>,
+
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    Serve {
+        #[clap(long, default_value = "80")]
+        port: u16,
+    },
+    Stop,
+}
+
+#[test]
+fn subcommands_read_their_table() {
+    let path = write_config(
+        "clap_derive_subcommands.toml",
+        "host = \"example.com\"\n\n[serve]\nport = 8080\n",
+    );
+
+    assert_eq!(
+        Tool {
+            host: Some("example.com".into()),
+            cmd: Command::Serve { port: 8080 },
+        },
+        Tool::parse_from(&["test", "--config", &path, "serve"])
+    );
+    assert_eq!(
+        Tool {
+            host: Some("example.com".into()),
+            cmd: Command::Serve { port: 9000 },
+        },
+        Tool::parse_from(&["test", "--config", &path, "serve", "--port", "9000"])
+    );
+}
+
+#[test]
+fn arrays_are_rejected() {
+    let path = write_config("clap_derive_array.toml", "host = [\"a\", \"b\"]\n");
+    let err = Opt::try_parse_from(&["test", "--config", &path]).unwrap_err();
+    assert!(err.message.contains("`host` takes a single value"));
+
+    let path = write_config("clap_derive_nested_array.toml", "[serve]\nport = [1, 2]\n");
+    let err = Tool::try_parse_from(&["test", "--config", &path, "serve"]).unwrap_err();
+    assert!(err.message.contains("`serve.port` takes a single value"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Remote {
+    #[clap(long)]
+    host: Option<String>,
+
+    #[clap(long)]
+    tls: bool,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(config_file = "--config")]
+struct Service {
+    #[clap(long)]
+    tag: Vec<String>,
+
+    #[clap(flatten, prefix = "remote")]
+    remote: Remote,
+}
+
+#[test]
+fn arrays_flags_and_flattened_args() {
+    let path = write_config(
+        "clap_derive_flattened.toml",
+        "tag = [\"a\", \"b\"]\nremote-host = \"example.com\"\nremote_tls = true\n",
+    );
+
+    assert_eq!(
+        Service {
+            tag: vec!["a".into(), "b".into()],
+            remote: Remote {
+                host: Some("example.com".into()),
+                tls: true,
+            },
+        },
+        Service::parse_from(&["test", "--config", &path])
+    );
+    assert_eq!(
+        Service {
+            tag: vec!["c".into()],
+            remote: Remote {
+                host: Some("example.com".into()),
+                tls: true,
+            },
+        },
+        Service::parse_from(&["test", "--config", &path, "--tag", "c", "--remote-tls"])
+    );
+}
+
+#[test]
+fn flags_take_booleans() {
+    let path = write_config("clap_derive_flag_false.toml", "verbose = false\n");
+    assert!(!Opt::parse_from(&["test", "--config", &path]).verbose);
+
+    let path = write_config("clap_derive_flag_string.toml", "verbose = \"yes\"\n");
+    let err = Opt::try_parse_from(&["test", "--config", &path]).unwrap_err();
+    assert!(err.message.contains("`verbose` should be a boolean"));
+}
@@ -307,3 +307,48 @@ fn test_cstring() {
     );
     assert!(Opt::try_parse_from(&["test", "bla\0bla"]).is_err());
 }
+
+#[test]
+fn test_parser_error_only_display() {
+    use std::fmt;
+
+    struct NotAPort;
+
+    impl fmt::Display for NotAPort {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("not a port number")
+        }
+    }
+
+    fn parse_port(s: &str) -> Result<u16, NotAPort> {
+        s.parse().map_err(|_| NotAPort)
+    }
+
+    fn parse_host(s: &str) -> Result<String, Box<dyn std::error::Error>> {
+        if s.is_empty() {
+            Err("empty host".into())
+        } else {
+            Ok(s.to_string())
+        }
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, parse(try_from_str = parse_port))]
+        port: u16,
+
+        #[clap(long, parse(try_from_str = parse_host))]
+        host: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            port: 8080,
+            host: Some("localhost".into()),
+        },
+        Opt::parse_from(&["test", "--port", "8080", "--host", "localhost"])
+    );
+
+    let err = Opt::try_parse_from(&["test", "--port", "http"]).unwrap_err();
+    assert!(err.message.contains("not a port number"));
+}
@@ -0,0 +1,135 @@
+#![cfg(debug_assertions)]
+
+use clap::{Clap, IntoApp};
+
+#[test]
+#[should_panic(expected = "Command `test`: short `-v` is used by both `verbose` and `version-check`")]
+fn conflicting_shorts() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(short)]
+        verbose: bool,
+        #[clap(short)]
+        version_check: bool,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+#[should_panic(expected = "Command `test`: long `--host` is used by both `host` and `other`")]
+fn conflicting_longs_across_flatten() {
+    #[derive(Clap, Debug)]
+    struct Common {
+        #[clap(long = "host")]
+        other: String,
+    }
+
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(long)]
+        host: String,
+        #[clap(flatten)]
+        common: Common,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+#[should_panic(expected = "Command `fetch`: required positional `remote` comes after optional positional `branch`")]
+fn required_positional_after_optional() {
+    #[derive(Clap, Debug)]
+    struct Remote {
+        remote: String,
+    }
+
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    enum Opt {
+        Fetch {
+            branch: Option<String>,
+            #[clap(flatten)]
+            remote: Remote,
+        },
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+fn consistent_app() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(short, long)]
+        verbose: bool,
+        input: String,
+        output: Option<String>,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+#[should_panic(
+    expected = "Command `test`: default value `abc` of `jobs` is invalid: invalid digit found in string"
+)]
+fn unparsable_default_value() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(long, default_value = "abc")]
+        jobs: i32,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+fn parsable_default_values() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(long, default_value = "4")]
+        jobs: i32,
+        #[clap(long, default_value = "a,b")]
+        names: Vec<String>,
+        #[clap(long, default_value = "1,2,3")]
+        ports: Vec<u16>,
+        #[clap(long, default_value = "1:2", value_delimiter = ":")]
+        levels: Vec<u8>,
+        #[clap(long, default_value_os = std::ffi::OsStr::new("8"))]
+        threads: u8,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+#[should_panic(expected = "Command `test`: default value `x` of `ports` is invalid")]
+fn unparsable_delimited_default_value() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(long, default_value = "1,x")]
+        ports: Vec<u16>,
+    }
+
+    Opt::into_app();
+}
+
+#[test]
+#[should_panic(expected = "Command `test`: default value `many` of `threads` is invalid")]
+fn unparsable_default_value_os() {
+    #[derive(Clap, Debug)]
+    #[clap(name = "test")]
+    struct Opt {
+        #[clap(long, default_value_os = std::ffi::OsStr::new("many"))]
+        threads: u8,
+    }
+
+    Opt::into_app();
+}
@@ -114,3 +114,86 @@ fn top_long_doc_comment_both_help_long_help() {
     assert!(long_help.contains("DO NOT PASS A BAR UNDER ANY CIRCUMSTANCES"));
     assert!(long_help.contains("Or something else"));
 }
+
+#[test]
+fn long_help_only_sections() {
+    #[derive(Clap, Debug)]
+    #[clap(
+        name = "lorem-ipsum",
+        before_long_help = "Read this first",
+        after_long_help = "Some detailed usage notes"
+    )]
+    struct LoremIpsum {
+        #[clap(long)]
+        foo: bool,
+    }
+
+    let short_help = get_help::<LoremIpsum>();
+    let long_help = get_long_help::<LoremIpsum>();
+
+    assert!(!short_help.contains("Read this first"));
+    assert!(!short_help.contains("Some detailed usage notes"));
+    assert!(long_help.contains("Read this first"));
+    assert!(long_help.contains("Some detailed usage notes"));
+}
+
+#[test]
+fn after_help_kept_in_long_help_with_examples() {
+    #[derive(Clap, Debug)]
+    #[clap(
+        name = "lorem-ipsum",
+        after_help = "Dolor sit amet",
+        example = "lorem-ipsum --foo"
+    )]
+    struct LoremIpsum {
+        #[clap(long)]
+        foo: bool,
+    }
+
+    let short_help = get_help::<LoremIpsum>();
+    let long_help = get_long_help::<LoremIpsum>();
+
+    assert!(short_help.contains("Dolor sit amet"));
+    assert!(!short_help.contains("EXAMPLES:"));
+    assert!(long_help.contains("Dolor sit amet\n\nEXAMPLES:\n    lorem-ipsum --foo"));
+}
+
+#[test]
+fn non_literal_doc_attributes() {
+    macro_rules! generated_doc {
+        () => {
+            "Generated by a macro"
+        };
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    #[doc = generated_doc!()]
+    struct LoremIpsum {
+        #[doc = include_str!("doc-comments-help.txt")]
+        #[clap(long)]
+        color: bool,
+    }
+
+    let help = get_long_help::<LoremIpsum>();
+
+    assert!(help.contains("Generated by a macro"));
+    assert!(help.contains("Colors the output according to the terminal."));
+}
+
+#[test]
+fn non_literal_doc_attributes_split_short_and_long_help() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct LoremIpsum {
+        #[doc = include_str!("doc-comments-help-long.txt")]
+        #[clap(long)]
+        color: bool,
+    }
+
+    let short_help = get_help::<LoremIpsum>();
+    let long_help = get_long_help::<LoremIpsum>();
+
+    assert!(short_help.contains("Colors the output\n"));
+    assert!(!short_help.contains("The terminal tells"));
+    assert!(long_help.contains("Colors the output."));
+    assert!(long_help.contains("The terminal tells whether colors are supported."));
+}
@@ -0,0 +1,120 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", docs)]
+struct Opt {
+    /// Be verbose
+    #[clap(short, long)]
+    verbose: bool,
+
+    #[clap(subcommand)]
+    cmd: Sub,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(docs)]
+enum Sub {
+    /// Fetch remote objects
+    Fetch {
+        /// Name of the remote
+        remote: String,
+    },
+
+    /// Manage stashes
+    Stash(Stash),
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(docs)]
+struct Stash {
+    #[clap(subcommand)]
+    cmd: StashCommand,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(docs)]
+enum StashCommand {
+    /// Remove all stashes
+    Clear,
+}
+
+#[test]
+fn subcommand_paths_are_listed_recursively() {
+    assert_eq!(
+        Opt::__clap_subcommand_paths(),
+        vec![vec!["fetch"], vec!["stash"], vec!["stash", "clear"]]
+    );
+}
+
+#[test]
+fn markdown_help_covers_the_whole_tree() {
+    let markdown = Opt::markdown_help();
+
+    assert!(markdown.starts_with("# `tool`\n\n```text\n"));
+    assert!(markdown.contains("Be verbose"));
+    assert!(markdown.contains("## `tool fetch`\n\n```text\n"));
+    assert!(markdown.contains("Name of the remote"));
+    assert!(markdown.contains("    tool fetch <remote>"));
+    assert!(markdown.contains("## `tool stash`\n\n"));
+    assert!(markdown.contains("### `tool stash clear`\n\n"));
+    assert!(markdown.contains("Remove all stashes"));
+}
+
+#[test]
+#[should_panic(expected = "`tool stash` has no subcommand `pop`")]
+fn long_help_of_an_unknown_subcommand_panics() {
+    Opt::__clap_long_help(&["stash", "pop"]);
+}
+
+#[test]
+fn artifacts_are_written() {
+    let dir = std::env::temp_dir().join("clap_derive_artifacts");
+    let paths = Opt::generate_artifacts(&dir).unwrap();
+    assert_eq!(
+        paths,
+        vec![
+            dir.join("tool.md"),
+            dir.join("tool.rst"),
+            dir.join("tool.1"),
+            dir.join("tool.yaml")
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tool.md")).unwrap(),
+        Opt::markdown_help()
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tool.yaml")).unwrap(),
+        Opt::carapace_spec()
+    );
+}
+
+#[test]
+fn rst_help_has_a_section_per_command() {
+    let rst = Opt::rst_help();
+    assert!(rst.starts_with("``tool``\n========\n\n.. code-block:: text\n\n    "));
+    assert!(rst.contains("\n``tool fetch``\n--------------\n\n.. code-block:: text\n\n"));
+    assert!(rst.contains("\n``tool stash clear``\n~~~~~~~~~~~~~~~~~~~~\n\n"));
+}
+
+#[test]
+fn roff_help_has_a_section_per_command() {
+    let roff = Opt::roff_help();
+    assert!(roff.starts_with(".TH TOOL 1\n.SH \"tool\"\n.nf\n"));
+    assert!(roff.contains("\n.fi\n.SS \"tool fetch\"\n.nf\n"));
+    assert!(roff.contains(".SS \"tool stash clear\"\n"));
+    assert!(roff.ends_with(".fi\n"));
+}
+
+#[test]
+fn help_snapshot_covers_the_whole_tree() {
+    let snapshot = Opt::help_snapshot();
+
+    assert!(snapshot.starts_with("$ tool --help\n"));
+    assert!(snapshot.contains("\n$ tool fetch --help\n"));
+    assert!(snapshot.contains("\n$ tool stash clear --help\n"));
+    assert!(snapshot.contains("Remove all stashes"));
+    assert!(!snapshot.contains('\u{1b}'));
+    assert!(snapshot.lines().all(|line| line == line.trim_end()));
+    assert_eq!(snapshot, Opt::help_snapshot());
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "dotenv")]
+
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(dotenv = "tests/dotenv.env")]
+struct Opt {
+    #[clap(long, env = "DOTENV_TEST_HOST")]
+    host: String,
+
+    #[clap(long, env = "DOTENV_TEST_JOBS", default_value = "1")]
+    jobs: u32,
+}
+
+#[test]
+fn dotenv_values_back_env_args() {
+    assert_eq!(
+        Opt {
+            host: "example.com".into(),
+            jobs: 4,
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            host: "localhost".into(),
+            jobs: 4,
+        },
+        Opt::parse_from(&["test", "--host", "localhost"])
+    );
+}
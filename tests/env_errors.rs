@@ -0,0 +1,61 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, env = "ENV_ERRORS_THREADS")]
+    threads: Option<u32>,
+}
+
+#[test]
+fn bad_env_value_names_the_variable() {
+    std::env::set_var("ENV_ERRORS_THREADS", "many");
+    let err = Opt::try_parse_from(&["test"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("(read from the `ENV_ERRORS_THREADS` environment variable)"));
+
+    let err = Opt::try_parse_from(&["test", "--threads", "lots"]).unwrap_err();
+    assert!(!err.message.contains("ENV_ERRORS_THREADS"));
+
+    // the same bad value given on the command line isn't taken for the
+    // environment one
+    let err = Opt::try_parse_from(&["test", "--threads", "many"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::ValueValidation);
+    assert!(err.message.contains("Invalid value for '--threads'"));
+    assert!(!err.message.contains("ENV_ERRORS_THREADS"));
+
+    std::env::set_var("ENV_ERRORS_THREADS", "4");
+    assert_eq!(Opt { threads: Some(4) }, Opt::parse_from(&["test"]));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Defaulted {
+    #[clap(long, env = "ENV_ERRORS_JOBS", default_value = "2")]
+    jobs: u32,
+}
+
+#[test]
+fn default_value_is_not_taken_for_the_variable() {
+    std::env::remove_var("ENV_ERRORS_JOBS");
+    assert_eq!(Defaulted { jobs: 2 }, Defaulted::parse_from(&["test"]));
+    assert_eq!(Defaulted { jobs: 3 }, Defaulted::parse_from(&["test", "--jobs", "3"]));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct FromMatches {
+    #[clap(long, env = "ENV_ERRORS_LEVEL")]
+    level: Option<u8>,
+}
+
+#[test]
+fn matches_of_into_app_name_the_variable() {
+    use clap::IntoApp;
+    use std::convert::TryFrom;
+
+    std::env::set_var("ENV_ERRORS_LEVEL", "high");
+    let matches = FromMatches::into_app().try_get_matches_from(&["test"]).unwrap();
+    let err = FromMatches::try_from(&matches).unwrap_err();
+    assert!(err
+        .message
+        .contains("(read from the `ENV_ERRORS_LEVEL` environment variable)"));
+}
@@ -0,0 +1,130 @@
+use clap::Clap;
+use std::env;
+use std::process::Command;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, env = ["ENV_FALLBACKS_TOKEN", "ENV_FALLBACKS_LEGACY_TOKEN"], warn_legacy_env)]
+    token: Option<String>,
+}
+
+#[test]
+fn first_set_variable_wins() {
+    env::remove_var("ENV_FALLBACKS_TOKEN");
+    env::remove_var("ENV_FALLBACKS_LEGACY_TOKEN");
+    assert_eq!(Opt { token: None }, Opt::try_parse_from(&["test"]).unwrap());
+
+    env::set_var("ENV_FALLBACKS_LEGACY_TOKEN", "legacy");
+    assert_eq!(
+        Opt {
+            token: Some("legacy".into())
+        },
+        Opt::try_parse_from(&["test"]).unwrap()
+    );
+
+    env::set_var("ENV_FALLBACKS_TOKEN", "new");
+    assert_eq!(
+        Opt {
+            token: Some("new".into())
+        },
+        Opt::try_parse_from(&["test"]).unwrap()
+    );
+
+    assert_eq!(
+        Opt {
+            token: Some("arg".into())
+        },
+        Opt::try_parse_from(&["test", "--token", "arg"]).unwrap()
+    );
+}
+
+const CHILD: &str = "ENV_FALLBACKS_TEST_CHILD";
+
+/// Run the test named `test` again in a child process, with only the legacy
+/// variable set, and give what it printed on stderr.
+fn stderr_of(test: &str) -> String {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&[test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(CHILD, "1")
+        .env_remove("ENV_FALLBACKS_TOKEN")
+        .env("ENV_FALLBACKS_LEGACY_TOKEN", "legacy")
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn legacy_variable_warned_about_once() {
+    if env::var_os(CHILD).is_some() {
+        for _ in 0..3 {
+            let opt = Opt::try_parse_from(&["test"]).unwrap();
+            assert_eq!(Some("legacy".into()), opt.token);
+        }
+        return;
+    }
+
+    let stderr = stderr_of("legacy_variable_warned_about_once");
+    let warning =
+        "warning: `ENV_FALLBACKS_LEGACY_TOKEN` is deprecated, use `ENV_FALLBACKS_TOKEN` instead";
+    assert_eq!(1, stderr.matches(warning).count(), "{}", stderr);
+}
+
+#[test]
+fn current_variable_not_warned_about() {
+    if env::var_os(CHILD).is_some() {
+        Opt::try_parse_from(&["test", "--token", "arg"]).unwrap();
+        return;
+    }
+
+    let stderr = stderr_of("current_variable_not_warned_about");
+    assert!(!stderr.contains("is deprecated"), "{}", stderr);
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Tool {
+    #[clap(subcommand)]
+    cmd: Subcommand,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Subcommand {
+    Serve {
+        #[clap(long, env = ["ENV_FALLBACKS_PORT", "ENV_FALLBACKS_LEGACY_PORT"])]
+        port: u16,
+    },
+}
+
+#[test]
+fn subcommands_read_fallbacks() {
+    env::remove_var("ENV_FALLBACKS_PORT");
+    env::set_var("ENV_FALLBACKS_LEGACY_PORT", "8080");
+    assert_eq!(
+        Tool {
+            cmd: Subcommand::Serve { port: 8080 }
+        },
+        Tool::try_parse_from(&["test", "serve"]).unwrap()
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Limits {
+    #[clap(long, env = ["ENV_FALLBACKS_JOBS", "ENV_FALLBACKS_LEGACY_JOBS"])]
+    jobs: Option<u8>,
+}
+
+#[test]
+fn into_app_reads_fallbacks() {
+    use clap::IntoApp;
+
+    env::remove_var("ENV_FALLBACKS_JOBS");
+    env::set_var("ENV_FALLBACKS_LEGACY_JOBS", "3");
+    let matches = Limits::into_app().try_get_matches_from(&["test"]).unwrap();
+    assert_eq!(matches.value_of("jobs"), Some("3"));
+
+    env::set_var("ENV_FALLBACKS_LEGACY_JOBS", "many");
+    assert!(Limits::into_app().try_get_matches_from(&["test"]).is_err());
+    let err = Limits::try_parse_from(&["test"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("(read from the `ENV_FALLBACKS_LEGACY_JOBS` environment variable)"));
+}
@@ -0,0 +1,88 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(after_long_help = "See the manual for more.")]
+struct Opt {
+    /// Address to listen on
+    #[clap(long, env = "ENV_HELP_ADDR")]
+    addr: String,
+
+    /// Worker threads
+    #[clap(long, env = "ENV_HELP_THREADS")]
+    threads: Option<u32>,
+
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[test]
+fn environment_section_in_long_help() {
+    let help = get_long_help::<Opt>();
+    assert!(help.contains(
+        "See the manual for more.\n\nENVIRONMENT:\n    ENV_HELP_ADDR       Address to listen on\n    ENV_HELP_THREADS    Worker threads"
+    ));
+}
+
+#[test]
+fn no_environment_section_in_short_help() {
+    assert!(!get_help::<Opt>().contains("ENVIRONMENT:"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct NoEnv {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[test]
+fn no_environment_section_without_env() {
+    assert!(!get_long_help::<NoEnv>().contains("ENVIRONMENT:"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(show_defaults, show_env)]
+struct Shown {
+    /// Worker threads
+    #[clap(long, required = false)]
+    threads: u32,
+
+    /// Cache directory
+    #[clap(long, env = "SHOWN_CACHE")]
+    cache: Option<String>,
+
+    /// Access token
+    #[clap(long, env = "SHOWN_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[test]
+fn show_defaults_and_env() {
+    std::env::set_var("SHOWN_CACHE", "/tmp/cache");
+    std::env::set_var("SHOWN_TOKEN", "hunter2");
+    let help = get_help::<Shown>();
+    std::env::remove_var("SHOWN_CACHE");
+    std::env::remove_var("SHOWN_TOKEN");
+
+    assert!(help.contains("[default: 0]"));
+    assert!(help.contains("[env: SHOWN_CACHE=/tmp/cache]"));
+    assert!(help.contains("[env: SHOWN_TOKEN]"));
+    assert!(!help.contains("hunter2"));
+    assert!(!help.contains("[env: THREADS"));
+    assert!(!help.contains("[env: VERBOSE"));
+
+    assert_eq!(
+        Shown {
+            threads: 0,
+            cache: None,
+            token: None,
+            verbose: false
+        },
+        Shown::parse_from(&["test"])
+    );
+}
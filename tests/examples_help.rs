@@ -0,0 +1,79 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(
+    name = "mytool",
+    example = "mytool build --release",
+    example = "mytool build --target wasm32"
+)]
+struct Opt {
+    #[clap(long)]
+    release: bool,
+}
+
+#[test]
+fn examples_section_in_long_help() {
+    assert!(get_long_help::<Opt>()
+        .contains("EXAMPLES:\n    mytool build --release\n    mytool build --target wasm32"));
+    assert!(!get_help::<Opt>().contains("EXAMPLES:"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(
+    name = "mytool",
+    after_long_help = "Report bugs upstream.",
+    example = "mytool --addr 0.0.0.0"
+)]
+struct WithEnv {
+    /// Address to listen on
+    #[clap(long, env = "EXAMPLES_HELP_ADDR")]
+    addr: String,
+}
+
+#[test]
+fn examples_go_between_after_long_help_and_environment() {
+    assert!(get_long_help::<WithEnv>().contains(concat!(
+        "Report bugs upstream.\n\n",
+        "EXAMPLES:\n    mytool --addr 0.0.0.0\n\n",
+        "ENVIRONMENT:\n    EXAMPLES_HELP_ADDR    Address to listen on"
+    )));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "mytool", example = "mytool build")]
+enum Cmd {
+    #[clap(example = "mytool build --release")]
+    Build {
+        #[clap(long)]
+        release: bool,
+    },
+}
+
+#[test]
+fn examples_on_enums_and_variants() {
+    assert!(get_long_help::<Cmd>().contains("EXAMPLES:\n    mytool build"));
+    assert!(get_subcommand_long_help::<Cmd>("build")
+        .contains("EXAMPLES:\n    mytool build --release"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "mytool")]
+struct FieldExamples {
+    /// Region to deploy to
+    #[clap(long, example = "eu-west-1")]
+    region: String,
+
+    #[clap(long, example = "8080", example = "8443")]
+    port: u16,
+}
+
+#[test]
+fn field_examples_in_help() {
+    let help = get_help::<FieldExamples>();
+    assert!(help.contains("Region to deploy to [e.g. eu-west-1]"));
+    assert!(help.contains("[e.g. 8080, 8443]"));
+    assert!(!help.contains("EXAMPLES:"));
+}
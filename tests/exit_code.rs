@@ -0,0 +1,61 @@
+use clap::Clap;
+use std::env;
+use std::process::Command;
+
+#[derive(Clap, Debug)]
+#[clap(error_exit_code = 2, usage_exit_code = 64)]
+struct Opt {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[derive(Clap, Debug)]
+#[clap(error_exit_code = 3)]
+struct OnlyError {
+    #[clap(long)]
+    verbose: bool,
+}
+
+const CHILD_ARGS: &str = "EXIT_CODE_TEST_ARGS";
+
+/// Run the test named `test` again in a child process, which parses the
+/// arguments given in `args` and exits.
+fn exit_code_of(test: &str, args: &str) -> Option<i32> {
+    let status = Command::new(env::current_exe().unwrap())
+        .args(&[test, "--exact", "--nocapture"])
+        .env(CHILD_ARGS, args)
+        .status()
+        .unwrap();
+    status.code()
+}
+
+fn child_args() -> Option<Vec<String>> {
+    env::var(CHILD_ARGS).ok().map(|args| {
+        std::iter::once("test".to_string())
+            .chain(args.split_whitespace().map(String::from))
+            .collect()
+    })
+}
+
+#[test]
+fn usage_error_exit_code() {
+    if let Some(args) = child_args() {
+        Opt::parse_from(args);
+        return;
+    }
+    assert_eq!(Some(64), exit_code_of("usage_error_exit_code", "--unknown"));
+    assert_eq!(Some(0), exit_code_of("usage_error_exit_code", "--help"));
+    assert_eq!(Some(0), exit_code_of("usage_error_exit_code", "--verbose"));
+}
+
+#[test]
+fn error_exit_code_covers_usage_errors() {
+    if let Some(args) = child_args() {
+        OnlyError::parse_from(args);
+        return;
+    }
+    assert_eq!(
+        Some(3),
+        exit_code_of("error_exit_code_covers_usage_errors", "--unknown")
+    );
+}
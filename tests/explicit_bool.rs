@@ -0,0 +1,56 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, explicit_bool)]
+    cache: bool,
+
+    #[clap(long, explicit_bool, env = "EXPLICIT_BOOL_TEST_COLOR")]
+    color: bool,
+}
+
+#[test]
+fn explicit_values() {
+    assert_eq!(
+        Opt {
+            cache: false,
+            color: false
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            cache: true,
+            color: false
+        },
+        Opt::parse_from(&["test", "--cache"])
+    );
+    assert_eq!(
+        Opt {
+            cache: true,
+            color: false
+        },
+        Opt::parse_from(&["test", "--cache=true"])
+    );
+    assert_eq!(
+        Opt {
+            cache: false,
+            color: true
+        },
+        Opt::parse_from(&["test", "--cache=false", "--color"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--cache=yes"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--cache", "false"]).is_err());
+}
+
+#[test]
+fn explicit_value_from_env() {
+    std::env::set_var("EXPLICIT_BOOL_TEST_COLOR", "false");
+    assert!(!Opt::parse_from(&["test"]).color);
+    assert!(Opt::parse_from(&["test", "--color=true"]).color);
+
+    std::env::set_var("EXPLICIT_BOOL_TEST_COLOR", "true");
+    assert!(Opt::parse_from(&["test"]).color);
+    assert!(!Opt::parse_from(&["test", "--color=false"]).color);
+    std::env::remove_var("EXPLICIT_BOOL_TEST_COLOR");
+}
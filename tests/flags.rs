@@ -59,6 +59,102 @@ fn multiple_flag() {
     assert!(Opt::try_parse_from(&["test", "-a", "foo"]).is_err());
 }
 
+#[test]
+fn occurrences_saturate() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, parse(from_occurrences))]
+        verbose: i8,
+    }
+
+    let args: Vec<_> = std::iter::once("test")
+        .chain(std::iter::repeat("-v").take(300))
+        .collect();
+    assert_eq!(Opt { verbose: 127 }, Opt::parse_from(&args));
+    assert_eq!(Opt { verbose: 3 }, Opt::parse_from(&["test", "-vvv"]));
+}
+
+#[test]
+fn occurrences_float() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, parse(from_occurrences))]
+        verbose: f64,
+    }
+
+    assert_eq!(Opt { verbose: 0.0 }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { verbose: 3.0 }, Opt::parse_from(&["test", "-vvv"]));
+}
+
+#[test]
+fn max_occurrences() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, parse(from_occurrences), max_occurrences = 2)]
+        verbose: u8,
+        #[clap(short, long, max_occurrences = 2)]
+        include: Vec<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            verbose: 2,
+            include: vec!["a".into(), "b".into()]
+        },
+        Opt::parse_from(&["test", "-vv", "-i", "a", "--include", "b"])
+    );
+    let err = Opt::try_parse_from(&["test", "-vvv"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::UnexpectedMultipleUsage);
+    assert!(err
+        .message
+        .contains("The argument '-v' was provided more than 2 times"));
+    let err = Opt::try_parse_from(&["test", "-ia", "-ib", "-ic"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("The argument '--include' was provided more than 2 times"));
+}
+
+#[test]
+fn occurrences_to_levels() {
+    #[derive(PartialEq, Debug)]
+    enum Level {
+        Warn,
+        Info,
+        Debug,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, levels = [Level::Warn, Level::Info, Level::Debug])]
+        verbose: Level,
+    }
+
+    assert_eq!(
+        Opt {
+            verbose: Level::Warn
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            verbose: Level::Info
+        },
+        Opt::parse_from(&["test", "-v"])
+    );
+    assert_eq!(
+        Opt {
+            verbose: Level::Debug
+        },
+        Opt::parse_from(&["test", "-vv"])
+    );
+    assert_eq!(
+        Opt {
+            verbose: Level::Debug
+        },
+        Opt::parse_from(&["test", "-vvvv"])
+    );
+}
+
 fn parse_from_flag(b: bool) -> std::sync::atomic::AtomicBool {
     std::sync::atomic::AtomicBool::new(b)
 }
@@ -143,3 +239,106 @@ fn combined_flags() {
         Opt::parse_from(&["test", "-bb", "-a", "-bb"])
     );
 }
+
+#[test]
+fn non_ascii_short_flags() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short = "é")]
+        ecrire: bool,
+        #[clap(short = "ü", parse(from_occurrences))]
+        uber: u8,
+    }
+
+    assert_eq!(
+        Opt {
+            ecrire: true,
+            uber: 2
+        },
+        Opt::parse_from(&["test", "-é", "-üü"])
+    );
+}
+
+#[test]
+fn digit_short_flags() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short = "1")]
+        one_per_line: bool,
+        #[clap(short = "2", long)]
+        two: bool,
+    }
+
+    assert_eq!(
+        Opt {
+            one_per_line: true,
+            two: false
+        },
+        Opt::parse_from(&["test", "-1"])
+    );
+    assert_eq!(
+        Opt {
+            one_per_line: true,
+            two: true
+        },
+        Opt::parse_from(&["test", "-12"])
+    );
+}
+
+#[test]
+fn verbosity() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(verbosity)]
+        level: i8,
+    }
+
+    assert_eq!(Opt { level: 0 }, Opt::parse_from(&["test"]));
+    assert_eq!(Opt { level: 2 }, Opt::parse_from(&["test", "-vv"]));
+    assert_eq!(Opt { level: -1 }, Opt::parse_from(&["test", "--quiet"]));
+    assert_eq!(Opt { level: 1 }, Opt::parse_from(&["test", "-v", "-q", "--verbose"]));
+
+    let args: Vec<_> = std::iter::once("test")
+        .chain(std::iter::repeat("-q").take(300))
+        .collect();
+    assert_eq!(Opt { level: -128 }, Opt::parse_from(&args));
+}
+
+#[test]
+fn verbosity_quiet_follows_the_field() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(verbosity, short = "d", long = "debug")]
+        level: i8,
+        #[clap(short)]
+        quick: bool,
+    }
+
+    assert_eq!(
+        Opt {
+            level: 1,
+            quick: true
+        },
+        Opt::parse_from(&["test", "-dd", "--debug-quiet", "-q"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--quiet"]).is_err());
+}
+
+#[test]
+fn verbosity_mut_quiet() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(verbosity, mut_quiet = |arg: clap::Arg| arg.short('s').long("silent").help("Hush"))]
+        level: i8,
+        #[clap(short, long)]
+        quiet: bool,
+    }
+
+    assert_eq!(
+        Opt {
+            level: -2,
+            quiet: true
+        },
+        Opt::parse_from(&["test", "-s", "--silent", "-q"])
+    );
+}
@@ -97,3 +97,221 @@ fn flatten_in_subcommand() {
         Opt::parse_from(&["test", "add", "-i", "43"])
     );
 }
+
+#[test]
+fn flatten_with_env_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Database {
+        #[clap(long, env = "HOST", default_value = "localhost")]
+        host: String,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, env_prefix = "FLATTEN_TEST_DB")]
+        db: Database,
+    }
+
+    std::env::set_var("FLATTEN_TEST_DB_HOST", "db.example.com");
+    assert_eq!(
+        Opt {
+            db: Database {
+                host: "db.example.com".into()
+            }
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt::__clap_env_args(),
+        vec![("host", "FLATTEN_TEST_DB_HOST", "")]
+    );
+    std::env::remove_var("FLATTEN_TEST_DB_HOST");
+}
+
+#[test]
+fn flatten_with_long_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(long_prefix = "log")]
+    struct Logging {
+        #[clap(long, default_value = "info")]
+        level: String,
+        #[clap(long)]
+        file: Option<String>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: u8,
+        #[clap(flatten)]
+        logging: Logging,
+    }
+
+    assert_eq!(
+        Opt {
+            level: 3,
+            logging: Logging {
+                level: "debug".into(),
+                file: Some("out.log".into()),
+            }
+        },
+        Opt::parse_from(&[
+            "test",
+            "--level",
+            "3",
+            "--log-level",
+            "debug",
+            "--log-file",
+            "out.log"
+        ])
+    );
+}
+
+#[test]
+fn flatten_twice_with_prefix() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Database {
+        #[clap(long, env = "HOST", default_value = "localhost")]
+        host: String,
+        #[clap(long)]
+        port: Option<u16>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "flatten-src")]
+        source: Database,
+        #[clap(flatten, prefix = "flatten-dest")]
+        destination: Database,
+    }
+
+    std::env::set_var("FLATTEN_DEST_HOST", "backup.example.com");
+    assert_eq!(
+        Opt {
+            source: Database {
+                host: "db.example.com".into(),
+                port: Some(5432),
+            },
+            destination: Database {
+                host: "backup.example.com".into(),
+                port: None,
+            },
+        },
+        Opt::parse_from(&[
+            "test",
+            "--flatten-src-host",
+            "db.example.com",
+            "--flatten-src-port",
+            "5432"
+        ])
+    );
+    std::env::remove_var("FLATTEN_DEST_HOST");
+}
+
+#[test]
+fn flatten_twice_with_prefix_drops_shorts() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Database {
+        #[clap(short, long)]
+        port: Option<u16>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "src")]
+        source: Database,
+        #[clap(flatten, prefix = "dest")]
+        destination: Database,
+    }
+
+    assert_eq!(
+        Opt {
+            source: Database { port: Some(5432) },
+            destination: Database { port: None },
+        },
+        Opt::parse_from(&["test", "--src-port", "5432"])
+    );
+    assert!(Opt::try_parse_from(&["test", "-p", "5432"]).is_err());
+}
+
+#[test]
+fn flatten_nested_prefixes() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Database {
+        #[clap(long, env = "HOST", default_value = "localhost")]
+        host: String,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Service {
+        #[clap(flatten, prefix = "db")]
+        database: Database,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, prefix = "flatten-nested")]
+        service: Service,
+    }
+
+    std::env::set_var("FLATTEN_NESTED_DB_HOST", "db.example.com");
+    assert_eq!(
+        Opt {
+            service: Service {
+                database: Database {
+                    host: "db.example.com".into(),
+                },
+            },
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            service: Service {
+                database: Database {
+                    host: "other.example.com".into(),
+                },
+            },
+        },
+        Opt::parse_from(&["test", "--flatten-nested-db-host", "other.example.com"])
+    );
+    std::env::remove_var("FLATTEN_NESTED_DB_HOST");
+}
+
+#[test]
+fn flatten_conflicts_and_requires() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Remote {
+        #[clap(long)]
+        host: Option<String>,
+        #[clap(long)]
+        port: Option<u16>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Local {
+        #[clap(long)]
+        socket: Option<String>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Auth {
+        #[clap(long)]
+        token: Option<String>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten, conflicts_with = "local", requires = "auth")]
+        remote: Remote,
+        #[clap(flatten)]
+        local: Local,
+        #[clap(flatten)]
+        auth: Auth,
+    }
+
+    assert!(Opt::try_parse_from(&["test", "--socket", "s"]).is_ok());
+    assert!(Opt::try_parse_from(&["test", "--port", "1", "--token", "t"]).is_ok());
+    assert!(Opt::try_parse_from(&["test", "--port", "1", "--socket", "s"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--host", "h"]).is_err());
+}
@@ -0,0 +1,113 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(
+    name = "outil",
+    help_message = "Affiche l'aide",
+    version_message = "Affiche la version"
+)]
+struct Opt {
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    /// Lance la tâche
+    Lancer {
+        #[clap(long)]
+        vite: bool,
+    },
+}
+
+#[test]
+fn help_and_version_messages() {
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("Affiche l'aide"));
+    assert!(help.contains("Affiche la version"));
+    assert!(!help.contains("Prints help information"));
+}
+
+#[test]
+fn messages_are_propagated_to_subcommands() {
+    let help = get_subcommand_long_help::<Opt>("lancer");
+    assert!(help.contains("Affiche l'aide"));
+    assert!(!help.contains("Prints help information"));
+}
+
+#[test]
+fn flags_still_work() {
+    assert_eq!(
+        Opt {
+            cmd: Command::Lancer { vite: true }
+        },
+        Opt::parse_from(&["outil", "lancer", "--vite"])
+    );
+    assert!(Opt::try_parse_from(&["outil", "--help"]).is_err());
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "outil", version_message = "Affiche la version")]
+struct Versioned {
+    #[clap(subcommand)]
+    cmd: Verbose,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Verbose {
+    Lancer {
+        #[clap(short = "V", long)]
+        verbeux: bool,
+    },
+}
+
+#[test]
+fn short_taken_by_a_subcommand_is_kept() {
+    assert_eq!(
+        Versioned {
+            cmd: Verbose::Lancer { verbeux: true }
+        },
+        Versioned::parse_from(&["outil", "lancer", "-V"])
+    );
+    let help = get_subcommand_long_help::<Versioned>("lancer");
+    assert!(help.contains("--version"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(
+    name = "outil",
+    version_message = "Affiche la version",
+    setting = clap::AppSettings::VersionlessSubcommands
+)]
+struct Versionless {
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[test]
+fn versionless_subcommands_get_no_version_flag() {
+    let help = get_subcommand_long_help::<Versionless>("lancer");
+    assert!(!help.contains("Affiche la version"));
+    assert!(!help.contains("--version"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "outil", help_message = "Affiche l'aide")]
+struct Hauteur {
+    #[clap(short = "h", long)]
+    hauteur: u32,
+}
+
+#[test]
+fn short_taken_by_an_argument_is_kept() {
+    assert_eq!(
+        Hauteur { hauteur: 3 },
+        Hauteur::parse_from(&["outil", "-h", "3"])
+    );
+    let help = get_long_help::<Hauteur>();
+    assert!(help.contains("Affiche l'aide"));
+    assert!(help.contains("--help"));
+}
@@ -0,0 +1,59 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(help_template = "shells: {usage}")]
+struct Completions {
+    shell: String,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", help_template = "top level: {usage}")]
+enum Opt {
+    #[clap(help_template = "run: {usage}")]
+    Run {
+        #[clap(long)]
+        fast: bool,
+    },
+    Completions(Completions),
+    Build,
+}
+
+fn subcommand_help(name: &str) -> String {
+    Opt::try_parse_from(&["tool", name, "--help"])
+        .unwrap_err()
+        .message
+}
+
+#[test]
+fn top_level_template() {
+    assert!(get_help::<Opt>().starts_with("top level: "));
+}
+
+#[test]
+fn variant_template() {
+    assert!(subcommand_help("run").starts_with("run: "));
+}
+
+#[test]
+fn template_of_the_wrapped_struct() {
+    assert!(subcommand_help("completions").starts_with("shells: "));
+}
+
+#[test]
+fn templates_are_not_inherited() {
+    assert!(!subcommand_help("build").contains("top level: "));
+}
+
+#[test]
+fn template_is_not_applied_when_flattened() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Outer {
+        #[clap(flatten)]
+        completions: Completions,
+    }
+
+    assert!(!get_help::<Outer>().contains("shells: "));
+}
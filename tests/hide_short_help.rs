@@ -0,0 +1,81 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Tuning {
+    /// Size of the read buffer
+    #[clap(long)]
+    buffer_size: Option<usize>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(hide_short_help)]
+struct Advanced {
+    /// Number of retries on failure
+    #[clap(long)]
+    retries: Option<u32>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    /// Input file
+    #[clap(long)]
+    input: String,
+
+    #[clap(flatten, hide_short_help)]
+    tuning: Tuning,
+
+    #[clap(flatten)]
+    advanced: Advanced,
+}
+
+#[test]
+fn only_in_long_help() {
+    let short_help = get_help::<Opt>();
+    assert!(short_help.contains("--input"));
+    assert!(!short_help.contains("--buffer-size"));
+    assert!(!short_help.contains("--retries"));
+
+    let long_help = get_long_help::<Opt>();
+    assert!(long_help.contains("--input"));
+    assert!(long_help.contains("--buffer-size"));
+    assert!(long_help.contains("--retries"));
+}
+
+#[test]
+fn hidden_arguments_still_parse() {
+    assert_eq!(
+        Opt {
+            input: "a".into(),
+            tuning: Tuning {
+                buffer_size: Some(8)
+            },
+            advanced: Advanced { retries: Some(3) },
+        },
+        Opt::parse_from(&["test", "--input", "a", "--buffer-size", "8", "--retries", "3"])
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Fields {
+    /// Shown in -h only
+    #[clap(long, hide_long_help)]
+    summary: bool,
+
+    /// Shown in --help only
+    #[clap(long, hide_short_help)]
+    details: bool,
+}
+
+#[test]
+fn per_field() {
+    let short_help = get_help::<Fields>();
+    assert!(short_help.contains("--summary"));
+    assert!(!short_help.contains("--details"));
+
+    let long_help = get_long_help::<Fields>();
+    assert!(!long_help.contains("--summary"));
+    assert!(long_help.contains("--details"));
+}
@@ -0,0 +1,85 @@
+use clap::Clap;
+use std::num::{NonZeroI64, NonZeroU32};
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long)]
+    id: u128,
+
+    #[clap(long, default_value = "0")]
+    offset: i128,
+
+    #[clap(long)]
+    size: Option<usize>,
+
+    #[clap(long)]
+    delta: Vec<isize>,
+
+    #[clap(long, default_value = "0")]
+    small: u8,
+}
+
+#[test]
+fn wide_integers() {
+    assert_eq!(
+        Opt {
+            id: 340_282_366_920_938_463_463_374_607_431_768_211_455,
+            offset: -170_141_183_460_469_231_731_687_303_715_884_105_728,
+            size: Some(4096),
+            delta: vec![-1, 2],
+            small: 0,
+        },
+        Opt::parse_from(&[
+            "test",
+            "--id",
+            "340282366920938463463374607431768211455",
+            "--offset=-170141183460469231731687303715884105728",
+            "--size",
+            "4096",
+            "--delta=-1",
+            "--delta",
+            "2",
+        ])
+    );
+}
+
+#[test]
+fn out_of_range_errors_give_the_bounds() {
+    let err = Opt::try_parse_from(&["test", "--id", "1", "--small", "300"]).unwrap_err();
+    assert!(err.message.contains("expected an integer from 0 to 255"));
+
+    let err = Opt::try_parse_from(&["test", "--id=-1"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("expected an integer from 0 to 340282366920938463463374607431768211455"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct NonZero {
+    #[clap(long)]
+    workers: NonZeroU32,
+
+    #[clap(long)]
+    step: Option<NonZeroI64>,
+}
+
+#[test]
+fn non_zero_integers() {
+    assert_eq!(
+        NonZero {
+            workers: NonZeroU32::new(8).unwrap(),
+            step: NonZeroI64::new(-2),
+        },
+        NonZero::parse_from(&["test", "--workers", "8", "--step=-2"])
+    );
+
+    let err = NonZero::try_parse_from(&["test", "--workers", "0"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("expected an integer from 1 to 4294967295"));
+
+    let err = NonZero::try_parse_from(&["test", "--workers", "1", "--step", "0"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("expected a non-zero integer from -9223372036854775808 to 9223372036854775807"));
+}
@@ -0,0 +1,56 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", lazy)]
+enum Cmd {
+    /// Build the project
+    Build {
+        #[clap(long)]
+        release: bool,
+    },
+
+    /// Run the tests
+    #[clap(alias = "t")]
+    Test {
+        #[clap(long)]
+        filter: Option<String>,
+    },
+}
+
+#[test]
+fn lazy_subcommands_parse() {
+    assert_eq!(
+        Cmd::Build { release: true },
+        Cmd::parse_from(&["tool", "build", "--release"])
+    );
+    assert_eq!(
+        Cmd::Test {
+            filter: Some("foo".into())
+        },
+        Cmd::parse_from(&["tool", "t", "--filter", "foo"])
+    );
+}
+
+#[test]
+fn unnamed_subcommands_are_not_built() {
+    use clap::ErrorKind;
+
+    let app = Cmd::__clap_lazy_app(&["test".into()]);
+    let err = app
+        .try_get_matches_from(&["tool", "build", "--release"])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnknownArgument);
+}
+
+#[test]
+fn lazy_subcommands_are_listed_in_help() {
+    let help = get_help::<Cmd>();
+    assert!(help.contains("Build the project"));
+    assert!(help.contains("Run the tests"));
+
+    let err = Cmd::try_parse_from(&["tool", "help", "build"]).unwrap_err();
+    assert!(err.message.contains("--release"));
+}
@@ -0,0 +1,96 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+fn french(key: &str) -> Option<String> {
+    match key {
+        "app.about" => Some("Un outil de démonstration".into()),
+        "args.output.help" => Some("Fichier de sortie".into()),
+        "args.output.help.long" => Some("Fichier où écrire le résultat".into()),
+        "commands.fetch.help" => Some("Récupère les objets distants".into()),
+        _ => None,
+    }
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", localizer = french, help_key = "app.about")]
+/// A demonstration tool
+struct Opt {
+    /// Output file
+    #[clap(long, help_key = "args.output.help")]
+    output: String,
+
+    /// Be verbose
+    #[clap(short, help_key = "args.verbose.help")]
+    verbose: bool,
+}
+
+#[test]
+fn help_is_translated() {
+    let help = get_help::<Opt>();
+    assert!(help.contains("Un outil de démonstration"));
+    assert!(help.contains("Fichier de sortie"));
+    assert!(!help.contains("A demonstration tool"));
+    assert!(!help.contains("Output file"));
+}
+
+#[test]
+fn long_help_is_translated() {
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("Fichier où écrire le résultat"));
+}
+
+#[test]
+fn unknown_keys_fall_back_to_doc_comments() {
+    let help = get_help::<Opt>();
+    assert!(help.contains("Be verbose"));
+}
+
+#[test]
+fn subcommand_about_is_translated() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(localizer = french)]
+    enum Opt {
+        /// Fetch remote objects
+        #[clap(help_key = "commands.fetch.help")]
+        Fetch,
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("Récupère les objets distants"));
+    assert!(!help.contains("Fetch remote objects"));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Output {
+    /// Output file
+    #[clap(long, help_key = "args.output.help")]
+    output: String,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    /// Fetch remote objects
+    #[clap(help_key = "commands.fetch.help")]
+    Fetch,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool", localizer = french)]
+struct Tool {
+    #[clap(flatten)]
+    output: Output,
+
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+#[test]
+fn flattened_and_subcommand_types_use_the_localizer() {
+    let help = get_help::<Tool>();
+    assert!(help.contains("Fichier de sortie"));
+    assert!(!help.contains("Output file"));
+    assert!(help.contains("Récupère les objets distants"));
+    assert!(!help.contains("Fetch remote objects"));
+}
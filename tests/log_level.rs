@@ -0,0 +1,119 @@
+#![cfg(feature = "log")]
+
+mod utils;
+
+use clap::Clap;
+use log::{Level, LevelFilter};
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, default_value = "warn")]
+    log_level: log::LevelFilter,
+
+    #[clap(long)]
+    max_level: Option<log::Level>,
+}
+
+#[test]
+fn log_levels_parsed() {
+    assert_eq!(
+        Opt {
+            log_level: LevelFilter::Warn,
+            max_level: None,
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            log_level: LevelFilter::Debug,
+            max_level: Some(Level::Error),
+        },
+        Opt::parse_from(&["test", "--log-level", "DEBUG", "--max-level", "error"])
+    );
+}
+
+#[test]
+fn log_levels_are_possible_values() {
+    assert!(Opt::try_parse_from(&["test", "--log-level", "loud"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--max-level", "off"]).is_err());
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("[possible values: off, error, warn, info, debug, trace]"));
+}
+
+#[test]
+fn tracing_levels_are_possible_values() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: Option<tracing::Level>,
+    }
+
+    assert_eq!(
+        Opt {
+            level: Some(tracing::Level::DEBUG)
+        },
+        Opt::parse_from(&["test", "--level", "Debug"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--level", "off"]).is_err());
+}
+
+#[test]
+fn other_level_types_are_left_alone() {
+    #[derive(Debug, PartialEq)]
+    struct Level(u8);
+
+    impl std::str::FromStr for Level {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Level)
+        }
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        level: Level,
+    }
+
+    assert_eq!(
+        Opt { level: Level(3) },
+        Opt::parse_from(&["test", "--level", "3"])
+    );
+    assert!(!get_long_help::<Opt>().contains("possible values"));
+}
+
+#[test]
+fn tracing_filter_from_rust_log() {
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(long, env = "RUST_LOG")]
+        filter: tracing_subscriber::EnvFilter,
+    }
+
+    std::env::set_var("RUST_LOG", "info,my_crate=debug");
+    let opt = Opt::try_parse_from(&["test"]);
+    std::env::remove_var("RUST_LOG");
+    assert!(opt.unwrap().filter.to_string().contains("my_crate=debug"));
+
+    let opt = Opt::parse_from(&["test", "--filter", "warn"]);
+    assert!(opt.filter.to_string().contains("warn"));
+    assert!(Opt::try_parse_from(&["test", "--filter", "my_crate=loud"]).is_err());
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("--filter <FILTER>"));
+    assert!(help.contains("[env: RUST_LOG"));
+}
+
+#[test]
+fn tracing_filter_not_read_from_rust_log_by_default() {
+    #[derive(Clap, Debug)]
+    struct Opt {
+        #[clap(long)]
+        filter: Option<tracing_subscriber::EnvFilter>,
+    }
+
+    assert!(!get_long_help::<Opt>().contains("RUST_LOG"));
+}
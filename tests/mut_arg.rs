@@ -0,0 +1,30 @@
+use clap::{Arg, Clap};
+
+fn with_alias(arg: Arg) -> Arg {
+    arg.alias("threads")
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, default_value = "1", mut_arg = with_alias)]
+    jobs: u32,
+
+    #[clap(long, mut_arg = |arg: Arg| arg.required(true))]
+    name: Option<String>,
+}
+
+#[test]
+fn mut_arg_runs_on_the_generated_arg() {
+    assert_eq!(
+        Opt {
+            jobs: 4,
+            name: Some("x".into()),
+        },
+        Opt::parse_from(&["test", "--threads", "4", "--name", "x"])
+    );
+}
+
+#[test]
+fn mut_arg_overrides_derived_settings() {
+    assert!(Opt::try_parse_from(&["test", "--jobs", "4"]).is_err());
+}
@@ -0,0 +1,38 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, trim)]
+    name: Option<String>,
+
+    #[clap(long, lowercase)]
+    region: Option<String>,
+
+    #[clap(long, trim, lowercase, possible_values = &["debug", "release"])]
+    profile: Option<String>,
+
+    #[clap(long, trim, default_value = "1")]
+    jobs: u32,
+}
+
+#[test]
+fn values_are_normalized_before_parsing() {
+    assert_eq!(
+        Opt {
+            name: Some("alice".into()),
+            region: Some("eu-west".into()),
+            profile: None,
+            jobs: 4,
+        },
+        Opt::parse_from(&["test", "--name", " alice\n", "--region", "EU-West", "--jobs", "4 "])
+    );
+}
+
+#[test]
+fn lowercase_makes_possible_values_case_insensitive() {
+    assert_eq!(
+        Some("release".into()),
+        Opt::parse_from(&["test", "--profile", "Release"]).profile
+    );
+    assert!(Opt::try_parse_from(&["test", "--profile", "fast"]).is_err());
+}
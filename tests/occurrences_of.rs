@@ -0,0 +1,42 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(short, long)]
+    file: Vec<String>,
+
+    #[clap(occurrences_of = "file")]
+    file_count: usize,
+
+    #[clap(long)]
+    color: Option<String>,
+
+    #[clap(occurrences_of = "color")]
+    color_count: u8,
+}
+
+#[test]
+fn counts_the_occurrences_of_a_sibling() {
+    assert_eq!(
+        Opt {
+            file: vec!["a".into(), "b".into(), "c".into()],
+            file_count: 2,
+            color: Some("always".into()),
+            color_count: 1,
+        },
+        Opt::parse_from(&["test", "-f", "a", "b", "--file", "c", "--color", "always"])
+    );
+}
+
+#[test]
+fn counts_nothing_when_absent() {
+    assert_eq!(
+        Opt {
+            file: vec![],
+            file_count: 0,
+            color: None,
+            color_count: 0,
+        },
+        Opt::parse_from(&["test"])
+    );
+}
@@ -93,6 +93,31 @@ fn default_value() {
     );
 }
 
+#[test]
+fn not_required_falls_back_to_default() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, required = false)]
+        arg: u32,
+        #[clap(required = false)]
+        name: String,
+    }
+    assert_eq!(
+        Opt {
+            arg: 0,
+            name: String::new()
+        },
+        Opt::parse_from(&["test"])
+    );
+    assert_eq!(
+        Opt {
+            arg: 42,
+            name: "foo".into()
+        },
+        Opt::parse_from(&["test", "-a42", "foo"])
+    );
+}
+
 #[test]
 fn option_from_str() {
     #[derive(Debug, PartialEq)]
@@ -135,6 +160,25 @@ fn optional_argument_for_optional_option() {
     assert!(Opt::try_parse_from(&["test", "-a42", "-a24"]).is_err());
 }
 
+#[test]
+fn optional_optional_positional_with_missing_value() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(default_missing_value = "-")]
+        #[allow(clippy::option_option)]
+        arg: Option<Option<i32>>,
+    }
+    assert_eq!(
+        Opt {
+            arg: Some(Some(42))
+        },
+        Opt::parse_from(&["test", "42"])
+    );
+    assert_eq!(Opt { arg: Some(None) }, Opt::parse_from(&["test", "-"]));
+    assert_eq!(Opt { arg: None }, Opt::parse_from(&["test"]));
+    assert!(Opt::try_parse_from(&["test", "x"]).is_err());
+}
+
 #[test]
 fn two_option_options() {
     #[derive(Clap, PartialEq, Debug)]
@@ -283,3 +327,172 @@ fn two_optional_vecs() {
 
     assert_eq!(Opt { arg: None, b: None }, Opt::parse_from(&["test"]));
 }
+
+#[test]
+fn requires_all_together() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(requires_all = ["user", "password"])]
+    struct Opt {
+        #[clap(long)]
+        user: Option<String>,
+        #[clap(long)]
+        password: Option<String>,
+        #[clap(long)]
+        host: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            user: Some("me".into()),
+            password: Some("secret".into()),
+            host: None
+        },
+        Opt::parse_from(&["test", "--user", "me", "--password", "secret"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--host", "h"]).is_ok());
+    assert!(Opt::try_parse_from(&["test", "--user", "me"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--password", "secret"]).is_err());
+}
+
+#[test]
+fn required_unless_any_or_all() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, required_unless_any = ["stdin", "url"])]
+        file: Option<String>,
+        #[clap(long)]
+        stdin: bool,
+        #[clap(long)]
+        url: Option<String>,
+        #[clap(long, required_unless_all = ["stdin", "url"])]
+        name: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            file: None,
+            stdin: true,
+            url: Some("u".into()),
+            name: None
+        },
+        Opt::parse_from(&["test", "--stdin", "--url", "u"])
+    );
+    assert_eq!(
+        Opt {
+            file: Some("f".into()),
+            stdin: false,
+            url: None,
+            name: Some("n".into())
+        },
+        Opt::parse_from(&["test", "--file", "f", "--name", "n"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--name", "n"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--stdin"]).is_err());
+}
+
+#[test]
+fn default_value_ifs() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long)]
+        mode: Option<String>,
+        #[clap(long)]
+        dry_run: bool,
+        #[clap(
+            long,
+            default_value = "2",
+            default_value_ifs = [("mode", "fast", "8"), ("mode", "slow", "1")]
+        )]
+        jobs: u32,
+        #[clap(long, default_value_ifs = [("dry_run", None, "none")])]
+        output: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            mode: Some("fast".into()),
+            dry_run: false,
+            jobs: 8,
+            output: None
+        },
+        Opt::parse_from(&["test", "--mode", "fast"])
+    );
+    assert_eq!(
+        Opt {
+            mode: Some("slow".into()),
+            dry_run: true,
+            jobs: 1,
+            output: Some("none".into())
+        },
+        Opt::parse_from(&["test", "--mode", "slow", "--dry-run"])
+    );
+    assert_eq!(
+        Opt {
+            mode: None,
+            dry_run: false,
+            jobs: 2,
+            output: None
+        },
+        Opt::parse_from(&["test"])
+    );
+}
+
+#[test]
+fn requires_if_and_required_if() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, requires_if("tls", "cert"))]
+        transport: Option<String>,
+        #[clap(long, required_if("transport", "tls"))]
+        key: Option<String>,
+        #[clap(long)]
+        cert: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            transport: Some("tls".into()),
+            key: Some("k".into()),
+            cert: Some("c".into())
+        },
+        Opt::parse_from(&["test", "--transport", "tls", "--key", "k", "--cert", "c"])
+    );
+    assert_eq!(
+        Opt {
+            transport: Some("tcp".into()),
+            key: None,
+            cert: None
+        },
+        Opt::parse_from(&["test", "--transport", "tcp"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--transport", "tls", "--key", "k"]).is_err());
+    assert!(Opt::try_parse_from(&["test", "--transport", "tls", "--cert", "c"]).is_err());
+}
+
+#[test]
+fn required_if_names_flattened_args() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Transport {
+        #[clap(long)]
+        transport: Option<String>,
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(flatten)]
+        net: Transport,
+        #[clap(long, required_if("transport", "tls"))]
+        key: Option<String>,
+    }
+
+    assert_eq!(
+        Opt {
+            net: Transport {
+                transport: Some("tcp".into())
+            },
+            key: None
+        },
+        Opt::parse_from(&["test", "--transport", "tcp"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--transport", "tls"]).is_err());
+}
@@ -0,0 +1,32 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "copy", override_usage = "copy [OPTIONS] <SRC>... <DEST>")]
+struct Copy {
+    #[clap(short)]
+    force: bool,
+
+    sources: Vec<String>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool")]
+enum Tool {
+    #[clap(override_usage = "tool move <SRC>... <DEST>")]
+    Move { paths: Vec<String> },
+}
+
+#[test]
+fn override_usage_on_struct() {
+    let help = get_help::<Copy>();
+    assert!(help.contains("copy [OPTIONS] <SRC>... <DEST>"));
+}
+
+#[test]
+fn override_usage_on_variant() {
+    let help = get_subcommand_long_help::<Tool>("move");
+    assert!(help.contains("tool move <SRC>... <DEST>"));
+}
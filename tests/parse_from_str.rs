@@ -0,0 +1,58 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(parse_from_str)]
+struct Opt {
+    #[clap(short, long)]
+    message: String,
+
+    files: Vec<String>,
+}
+
+#[test]
+fn words_are_split_like_a_shell() {
+    assert_eq!(
+        Opt {
+            message: "hello world".into(),
+            files: vec!["a b".into(), "c".into(), "it's".into(), "$HOME".into()],
+        },
+        Opt::parse_from_str(r#"-m "hello world" 'a b'  c it\'s "\$HOME""#)
+    );
+}
+
+#[test]
+fn empty_quotes_are_words() {
+    assert_eq!(
+        Opt {
+            message: "".into(),
+            files: vec![],
+        },
+        Opt::parse_from_str("--message ''")
+    );
+}
+
+#[test]
+fn unterminated_quotes_are_errors() {
+    assert!(Opt::try_parse_from_str(r#"-m "hello"#).is_err());
+    assert!(Opt::try_parse_from_str("-m 'hello").is_err());
+    assert!(Opt::try_parse_from_str(r"-m hello\").is_err());
+}
+
+#[test]
+fn errors_only_give_the_offset() {
+    let e = Opt::try_parse_from_str(r#"-m "hunter2"#).unwrap_err();
+    assert_eq!(e.kind, clap::ErrorKind::InvalidValue);
+    assert!(e.message.contains("unterminated double quote at byte 3"));
+    assert!(!e.message.contains("hunter2"));
+}
+
+#[test]
+fn blanks_separate_words() {
+    assert_eq!(
+        Opt {
+            message: "one".into(),
+            files: vec!["two three".into(), "four".into()],
+        },
+        Opt::parse_from_str(" -m\tone two\\ three \"fo\"ur ")
+    );
+}
@@ -0,0 +1,52 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "greet", version = "1.2.3", parse_output)]
+struct Opt {
+    /// Who to greet
+    #[clap(long)]
+    name: String,
+}
+
+#[test]
+fn parsed() {
+    match Opt::try_parse_or_output_from(&["greet", "--name", "world"]).unwrap() {
+        OptOutput::Parsed(opt) => assert_eq!(
+            Opt {
+                name: "world".into()
+            },
+            opt
+        ),
+        output => panic!("unexpected output: {:?}", output),
+    }
+}
+
+#[test]
+fn help_requested() {
+    match Opt::try_parse_or_output_from(&["greet", "--help"]).unwrap() {
+        OptOutput::HelpRequested(help) => {
+            assert!(help.contains("Who to greet"));
+        }
+        output => panic!("unexpected output: {:?}", output),
+    }
+}
+
+#[test]
+fn version_requested() {
+    match Opt::try_parse_or_output_from(&["greet", "-V"]).unwrap() {
+        OptOutput::VersionRequested(version) => {
+            assert!(version.contains("1.2.3"));
+        }
+        output => panic!("unexpected output: {:?}", output),
+    }
+}
+
+#[test]
+fn version_after_double_dash_is_not_a_request() {
+    assert!(Opt::try_parse_or_output_from(&["greet", "--", "--version"]).is_err());
+}
+
+#[test]
+fn errors_are_kept() {
+    assert!(Opt::try_parse_or_output_from(&["greet"]).is_err());
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "serde")]
+
+use clap::{Clap, FromArgMatches, IntoApp};
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(partial)]
+struct Opt {
+    #[clap(long, default_value = "1")]
+    jobs: u32,
+
+    #[clap(long)]
+    host: Option<String>,
+
+    #[clap(long, env = "PARTIAL_TEST_USER")]
+    user: Option<String>,
+
+    #[clap(short, long)]
+    verbose: bool,
+
+    #[clap(long)]
+    dry_run: bool,
+}
+
+fn parse_merged(args: &[&str], other: OptPartial) -> Opt {
+    let matches = Opt::into_app().get_matches_from(args);
+    let mut opt = Opt::from_argmatches(&matches);
+    opt.merge_from(&matches, other);
+    opt
+}
+
+#[test]
+fn partial_values_fill_missing_arguments() {
+    let other: OptPartial = toml::from_str(
+        r#"
+            jobs = 4
+            host = "example.com"
+            verbose = true
+            dry_run = true
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Opt {
+            jobs: 4,
+            host: Some("example.com".into()),
+            user: None,
+            verbose: true,
+            dry_run: true,
+        },
+        parse_merged(&["test"], other)
+    );
+}
+
+#[test]
+fn command_line_wins_over_partial() {
+    let other = OptPartial {
+        jobs: Some(4),
+        host: Some("example.com".into()),
+        ..OptPartial::default()
+    };
+
+    assert_eq!(
+        Opt {
+            jobs: 8,
+            host: Some("localhost".into()),
+            user: None,
+            verbose: false,
+            dry_run: false,
+        },
+        parse_merged(&["test", "--jobs", "8", "--host", "localhost"], other)
+    );
+}
+
+#[test]
+fn environment_wins_over_partial() {
+    std::env::set_var("PARTIAL_TEST_USER", "from-env");
+    let other = OptPartial {
+        user: Some("from-file".into()),
+        ..OptPartial::default()
+    };
+
+    assert_eq!(Some("from-env".into()), parse_merged(&["test"], other).user);
+    std::env::remove_var("PARTIAL_TEST_USER");
+}
+
+#[test]
+fn kebab_case_keys() {
+    let other: OptPartial = toml::from_str("dry-run = true").unwrap();
+    assert!(parse_merged(&["test"], other).dry_run);
+}
@@ -0,0 +1,50 @@
+use clap::Clap;
+use std::path::{Path, PathBuf};
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long, canonicalize)]
+    config: Option<PathBuf>,
+
+    #[clap(long, expand_tilde)]
+    cache: Option<PathBuf>,
+
+    #[clap(long, expand_tilde, canonicalize)]
+    data: Vec<PathBuf>,
+}
+
+#[test]
+fn canonicalize_resolves_relative_paths() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let relative = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("..")
+        .join("Cargo.toml");
+    let opt = Opt::parse_from(&["test", "--config", relative.to_str().unwrap()]);
+    assert_eq!(Some(manifest.canonicalize().unwrap()), opt.config);
+}
+
+#[test]
+fn canonicalize_rejects_missing_paths() {
+    let err = Opt::try_parse_from(&["test", "--config", "no/such/file.toml"]).unwrap_err();
+    assert!(err.message.contains("invalid path `no/such/file.toml`"));
+}
+
+#[test]
+fn expand_tilde_joins_the_home_directory() {
+    let saved = std::env::var_os("HOME");
+    std::env::set_var("HOME", env!("CARGO_MANIFEST_DIR"));
+    let opt = Opt::parse_from(&["test", "--cache", "~/target", "--data", "~/tests"]);
+    let home = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(Some(home.join("target")), opt.cache);
+    assert_eq!(vec![home.join("tests").canonicalize().unwrap()], opt.data);
+
+    // only a leading `~` is expanded
+    let opt = Opt::parse_from(&["test", "--cache", "a/~/b"]);
+    assert_eq!(Some(PathBuf::from("a/~/b")), opt.cache);
+
+    match saved {
+        Some(home) => std::env::set_var("HOME", home),
+        None => std::env::remove_var("HOME"),
+    }
+}
@@ -0,0 +1,59 @@
+use clap::Clap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(name = "tool")]
+enum Opt {
+    Build,
+
+    #[clap(plugin)]
+    External(PathBuf, Vec<OsString>),
+}
+
+fn write_plugin(dir: &Path, name: &str, executable: bool) -> PathBuf {
+    let plugin = dir.join(format!("tool-{}{}", name, std::env::consts::EXE_SUFFIX));
+    std::fs::write(&plugin, "").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if executable { 0o755 } else { 0o644 };
+        std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    plugin
+}
+
+#[test]
+fn unknown_subcommands_dispatch_to_plugins() {
+    let dir = std::env::temp_dir().join("clap_derive_plugins");
+    std::fs::create_dir_all(&dir).unwrap();
+    let plugin = write_plugin(&dir, "hello", true);
+    let not_executable = write_plugin(&dir, "readme", false);
+    let saved = std::env::var_os("PATH");
+    std::env::set_var("PATH", &dir);
+
+    assert_eq!(Opt::Build, Opt::parse_from(&["tool", "build"]));
+    assert_eq!(
+        Opt::External(plugin.clone(), vec!["--loud".into(), "world".into()]),
+        Opt::parse_from(&["tool", "hello", "--loud", "world"])
+    );
+    assert_eq!(Some(plugin), Opt::__clap_find_plugin("hello"));
+    assert_eq!(None, Opt::__clap_find_plugin("bye"));
+    if cfg!(unix) {
+        assert_eq!(None, Opt::__clap_find_plugin("readme"));
+    } else {
+        assert_eq!(Some(not_executable), Opt::__clap_find_plugin("readme"));
+    }
+
+    // a missing plugin is an error callers can handle
+    let err = Opt::try_parse_from(&["tool", "bye"]).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::UnrecognizedSubcommand);
+    assert!(err.message.contains("no such subcommand: `bye`"));
+
+    match saved {
+        Some(path) => std::env::set_var("PATH", path),
+        None => std::env::remove_var("PATH"),
+    }
+}
@@ -0,0 +1,114 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    /// Output format
+    #[clap(long, possible_values = &["json", "yaml"])]
+    format: String,
+
+    /// Log target
+    #[clap(long, possible_values = &["stdout", "stderr"], hide_possible_values)]
+    target: String,
+}
+
+#[test]
+fn hidden_possible_values_not_in_help() {
+    let help = get_help::<Opt>();
+    assert!(help.contains("[possible values: json, yaml]"));
+    assert!(!help.contains("stdout"));
+}
+
+#[test]
+fn hidden_possible_values_still_checked() {
+    assert_eq!(
+        Opt {
+            format: "json".into(),
+            target: "stderr".into()
+        },
+        Opt::parse_from(&["test", "--format", "json", "--target", "stderr"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--format", "json", "--target", "file"]).is_err());
+}
+
+fn list_profiles() -> Vec<String> {
+    vec!["dev".to_string(), "prod".to_string()]
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(dynamic_completion)]
+struct Dynamic {
+    /// Profile to use
+    #[clap(long, possible_values_fn = list_profiles)]
+    profile: Option<String>,
+
+    #[clap(possible_values_fn = || vec!["eu", "us"])]
+    region: String,
+}
+
+#[test]
+fn possible_values_fn_checked() {
+    assert_eq!(
+        Dynamic {
+            profile: Some("prod".into()),
+            region: "eu".into()
+        },
+        Dynamic::parse_from(&["test", "--profile", "prod", "eu"])
+    );
+    assert!(Dynamic::try_parse_from(&["test", "--profile", "staging", "eu"]).is_err());
+    assert!(Dynamic::try_parse_from(&["test", "asia"]).is_err());
+}
+
+#[test]
+fn possible_values_fn_in_help_and_completion() {
+    assert!(get_help::<Dynamic>().contains("[possible values: dev, prod]"));
+
+    let words = vec!["--profile".to_string(), String::new()];
+    assert_eq!(Dynamic::__clap_complete(&words), vec!["dev", "prod"]);
+}
+
+#[test]
+fn listed_possible_values_and_aliases() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, possible_values("json", "yaml"), visible_aliases("fmt", "output"))]
+        format: String,
+    }
+
+    assert!(get_help::<Opt>().contains("[possible values: json, yaml]"));
+    assert!(get_help::<Opt>().contains("[aliases: fmt, output]"));
+    assert_eq!(
+        Opt {
+            format: "yaml".into()
+        },
+        Opt::parse_from(&["test", "--fmt", "yaml"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--output", "toml"]).is_err());
+}
+
+static LISTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn list_counted() -> Vec<&'static str> {
+    LISTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    vec!["a", "b"]
+}
+
+#[test]
+fn possible_values_fn_called_once() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(dynamic_completion)]
+    struct Opt {
+        #[clap(long, possible_values_fn = list_counted)]
+        letter: String,
+    }
+
+    for _ in 0..3 {
+        assert!(get_help::<Opt>().contains("[possible values: a, b]"));
+        assert!(Opt::try_parse_from(&["test", "--letter", "c"]).is_err());
+        let words = vec!["--letter".to_string(), String::new()];
+        assert_eq!(Opt::__clap_complete(&words), vec!["a", "b"]);
+    }
+    assert_eq!(LISTED.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
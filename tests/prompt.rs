@@ -0,0 +1,288 @@
+#![cfg(feature = "prompt")]
+
+use clap::Clap;
+
+type Prefix = (
+    &'static str,
+    &'static str,
+    Option<(&'static str, bool)>,
+    Option<fn(&str) -> Option<String>>,
+);
+
+const NO_PREFIX: Prefix = ("", "", None, None);
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Opt {
+    /// User name
+    #[clap(long)]
+    user: String,
+
+    /// Password of the user
+    #[clap(long, secret)]
+    password: String,
+
+    #[clap(long, default_value = "localhost")]
+    host: String,
+
+    #[clap(long)]
+    port: Option<u16>,
+
+    #[clap(short)]
+    verbose: bool,
+}
+
+#[test]
+fn required_args_are_prompted() {
+    assert_eq!(
+        Opt::__clap_prompt_args(NO_PREFIX),
+        vec![
+            ("user", "User name", false),
+            ("password", "Password of the user", true)
+        ]
+    );
+}
+
+#[test]
+fn complete_command_lines_are_not_prompted() {
+    assert_eq!(
+        Opt {
+            user: "alice".into(),
+            password: "hunter2".into(),
+            host: "localhost".into(),
+            port: None,
+            verbose: false,
+        },
+        Opt::try_parse_or_prompt_from(&["test", "--user", "alice", "--password", "hunter2"])
+            .unwrap()
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Setup {
+    /// Project name
+    name: String,
+
+    /// Deployment profile
+    #[clap(long, default_value = "dev", possible_values = &["dev", "prod"])]
+    profile: String,
+
+    /// Access token
+    #[clap(long, secret)]
+    token: Option<String>,
+
+    /// Extra tags
+    #[clap(long)]
+    tag: Vec<String>,
+
+    /// Enable tracing
+    #[clap(long)]
+    trace: bool,
+
+    #[clap(long, hidden = true)]
+    debug_dump: bool,
+}
+
+#[test]
+fn wizard_asks_for_every_visible_argument() {
+    let no_values: &[&str] = &[];
+    assert_eq!(
+        Setup::__clap_wizard_args(NO_PREFIX),
+        vec![
+            ("", "Project name", None, "value", true, false, None, no_values),
+            (
+                "--profile",
+                "Deployment profile",
+                None,
+                "value",
+                false,
+                false,
+                Some("dev"),
+                &["dev", "prod"][..]
+            ),
+            ("--token", "Access token", None, "value", false, true, None, no_values),
+            ("--tag", "Extra tags", None, "values", false, false, None, no_values),
+            ("--trace", "Enable tracing", None, "flag", false, false, None, no_values),
+        ]
+    );
+}
+
+#[test]
+fn wizard_parses_the_answers() {
+    let mut answers = vec!["my project", "", "s3cret", "a", "b", "", "y"].into_iter();
+    let mut prompts = Vec::new();
+    let setup = Setup::__clap_wizard_with("test".into(), |prompt, secret| {
+        prompts.push((prompt.to_string(), secret));
+        Ok(answers.next().unwrap().to_string())
+    })
+    .unwrap();
+
+    assert_eq!(
+        Setup {
+            name: "my project".into(),
+            profile: "dev".into(),
+            token: Some("s3cret".into()),
+            tag: vec!["a".into(), "b".into()],
+            trace: true,
+            debug_dump: false,
+        },
+        setup
+    );
+    assert!(prompts.contains(&("Access token: ".to_string(), true)));
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Tool {
+    /// Be verbose
+    #[clap(long)]
+    verbose: bool,
+
+    #[clap(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+enum Command {
+    Fetch {
+        /// Name of the remote
+        remote: String,
+    },
+    Clean,
+}
+
+#[test]
+fn wizard_finds_the_arguments_of_subcommands() {
+    let no_values: &[&str] = &[];
+    assert_eq!(
+        Tool::__clap_wizard_args_at(&["fetch"], NO_PREFIX),
+        vec![("", "Name of the remote", None, "value", true, false, None, no_values)]
+    );
+    assert!(Tool::__clap_wizard_args_at(&["clean"], NO_PREFIX).is_empty());
+}
+
+#[test]
+fn wizard_asks_for_the_subcommand() {
+    let mut answers = vec!["", "push", "fetch", "-origin"].into_iter();
+    let mut prompts = Vec::new();
+    let tool = Tool::__clap_wizard_with("test".into(), |prompt, _| {
+        prompts.push(prompt.to_string());
+        Ok(answers.next().unwrap().to_string())
+    })
+    .unwrap();
+
+    assert_eq!(
+        Tool {
+            verbose: false,
+            cmd: Some(Command::Fetch {
+                remote: "-origin".into()
+            }),
+        },
+        tool
+    );
+    assert_eq!(
+        prompts,
+        vec![
+            "Be verbose? [y/N] ",
+            "Subcommand (fetch/clean): ",
+            "Subcommand (fetch/clean): ",
+            "Name of the remote: ",
+        ]
+    );
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Remote {
+    #[clap(subcommand)]
+    cmd: RemoteCommand,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+enum RemoteCommand {
+    Add {
+        /// Remote name
+        name: String,
+
+        /// Remote URL
+        #[clap(long)]
+        url: String,
+    },
+}
+
+#[test]
+fn required_args_of_subcommands_are_listed() {
+    assert_eq!(
+        Remote::__clap_prompt_args_at(&["add"], NO_PREFIX),
+        vec![("name", "Remote name", false), ("url", "Remote URL", false)]
+    );
+}
+
+#[test]
+fn required_args_of_subcommands_are_prompted() {
+    let mut prompts = Vec::new();
+    let remote = Remote::__clap_parse_or_prompt_with(
+        vec!["test".into(), "add".into(), "origin".into()],
+        true,
+        |prompt, _| {
+            prompts.push(prompt.to_string());
+            Ok("https://example.com".to_string())
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        Remote {
+            cmd: RemoteCommand::Add {
+                name: "origin".into(),
+                url: "https://example.com".into(),
+            },
+        },
+        remote
+    );
+    assert_eq!(prompts, vec!["Remote URL: "]);
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Mirror {
+    #[clap(flatten, prefix = "remote", rename_all = "snake")]
+    remote: Endpoint,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(prompt)]
+struct Endpoint {
+    /// Push URL
+    #[clap(long)]
+    push_url: String,
+
+    /// Dry run
+    #[clap(short)]
+    dry: bool,
+}
+
+#[test]
+fn flattened_args_are_prefixed() {
+    let no_values: &[&str] = &[];
+    assert_eq!(
+        Mirror::__clap_prompt_args(NO_PREFIX),
+        vec![("remote-push-url", "Push URL", false)]
+    );
+    assert_eq!(
+        Mirror::__clap_wizard_args(NO_PREFIX),
+        vec![(
+            "--remote-push_url",
+            "Push URL",
+            None,
+            "value",
+            true,
+            false,
+            None,
+            no_values
+        )]
+    );
+}
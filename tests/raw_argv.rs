@@ -0,0 +1,39 @@
+use clap::Clap;
+use std::ffi::OsString;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long)]
+    name: String,
+
+    #[clap(flatten)]
+    log: Log,
+
+    #[clap(raw_argv)]
+    argv: Vec<OsString>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Log {
+    #[clap(short)]
+    verbose: bool,
+
+    #[clap(raw_argv)]
+    argv: Vec<OsString>,
+}
+
+#[test]
+fn raw_argv_is_the_whole_command_line() {
+    let argv: Vec<OsString> = vec!["tool".into(), "--name=x".into(), "-v".into()];
+    assert_eq!(
+        Opt {
+            name: "x".into(),
+            log: Log {
+                verbose: true,
+                argv: argv.clone(),
+            },
+            argv: argv.clone(),
+        },
+        Opt::parse_from(argv)
+    );
+}
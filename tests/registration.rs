@@ -0,0 +1,54 @@
+#![cfg(feature = "inventory")]
+
+use clap::{Clap, FromArgMatches};
+
+trait Command: std::fmt::Debug {
+    fn run(&self) -> String;
+}
+
+#[derive(Clap, Debug)]
+#[clap(name = "host")]
+enum Opt {
+    Build,
+
+    #[clap(registered)]
+    Registered(Box<dyn Command>),
+}
+
+#[derive(Clap, Debug)]
+struct Hello {
+    #[clap(long)]
+    name: String,
+}
+
+impl Command for Hello {
+    fn run(&self) -> String {
+        format!("hello {}", self.name)
+    }
+}
+
+inventory::submit! {
+    OptRegistration {
+        name: "hello",
+        augment_app: Hello::augment_app,
+        from_argmatches: |matches| Box::new(Hello::from_argmatches(matches)),
+    }
+}
+
+#[test]
+fn registered_subcommands_are_parsed() {
+    match Opt::parse_from(&["host", "hello", "--name", "world"]) {
+        Opt::Registered(command) => assert_eq!(command.run(), "hello world"),
+        opt => panic!("unexpected {:?}", opt),
+    }
+
+    match Opt::parse_from(&["host", "build"]) {
+        Opt::Build => (),
+        opt => panic!("unexpected {:?}", opt),
+    }
+}
+
+#[test]
+fn unregistered_subcommands_are_rejected() {
+    assert!(Opt::try_parse_from(&["host", "bye"]).is_err());
+}
@@ -0,0 +1,165 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+fn parse_token(s: &str) -> Result<String, String> {
+    if s.starts_with("tk-") {
+        Ok(s.to_string())
+    } else {
+        Err(format!("`{}` is not a token", s))
+    }
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    /// API token
+    #[clap(long, secret, parse(try_from_str = parse_token), default_value = "tk-default")]
+    token: String,
+
+    /// Password
+    #[clap(long, secret)]
+    password: Option<String>,
+}
+
+#[test]
+fn secret_value_parsed() {
+    assert_eq!(
+        Opt {
+            token: "tk-1234".to_string(),
+            password: None,
+        },
+        Opt::parse_from(&["test", "--token", "tk-1234"])
+    );
+}
+
+#[test]
+fn secret_value_not_in_error() {
+    let err = Opt::try_parse_from(&["test", "--token", "hunter2"]).unwrap_err();
+    assert!(!err.message.contains("hunter2"));
+    assert!(err.message.contains("the argument is secret"));
+}
+
+#[test]
+fn secret_default_and_env_not_in_help() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, secret, env = "SECRET_TEST_HELP_PASSWORD", default_value = "tk-default")]
+        password: String,
+    }
+
+    std::env::set_var("SECRET_TEST_HELP_PASSWORD", "hunter2");
+    let help = get_long_help::<Opt>();
+    std::env::remove_var("SECRET_TEST_HELP_PASSWORD");
+    assert!(!help.contains("tk-default"));
+    assert!(!help.contains("hunter2"));
+}
+
+#[test]
+fn secret_value_not_in_clap_errors() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, short, secret, possible_values = &["red", "blue"])]
+        color: String,
+    }
+
+    for args in &[
+        &["test", "--color", "hunter2"][..],
+        &["test", "--color=hunter2"][..],
+        &["test", "-c", "hunter2"][..],
+        &["test", "-chunter2"][..],
+    ] {
+        let err = Opt::try_parse_from(*args).unwrap_err();
+        assert!(!err.message.contains("hunter2"), "{}", err.message);
+        assert!(err.message.contains("<secret>"), "{}", err.message);
+    }
+}
+
+#[test]
+fn every_secret_value_not_in_clap_errors() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(short, long)]
+        verbose: bool,
+
+        #[clap(short, long, secret, possible_values = &["red", "blue"])]
+        colors: Vec<String>,
+    }
+
+    for args in &[
+        &["test", "--colors", "red", "hunter2"][..],
+        &["test", "--colors=red", "hunter2"][..],
+        &["test", "-c", "blue", "hunter2", "-v"][..],
+        &["test", "-vc", "hunter2"][..],
+        &["test", "-vchunter2"][..],
+    ] {
+        let err = Opt::try_parse_from(*args).unwrap_err();
+        assert!(!err.message.contains("hunter2"), "{}", err.message);
+        assert!(err.message.contains("<secret>"), "{}", err.message);
+    }
+}
+
+#[test]
+fn secret_env_value_not_in_errors() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, secret, env = "SECRET_TEST_ENV_PIN")]
+        pin: u32,
+    }
+
+    std::env::set_var("SECRET_TEST_ENV_PIN", "hunter2");
+    let err = Opt::try_parse_from(&["test"]).unwrap_err();
+    std::env::remove_var("SECRET_TEST_ENV_PIN");
+    assert!(!err.message.contains("hunter2"), "{}", err.message);
+    assert!(err.message.contains("SECRET_TEST_ENV_PIN"), "{}", err.message);
+}
+
+#[test]
+fn secret_os_str_value_not_in_error() {
+    fn parse_key(s: &std::ffi::OsStr) -> Result<String, std::ffi::OsString> {
+        match s.to_str() {
+            Some(s) if s.starts_with("key-") => Ok(s.to_string()),
+            _ => Err(format!("`{}` is not a key", s.to_string_lossy()).into()),
+        }
+    }
+
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, secret, parse(try_from_os_str = parse_key))]
+        key: String,
+    }
+
+    let err = Opt::try_parse_from(&["test", "--key", "hunter2"]).unwrap_err();
+    assert!(!err.message.contains("hunter2"), "{}", err.message);
+}
+
+#[test]
+fn only_quoted_secret_values_are_redacted() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, secret)]
+        pass: Option<String>,
+
+        #[clap(long, possible_values = &["low", "high"])]
+        passes: String,
+    }
+
+    let err = Opt::try_parse_from(&["test", "--pass", "low", "--passes", "lowest"]).unwrap_err();
+    assert!(err.message.contains("'lowest'"), "{}", err.message);
+
+    let err = Opt::try_parse_from(&["test", "--pass=low", "--passes", "low"]);
+    assert!(err.is_ok());
+}
+
+#[test]
+fn help_is_not_redacted() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        /// Color of the output
+        #[clap(long, secret)]
+        color: String,
+    }
+
+    let err = Opt::try_parse_from(&["test", "--color", "output", "--help"]).unwrap_err();
+    assert!(err.message.contains("Color of the output"), "{}", err.message);
+}
@@ -0,0 +1,39 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Network {
+    /// Port to listen on
+    #[clap(long)]
+    port: Option<u16>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(source_order)]
+struct Opt {
+    /// Verbose output
+    #[clap(long)]
+    verbose: bool,
+
+    /// Address to bind
+    #[clap(long)]
+    address: Option<String>,
+
+    #[clap(flatten)]
+    network: Network,
+
+    /// Config file
+    #[clap(long)]
+    config: Option<String>,
+}
+
+#[test]
+fn help_lists_args_in_source_order() {
+    let help = get_help::<Opt>();
+    let position = |flag: &str| help.find(flag).unwrap();
+    assert!(position("--verbose") < position("--address"));
+    assert!(position("--address") < position("--port"));
+    assert!(position("--port") < position("--config"));
+}
@@ -0,0 +1,95 @@
+use clap::{App, AppSettings, Arg, ArgMatches, Clap, IntoApp};
+use std::convert::TryFrom;
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(short, parse(from_occurrences), max_occurrences = 2)]
+    verbose: u8,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Jobs {
+    #[clap(long)]
+    jobs: u8,
+    #[clap(long, value_names = &["LOW", "HIGH"])]
+    range: Option<(u8, u8)>,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+enum Command {
+    Start,
+    Stop,
+}
+
+fn hydrate<T>(matches: &ArgMatches) -> Result<T, clap::Error>
+where
+    T: for<'a> TryFrom<&'a ArgMatches, Error = clap::Error>,
+{
+    T::try_from(matches)
+}
+
+#[test]
+fn try_from_matches() {
+    let matches = Opt::into_app().get_matches_from(&["test", "-vv"]);
+    assert_eq!(Opt { verbose: 2 }, hydrate::<Opt>(&matches).unwrap());
+}
+
+#[test]
+fn try_from_fails_on_checks() {
+    let matches = Opt::into_app().get_matches_from(&["test", "-vvv"]);
+    let err = Opt::try_from(&matches).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::UnexpectedMultipleUsage);
+}
+
+#[test]
+fn try_from_subcommand() {
+    let matches = Command::into_app().get_matches_from(&["test", "stop"]);
+    assert_eq!(Command::Stop, Command::try_from(&matches).unwrap());
+
+    // the derived `App` would print the help and exit instead
+    let matches = Command::into_app()
+        .unset_setting(AppSettings::SubcommandRequiredElseHelp)
+        .try_get_matches_from(&["test"])
+        .unwrap();
+    let err = Command::try_from(&matches).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::MissingSubcommand);
+}
+
+// matches of an `App` other than the derived one skip its validators
+fn foreign_matches(args: &[&str]) -> ArgMatches {
+    App::new("test")
+        .arg(Arg::with_name("jobs").long("jobs").takes_value(true))
+        .arg(Arg::with_name("range").long("range").takes_value(true))
+        .get_matches_from(args)
+}
+
+#[test]
+fn try_from_fails_on_foreign_matches() {
+    let jobs = Jobs::try_from(&foreign_matches(&["test", "--jobs", "4"])).unwrap();
+    assert_eq!(Jobs { jobs: 4, range: None }, jobs);
+
+    let err = Jobs::try_from(&foreign_matches(&["test", "--jobs", "many"])).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::ValueValidation);
+    assert!(err.message.contains("'jobs'"));
+
+    let err = Jobs::try_from(&foreign_matches(&["test"])).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::MissingRequiredArgument);
+
+    let err = Jobs::try_from(&foreign_matches(&["test", "--jobs", "4", "--range", "1"])).unwrap_err();
+    assert_eq!(err.kind, clap::ErrorKind::WrongNumberOfValues);
+}
+
+#[test]
+fn try_from_errors_are_redacted() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Login {
+        #[clap(long, secret)]
+        pin: u32,
+    }
+
+    let matches = App::new("test")
+        .arg(Arg::with_name("pin").long("pin").takes_value(true))
+        .get_matches_from(&["test", "--pin", "hunter2"]);
+    let err = Login::try_from(&matches).unwrap_err();
+    assert!(!err.message.contains("hunter2"), "{}", err.message);
+}
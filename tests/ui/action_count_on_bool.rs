@@ -0,0 +1,12 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(short, action = Count)]
+    verbose: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
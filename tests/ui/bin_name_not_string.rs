@@ -0,0 +1,12 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(bin_name)]
+    program: u32,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,13 @@
+use clap::Clap;
+use std::path::PathBuf;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long, canonicalize, parse(from_os_str))]
+    config: PathBuf,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,13 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic", color = "sometimes")]
+struct Opt {
+    #[clap(short)]
+    verbose: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,16 @@
+use clap::Clap;
+
+fn list() -> Vec<String> {
+    Vec::new()
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long, complete_with = list)]
+    verbose: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
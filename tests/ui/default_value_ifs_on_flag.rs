@@ -0,0 +1,15 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long)]
+    mode: Option<String>,
+
+    #[clap(long, default_value_ifs = [("mode", "fast", "true")])]
+    quick: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,14 @@
+use clap::{AppSettings, Clap};
+
+#[derive(Clap, Debug)]
+#[clap(setting = AppSettings::AllowNegativeNumbers)]
+struct Opt {
+    #[clap(short = "1")]
+    one_per_line: bool,
+    offset: i32,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
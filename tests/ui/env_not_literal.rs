@@ -0,0 +1,15 @@
+use clap::Clap;
+
+const LEGACY: &str = "LEGACY_OUTPUT";
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+struct Opt {
+    #[clap(long, env = ["OUTPUT", LEGACY])]
+    output: String,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,19 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Output {
+    #[clap(long)]
+    output: String,
+}
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+struct Opt {
+    #[clap(flatten, example = "basic --output out.txt")]
+    output: Output,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,17 @@
+use clap::Clap;
+
+#[derive(Debug)]
+struct Common {
+    verbose: bool,
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(flatten)]
+    common: Common,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,20 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Remote {
+    #[clap(long)]
+    host: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(flatten, conflicts_with = "locale")]
+    remote: Remote,
+    #[clap(long)]
+    local: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
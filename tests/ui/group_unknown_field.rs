@@ -0,0 +1,13 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(group(name = "input", args = ["file", "url"]))]
+struct Opt {
+    #[clap(long)]
+    file: Option<String>,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,13 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(hide_possible_values)]
+struct Opt {
+    #[clap(long)]
+    format: String,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
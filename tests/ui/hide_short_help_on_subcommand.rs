@@ -0,0 +1,17 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(subcommand, hide_short_help)]
+    cmd: Command,
+}
+
+#[derive(Clap, Debug)]
+enum Command {
+    Run,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
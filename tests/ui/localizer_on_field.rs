@@ -0,0 +1,17 @@
+use clap::Clap;
+
+fn lookup(_: &str) -> Option<String> {
+    None
+}
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+struct Opt {
+    #[clap(long, localizer = lookup)]
+    output: String,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,12 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(short, max_occurrences = 2)]
+    level: u8,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
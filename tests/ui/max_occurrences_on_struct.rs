@@ -0,0 +1,13 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(max_occurrences = 2)]
+struct Opt {
+    #[clap(short, parse(from_occurrences))]
+    verbose: u8,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
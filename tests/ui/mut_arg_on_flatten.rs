@@ -0,0 +1,22 @@
+use clap::{Arg, Clap};
+
+fn tweak(arg: Arg) -> Arg {
+    arg
+}
+
+#[derive(Clap, Debug)]
+struct Common {
+    #[clap(long)]
+    verbose: bool,
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(flatten, mut_arg = tweak)]
+    common: Common,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,12 @@
+use clap::{Arg, Clap};
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(short, mut_quiet = |arg: Arg| arg.short('s'))]
+    level: u8,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
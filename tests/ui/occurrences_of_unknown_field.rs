@@ -0,0 +1,15 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long)]
+    file: Vec<String>,
+
+    #[clap(occurrences_of = "files")]
+    file_count: usize,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,15 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+enum Opt {
+    Build,
+
+    #[clap(plugin)]
+    External(Vec<String>),
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
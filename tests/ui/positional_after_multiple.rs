@@ -0,0 +1,14 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(name = "basic")]
+struct Opt {
+    #[clap(min_values = 1)]
+    sources: Option<String>,
+    targets: Vec<String>,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
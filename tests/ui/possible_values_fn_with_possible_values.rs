@@ -0,0 +1,16 @@
+use clap::Clap;
+
+fn list_formats() -> Vec<String> {
+    vec!["json".to_string()]
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long, possible_values = &["json"], possible_values_fn = list_formats)]
+    format: String,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
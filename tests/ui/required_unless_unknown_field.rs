@@ -0,0 +1,15 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(long, required_unless_any = ["stdin", "url"])]
+    file: Option<String>,
+
+    #[clap(long)]
+    stdin: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
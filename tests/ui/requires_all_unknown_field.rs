@@ -0,0 +1,15 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(requires_all = ["user", "pasword"])]
+struct Opt {
+    #[clap(long)]
+    user: Option<String>,
+    #[clap(long)]
+    password: Option<String>,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
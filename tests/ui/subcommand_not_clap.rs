@@ -0,0 +1,17 @@
+use clap::Clap;
+
+#[derive(Debug)]
+enum Command {
+    Fetch,
+}
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
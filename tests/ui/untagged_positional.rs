@@ -0,0 +1,16 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+#[clap(untagged)]
+enum Source {
+    Path { path: String },
+    Url {
+        #[clap(long)]
+        url: String,
+    },
+}
+
+fn main() {
+    let source = Source::parse();
+    println!("{:?}", source);
+}
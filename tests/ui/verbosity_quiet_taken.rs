@@ -0,0 +1,14 @@
+use clap::Clap;
+
+#[derive(Clap, Debug)]
+struct Opt {
+    #[clap(verbosity)]
+    level: i8,
+    #[clap(short, long)]
+    quiet: bool,
+}
+
+fn main() {
+    let opt = Opt::parse();
+    println!("{:?}", opt);
+}
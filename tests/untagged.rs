@@ -0,0 +1,109 @@
+use clap::Clap;
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(untagged)]
+enum Auth {
+    Token {
+        #[clap(long)]
+        token: String,
+    },
+    Basic {
+        #[clap(long)]
+        user: String,
+        #[clap(long)]
+        pass: String,
+    },
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Opt {
+    #[clap(long)]
+    url: String,
+
+    #[clap(flatten)]
+    auth: Auth,
+}
+
+#[test]
+fn variant_given() {
+    assert_eq!(
+        Opt {
+            url: "https://example.com".into(),
+            auth: Auth::Token { token: "abc".into() },
+        },
+        Opt::parse_from(&["test", "--url", "https://example.com", "--token", "abc"])
+    );
+    assert_eq!(
+        Opt {
+            url: "https://example.com".into(),
+            auth: Auth::Basic {
+                user: "me".into(),
+                pass: "secret".into()
+            },
+        },
+        Opt::parse_from(&[
+            "test",
+            "--url",
+            "https://example.com",
+            "--user",
+            "me",
+            "--pass",
+            "secret"
+        ])
+    );
+}
+
+#[test]
+fn variants_conflict() {
+    assert!(
+        Opt::try_parse_from(&["test", "--url", "u", "--token", "abc", "--user", "me"]).is_err()
+    );
+}
+
+#[test]
+fn variant_incomplete() {
+    assert!(Opt::try_parse_from(&["test", "--url", "u", "--user", "me"]).is_err());
+}
+
+#[test]
+fn one_variant_required() {
+    assert!(Opt::try_parse_from(&["test", "--url", "u"]).is_err());
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(untagged)]
+enum Output {
+    Stdout,
+    File {
+        #[clap(long)]
+        output: String,
+        #[clap(long)]
+        append: bool,
+    },
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Report {
+    #[clap(flatten)]
+    output: Output,
+}
+
+#[test]
+fn unit_variant_when_none_given() {
+    assert_eq!(
+        Report {
+            output: Output::Stdout
+        },
+        Report::parse_from(&["test"])
+    );
+    assert_eq!(
+        Report {
+            output: Output::File {
+                output: "out.txt".into(),
+                append: true
+            }
+        },
+        Report::parse_from(&["test", "--output", "out.txt", "--append"])
+    );
+    assert!(Report::try_parse_from(&["test", "--append"]).is_err());
+}
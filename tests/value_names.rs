@@ -0,0 +1,111 @@
+mod utils;
+
+use clap::Clap;
+use utils::*;
+
+#[test]
+fn value_names_follow_rename_all_value() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(rename_all_value = "screaming_snake")]
+    struct Opt {
+        #[clap(long)]
+        output_file: String,
+        #[clap(long)]
+        log_level: Option<String>,
+        #[clap(long)]
+        verbose: bool,
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("--output-file <OUTPUT_FILE>"));
+    assert!(help.contains("--log-level <LOG_LEVEL>"));
+    assert!(help.contains("--verbose"));
+}
+
+#[test]
+fn explicit_value_name_wins_over_rename_all_value() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(rename_all_value = "screaming_snake")]
+    struct Opt {
+        #[clap(long, value_name = "PATH")]
+        output_file: String,
+        #[clap(long, rename_all_value = "kebab")]
+        input_file: String,
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("--output-file <PATH>"));
+    assert!(help.contains("--input-file <input-file>"));
+}
+
+#[test]
+fn rename_all_value_reaches_variant_fields() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(rename_all_value = "screaming_snake")]
+    enum Opt {
+        Run {
+            #[clap(long)]
+            work_dir: String,
+        },
+    }
+
+    let help = Opt::try_parse_from(&["test", "run", "--help"])
+        .unwrap_err()
+        .message;
+    assert!(help.contains("--work-dir <WORK_DIR>"));
+}
+
+#[test]
+fn value_names_name_each_value() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, value_names = ["WIDTH", "HEIGHT"])]
+        size: Vec<u32>,
+        #[clap(long, value_names = ["FILE"])]
+        output: Option<String>,
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("--size <WIDTH> <HEIGHT>"));
+    assert!(help.contains("--output <FILE>"));
+
+    assert_eq!(
+        Opt {
+            size: vec![80, 24],
+            output: None
+        },
+        Opt::parse_from(&["test", "--size", "80", "24"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--size", "80"]).is_err());
+}
+
+#[test]
+fn value_names_of_tuples_and_arrays() {
+    #[derive(Clap, PartialEq, Debug)]
+    struct Opt {
+        #[clap(long, value_names = &["WIDTH", "HEIGHT"])]
+        size: Option<(u32, u32)>,
+        #[clap(long, value_names = ["R", "G", "B"])]
+        color: [u8; 3],
+    }
+
+    let help = get_help::<Opt>();
+    assert!(help.contains("--size <WIDTH> <HEIGHT>"));
+    assert!(help.contains("--color <R> <G> <B>"));
+
+    assert_eq!(
+        Opt {
+            size: Some((80, 24)),
+            color: [255, 0, 128]
+        },
+        Opt::parse_from(&["test", "--size", "80", "24", "--color", "255", "0", "128"])
+    );
+    assert_eq!(
+        Opt {
+            size: None,
+            color: [0, 0, 0]
+        },
+        Opt::parse_from(&["test", "--color", "0", "0", "0"])
+    );
+    assert!(Opt::try_parse_from(&["test", "--color", "0", "0", "256"]).is_err());
+}
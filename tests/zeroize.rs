@@ -0,0 +1,102 @@
+#![cfg(feature = "zeroize")]
+
+use clap::Clap;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const SECRET: &str = "correct horse battery staple";
+
+/// Counts the buffers freed while still holding `SECRET`.
+struct Leaks;
+
+static LEAKED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Leaks {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let freed = std::slice::from_raw_parts(ptr, layout.size());
+        if freed
+            .windows(SECRET.len())
+            .any(|window| window == SECRET.as_bytes())
+        {
+            LEAKED.fetch_add(1, Ordering::SeqCst);
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Leaks = Leaks;
+
+fn leaked<T>(parse: impl FnOnce() -> T) -> (T, usize) {
+    let before = LEAKED.load(Ordering::SeqCst);
+    let parsed = parse();
+    (parsed, LEAKED.load(Ordering::SeqCst) - before)
+}
+
+#[derive(Clap, PartialEq, Debug)]
+#[clap(zeroize)]
+struct Opt {
+    #[clap(long)]
+    user: String,
+
+    #[clap(long, secret)]
+    passphrase: String,
+}
+
+#[derive(Clap, PartialEq, Debug)]
+struct Plain {
+    #[clap(long)]
+    user: String,
+
+    #[clap(long, secret)]
+    passphrase: String,
+}
+
+// the only test using `SECRET`, the others running alongside it
+#[test]
+fn copies_of_the_command_line_are_wiped() {
+    let args = ["test", "--user", "alice", "--passphrase", SECRET];
+    let (plain, leaked_by_plain) = leaked(|| Plain::try_parse_from(&args).unwrap());
+    let (opt, leaked_by_opt) = leaked(|| Opt::try_parse_from(&args).unwrap());
+    assert_eq!(plain.passphrase, SECRET);
+    assert_eq!(opt.passphrase, SECRET);
+    assert!(
+        leaked_by_opt < leaked_by_plain,
+        "{} copies left by `zeroize`, {} without",
+        leaked_by_opt,
+        leaked_by_plain
+    );
+
+    let args = ["test", "--passphrase", SECRET, "--unknown"];
+    let (_, leaked_by_plain) = leaked(|| Plain::try_parse_from(&args).unwrap_err());
+    let (err, leaked_by_opt) = leaked(|| Opt::try_parse_from(&args).unwrap_err());
+    assert!(!err.message.contains(SECRET));
+    assert!(
+        leaked_by_opt < leaked_by_plain,
+        "{} copies left by `zeroize` on error, {} without",
+        leaked_by_opt,
+        leaked_by_plain
+    );
+}
+
+#[cfg(feature = "prompt")]
+#[test]
+fn prompted_types_parse_the_command_line() {
+    #[derive(Clap, PartialEq, Debug)]
+    #[clap(prompt, zeroize)]
+    struct Prompted {
+        #[clap(long, secret)]
+        passphrase: String,
+    }
+
+    assert_eq!(
+        Prompted {
+            passphrase: "hunter2".into(),
+        },
+        Prompted::try_parse_or_prompt_from(&["test", "--passphrase", "hunter2"]).unwrap()
+    );
+}